@@ -0,0 +1,250 @@
+use crate::vec3d::Vec3d;
+
+/// An Aabb is an axis-aligned bounding box given by its smallest
+/// and largest corner. An empty box has its smallest corner at
+/// infinity so that growing it by a point makes it that point.
+#[derive(Copy, Clone)]
+pub struct Aabb {
+    pub min: Vec3d,
+    pub max: Vec3d,
+}
+
+impl Aabb {
+    /// Creates an empty Aabb.
+    ///
+    /// # Return
+    /// A new Aabb containing nothing
+    ///
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Vec3d::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vec3d::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    /// Creates the smallest Aabb containing some points.
+    ///
+    /// # Arguments
+    /// * `points` - The points.
+    ///
+    /// # Return
+    /// A new Aabb
+    ///
+    pub fn from_points<'a, I>(points: I) -> Aabb
+    where
+        I: IntoIterator<Item = &'a Vec3d>,
+    {
+        let mut aabb = Aabb::empty();
+        for point in points {
+            aabb.grow(*point);
+        }
+        aabb
+    }
+
+    /// Checks if the Aabb contains nothing.
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    ///
+    /// # Return
+    /// If the Aabb is empty
+    ///
+    pub fn is_empty(&self) -> bool {
+        !(self.min.x <= self.max.x && self.min.y <= self.max.y && self.min.z <= self.max.z)
+    }
+
+    /// Grows the Aabb so it contains a point.
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    /// * `point` - The point.
+    ///
+    pub fn grow(&mut self, point: Vec3d) {
+        self.min = Vec3d::new(
+            self.min.x.min(point.x),
+            self.min.y.min(point.y),
+            self.min.z.min(point.z),
+        );
+        self.max = Vec3d::new(
+            self.max.x.max(point.x),
+            self.max.y.max(point.y),
+            self.max.z.max(point.z),
+        );
+    }
+
+    /// Creates the smallest Aabb containing two Aabb's.
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    /// * `other` - The other Aabb.
+    ///
+    /// # Return
+    /// The merged Aabb
+    ///
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        if other.is_empty() {
+            return *self;
+        }
+        let mut merged = *self;
+        merged.grow(other.min);
+        merged.grow(other.max);
+        merged
+    }
+
+    /// The point in the middle of the Aabb.
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    ///
+    /// # Return
+    /// The center
+    ///
+    pub fn center(&self) -> Vec3d {
+        (self.min + self.max) * 0.5
+    }
+
+    /// The surface area of the Aabb.
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    ///
+    /// # Return
+    /// The surface area, 0 if the Aabb is empty
+    ///
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let size = self.max - self.min;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    /// The eight corners of the Aabb.
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    ///
+    /// # Return
+    /// The corners
+    ///
+    pub fn corners(&self) -> [Vec3d; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3d::new(a.x, a.y, a.z),
+            Vec3d::new(b.x, a.y, a.z),
+            Vec3d::new(a.x, b.y, a.z),
+            Vec3d::new(b.x, b.y, a.z),
+            Vec3d::new(a.x, a.y, b.z),
+            Vec3d::new(b.x, a.y, b.z),
+            Vec3d::new(a.x, b.y, b.z),
+            Vec3d::new(b.x, b.y, b.z),
+        ]
+    }
+
+    /// Checks if two Aabb's overlap.
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    /// * `other` - The other Aabb.
+    ///
+    /// # Return
+    /// If the Aabb's share any point
+    ///
+    #[allow(dead_code)]
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+            && self.min.z <= other.max.z
+            && other.min.z <= self.max.z
+    }
+
+    /// The squared distance from a point to the closest point in the Aabb.
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    /// * `point` - The point.
+    ///
+    /// # Return
+    /// The squared distance, 0 if the point is inside
+    ///
+    #[allow(dead_code)]
+    pub fn distance_squared(&self, point: Vec3d) -> f32 {
+        let dx = f32::max(0.0, f32::max(self.min.x - point.x, point.x - self.max.x));
+        let dy = f32::max(0.0, f32::max(self.min.y - point.y, point.y - self.max.y));
+        let dz = f32::max(0.0, f32::max(self.min.z - point.z, point.z - self.max.z));
+        dx * dx + dy * dy + dz * dz
+    }
+
+    /// Finds where a ray enters the Aabb (slab test).
+    ///
+    /// # Arguments
+    /// * `self` - The Aabb the function was called for.
+    /// * `origin` - The start of the ray.
+    /// * `inverse_direction` - 1 / each component of the direction of the ray.
+    ///
+    /// # Return
+    /// How far along the ray it enters, 0 if it starts inside, or None if it misses
+    ///
+    pub fn intersect_ray(&self, origin: Vec3d, inverse_direction: Vec3d) -> Option<f32> {
+        let mut near: f32 = 0.0;
+        let mut far = f32::INFINITY;
+        let slabs = [
+            (self.min.x, self.max.x, origin.x, inverse_direction.x),
+            (self.min.y, self.max.y, origin.y, inverse_direction.y),
+            (self.min.z, self.max.z, origin.z, inverse_direction.z),
+        ];
+        for (min, max, start, inverse) in slabs.iter() {
+            let t0 = (min - start) * inverse;
+            let t1 = (max - start) * inverse;
+            // a ray parallel to the slab gives NaN and is decided by the other slabs
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+        if near <= far {
+            Some(near)
+        } else {
+            None
+        }
+    }
+}
+
+/// A BoundingSphere is a sphere containing a set of points.
+#[derive(Copy, Clone)]
+pub struct BoundingSphere {
+    pub center: Vec3d,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Creates a sphere around some points centered on their Aabb.
+    ///
+    /// # Arguments
+    /// * `points` - The points.
+    /// * `aabb` - The Aabb of the points.
+    ///
+    /// # Return
+    /// A new BoundingSphere, with a negative radius if there are no points
+    ///
+    pub fn from_points<'a, I>(points: I, aabb: &Aabb) -> BoundingSphere
+    where
+        I: IntoIterator<Item = &'a Vec3d>,
+    {
+        if aabb.is_empty() {
+            return BoundingSphere {
+                center: Vec3d::new(0.0, 0.0, 0.0),
+                radius: -1.0,
+            };
+        }
+        let center = aabb.center();
+        let mut radius: f32 = 0.0;
+        for point in points {
+            radius = radius.max((*point - center).length());
+        }
+        BoundingSphere {
+            center: center,
+            radius: radius,
+        }
+    }
+}
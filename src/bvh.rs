@@ -0,0 +1,465 @@
+use crate::{bounds::Aabb, camera::Camera, transform::Transform, triangle::Triangle, vec3d::Vec3d};
+
+/// A node of a Bvh. Leaves hold a range of the triangle indices of
+/// the Bvh, other nodes have no Triangles and two children at `left`
+/// and `left + 1`.
+#[derive(Copy, Clone)]
+pub struct BvhNode {
+    pub aabb: Aabb,
+    pub left: usize,
+    pub start: usize,
+    pub count: usize,
+}
+
+/// A Bvh is a bounding volume hierarchy over the Triangles of a Mesh.
+/// Every node has an Aabb containing the Triangles below it, so whole
+/// groups of Triangles can be skipped by culling, picking and collision
+/// queries. It is built top down by splitting along the surface area
+/// heuristic and refit when the Triangles move.
+///
+/// The queries take the Triangles the Bvh was built over and return
+/// their indices.
+#[derive(Clone)]
+pub struct Bvh {
+    pub nodes: Vec<BvhNode>,
+    pub indices: Vec<usize>,
+}
+
+impl Bvh {
+    /// The most Triangles kept in a leaf.
+    pub const LEAF_SIZE: usize = 4;
+
+    /// How many buckets the centers are sorted into to find a split.
+    pub const BINS: usize = 12;
+
+    /// Builds a Bvh over Triangles.
+    ///
+    /// # Arguments
+    /// * `tris` - The Triangles.
+    ///
+    /// # Return
+    /// A new Bvh
+    ///
+    pub fn build(tris: &Vec<Triangle>) -> Bvh {
+        let boxes: Vec<Aabb> = tris.iter().map(Bvh::triangle_aabb).collect();
+        let centers: Vec<Vec3d> = boxes.iter().map(|aabb| aabb.center()).collect();
+        let mut bvh = Bvh {
+            nodes: vec![BvhNode {
+                aabb: Aabb::empty(),
+                left: 0,
+                start: 0,
+                count: tris.len(),
+            }],
+            indices: (0..tris.len()).collect(),
+        };
+        bvh.subdivide(0, &boxes, &centers);
+        bvh
+    }
+
+    /// Recalculates the Aabb's of the nodes after the Triangles moved.
+    /// The tree is kept as it is, so it gets slower to search the more
+    /// the Triangles move relative to each other.
+    ///
+    /// # Arguments
+    /// * `self` - The Bvh the function was called for.
+    /// * `tris` - The moved Triangles.
+    ///
+    pub fn refit(&mut self, tris: &Vec<Triangle>) {
+        // children always come after their parent
+        for i in (0..self.nodes.len()).rev() {
+            let node = self.nodes[i];
+            let mut aabb = Aabb::empty();
+            if node.count > 0 {
+                for index in self.indices[node.start..node.start + node.count].iter() {
+                    aabb = aabb.merge(&Bvh::triangle_aabb(&tris[*index]));
+                }
+            } else {
+                aabb = self.nodes[node.left]
+                    .aabb
+                    .merge(&self.nodes[node.left + 1].aabb);
+            }
+            self.nodes[i].aabb = aabb;
+        }
+    }
+
+    /// Finds the Triangles that may be seen by a Camera.
+    ///
+    /// # Arguments
+    /// * `self` - The Bvh the function was called for.
+    /// * `camera` - The Camera.
+    /// * `size` - The dimensions of the screen.
+    /// * `transform` - The Transform of the Mesh the Bvh was built over.
+    ///
+    /// # Return
+    /// The indices of the Triangles in leaves that overlap the frustum
+    ///
+    pub fn query_frustum(
+        &self,
+        camera: &Camera,
+        size: (f32, f32),
+        transform: &Transform,
+    ) -> Vec<usize> {
+        let r = camera.rotation_matrix();
+        let planes = camera.frustum_planes(size);
+        if transform.is_identity() {
+            return self.collect(|aabb| camera.sees_aabb(aabb, &planes, r.clone()));
+        }
+        self.collect(|aabb| camera.sees_aabb(&transform.apply_aabb(aabb), &planes, r.clone()))
+    }
+
+    /// Finds the Triangles whose Aabb overlaps a box.
+    ///
+    /// # Arguments
+    /// * `self` - The Bvh the function was called for.
+    /// * `tris` - The Triangles the Bvh was built over.
+    /// * `aabb` - The box.
+    ///
+    /// # Return
+    /// The indices of the Triangles
+    ///
+    #[allow(dead_code)]
+    pub fn query_aabb(&self, tris: &Vec<Triangle>, aabb: &Aabb) -> Vec<usize> {
+        self.collect(|node| node.overlaps(aabb))
+            .into_iter()
+            .filter(|index| Bvh::triangle_aabb(&tris[*index]).overlaps(aabb))
+            .collect()
+    }
+
+    /// Finds the first Triangle hit by a ray.
+    ///
+    /// # Arguments
+    /// * `self` - The Bvh the function was called for.
+    /// * `tris` - The Triangles the Bvh was built over.
+    /// * `origin` - The start of the ray.
+    /// * `direction` - The direction of the ray.
+    ///
+    /// # Return
+    /// The index of the Triangle and how far along the ray it was hit
+    ///
+    pub fn intersect_ray(
+        &self,
+        tris: &Vec<Triangle>,
+        origin: Vec3d,
+        direction: Vec3d,
+    ) -> Option<(usize, f32)> {
+        if self.indices.len() == 0 {
+            return None;
+        }
+        let inverse = Vec3d::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let mut best: Option<(usize, f32)> = None;
+        let mut stack: Vec<usize> = vec![0];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            let entry = match node.aabb.intersect_ray(origin, inverse) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if best.is_some_and(|(_, t)| entry > t) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.left);
+                stack.push(node.left + 1);
+                continue;
+            }
+            for index in self.indices[node.start..node.start + node.count].iter() {
+                if let Some(t) = tris[*index].intersect_ray(origin, direction) {
+                    if best.is_none_or(|(_, closest)| t < closest) {
+                        best = Some((*index, t));
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Finds the point on the Triangles closest to another point.
+    ///
+    /// # Arguments
+    /// * `self` - The Bvh the function was called for.
+    /// * `tris` - The Triangles the Bvh was built over.
+    /// * `point` - The other point.
+    ///
+    /// # Return
+    /// The index of the closest Triangle and the closest point on it
+    ///
+    #[allow(dead_code)]
+    pub fn closest_point(&self, tris: &Vec<Triangle>, point: Vec3d) -> Option<(usize, Vec3d)> {
+        if self.indices.len() == 0 {
+            return None;
+        }
+        let mut best: Option<(usize, Vec3d)> = None;
+        let mut best_distance = f32::INFINITY;
+        let mut stack: Vec<usize> = vec![0];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if node.aabb.distance_squared(point) >= best_distance {
+                continue;
+            }
+            if node.count == 0 {
+                // look at the closer child first so the other one is more likely skipped
+                let (near, far) = if self.nodes[node.left].aabb.distance_squared(point)
+                    <= self.nodes[node.left + 1].aabb.distance_squared(point)
+                {
+                    (node.left, node.left + 1)
+                } else {
+                    (node.left + 1, node.left)
+                };
+                stack.push(far);
+                stack.push(near);
+                continue;
+            }
+            for index in self.indices[node.start..node.start + node.count].iter() {
+                let closest = tris[*index].closest_point(point);
+                let difference = closest - point;
+                let distance = difference.dot(difference);
+                if distance < best_distance {
+                    best_distance = distance;
+                    best = Some((*index, closest));
+                }
+            }
+        }
+        best
+    }
+
+    /// Collects the Triangles of every leaf whose Aabb and parents pass a test.
+    ///
+    /// # Arguments
+    /// * `self` - The Bvh the function was called for.
+    /// * `test` - Decides if a node is searched.
+    ///
+    /// # Return
+    /// The indices of the Triangles
+    ///
+    fn collect<F>(&self, test: F) -> Vec<usize>
+    where
+        F: Fn(&Aabb) -> bool,
+    {
+        let mut out: Vec<usize> = Vec::new();
+        if self.indices.len() == 0 {
+            return out;
+        }
+        let mut stack: Vec<usize> = vec![0];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if !test(&node.aabb) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.left);
+                stack.push(node.left + 1);
+            } else {
+                out.extend_from_slice(&self.indices[node.start..node.start + node.count]);
+            }
+        }
+        out
+    }
+
+    /// Splits a node in two where the surface area heuristic says it is
+    /// cheapest to search both halves. The centers of the Triangles are
+    /// sorted into buckets along each axis and every boundary between
+    /// buckets is tried. Nodes that are cheaper to search as they are
+    /// become leaves.
+    ///
+    /// # Arguments
+    /// * `self` - The Bvh the function was called for.
+    /// * `node` - The index of the node.
+    /// * `boxes` - The Aabb of every Triangle.
+    /// * `centers` - The center of every Aabb.
+    ///
+    fn subdivide(&mut self, node: usize, boxes: &Vec<Aabb>, centers: &Vec<Vec3d>) {
+        let start = self.nodes[node].start;
+        let count = self.nodes[node].count;
+        let mut aabb = Aabb::empty();
+        let mut center_box = Aabb::empty();
+        for index in self.indices[start..start + count].iter() {
+            aabb = aabb.merge(&boxes[*index]);
+            center_box.grow(centers[*index]);
+        }
+        self.nodes[node].aabb = aabb;
+        if count <= Bvh::LEAF_SIZE {
+            return;
+        }
+
+        // (cost, axis, position) of the best split cheaper than a leaf
+        let leaf_cost = aabb.surface_area() * count as f32;
+        let mut best: Option<(f32, usize, f32)> = None;
+        for axis in 0..3 {
+            let low = Bvh::component(center_box.min, axis);
+            let high = Bvh::component(center_box.max, axis);
+            if !(high > low) {
+                continue;
+            }
+            let scale = Bvh::BINS as f32 / (high - low);
+            let mut bins = [(Aabb::empty(), 0); Bvh::BINS];
+            for index in self.indices[start..start + count].iter() {
+                let bin = ((Bvh::component(centers[*index], axis) - low) * scale) as usize;
+                let bin = bin.min(Bvh::BINS - 1);
+                bins[bin].0 = bins[bin].0.merge(&boxes[*index]);
+                bins[bin].1 += 1;
+            }
+            for split in 1..Bvh::BINS {
+                let (mut left, mut right) = (Aabb::empty(), Aabb::empty());
+                let (mut left_count, mut right_count) = (0, 0);
+                for bin in bins[..split].iter() {
+                    left = left.merge(&bin.0);
+                    left_count += bin.1;
+                }
+                for bin in bins[split..].iter() {
+                    right = right.merge(&bin.0);
+                    right_count += bin.1;
+                }
+                if left_count == 0 || right_count == 0 {
+                    continue;
+                }
+                let cost = left.surface_area() * left_count as f32
+                    + right.surface_area() * right_count as f32;
+                if cost < best.map_or(leaf_cost, |best| best.0) {
+                    best = Some((cost, axis, low + split as f32 / scale));
+                }
+            }
+        }
+
+        // move the Triangles left of the split to the front
+        let (_, axis, position) = match best {
+            Some(best) => best,
+            None => return,
+        };
+        let mut i = start;
+        let mut j = start + count;
+        while i < j {
+            if Bvh::component(centers[self.indices[i]], axis) < position {
+                i += 1;
+            } else {
+                j -= 1;
+                self.indices.swap(i, j);
+            }
+        }
+        let left_count = i - start;
+        if left_count == 0 || left_count == count {
+            return;
+        }
+
+        let left = self.nodes.len();
+        self.nodes.push(BvhNode {
+            aabb: Aabb::empty(),
+            left: 0,
+            start: start,
+            count: left_count,
+        });
+        self.nodes.push(BvhNode {
+            aabb: Aabb::empty(),
+            left: 0,
+            start: start + left_count,
+            count: count - left_count,
+        });
+        self.nodes[node].left = left;
+        self.nodes[node].count = 0;
+        self.subdivide(left, boxes, centers);
+        self.subdivide(left + 1, boxes, centers);
+    }
+
+    /// The Aabb of a Triangle.
+    ///
+    /// # Arguments
+    /// * `tri` - The Triangle.
+    ///
+    /// # Return
+    /// The Aabb
+    ///
+    fn triangle_aabb(tri: &Triangle) -> Aabb {
+        Aabb::from_points([tri.verticies.0, tri.verticies.1, tri.verticies.2].iter())
+    }
+
+    /// One component of a Vec3d.
+    ///
+    /// # Arguments
+    /// * `v` - The Vec3d.
+    /// * `axis` - 0 for x, 1 for y and 2 for z.
+    ///
+    /// # Return
+    /// The component
+    ///
+    fn component(v: Vec3d, axis: usize) -> f32 {
+        match axis {
+            0 => v.x,
+            1 => v.y,
+            _ => v.z,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bvh::Bvh, mesh::Mesh, vec3d::Vec3d};
+
+    fn sphere() -> Mesh {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_icosphere(1.0, 2, false);
+        mesh
+    }
+
+    /// Points spread around the sphere that do not change between runs.
+    fn point(i: usize, distance: f32) -> Vec3d {
+        let i = i as f32;
+        Vec3d::new((i * 1.7).sin(), (i * 2.3).cos(), (i * 0.9).sin()).normalize() * distance
+    }
+
+    #[test]
+    fn ray_matches_brute_force() {
+        let mesh = sphere();
+        let bvh = Bvh::build(&mesh.tris);
+        for i in 0..100 {
+            let origin = point(i, 3.0);
+            // some rays aim past the sphere
+            let direction = point(i + 1000, 0.6) - origin;
+            let brute = mesh
+                .tris
+                .iter()
+                .filter_map(|tri| tri.intersect_ray(origin, direction))
+                .fold(None, |best: Option<f32>, t| {
+                    Some(best.map_or(t, |b| b.min(t)))
+                });
+            let found = bvh.intersect_ray(&mesh.tris, origin, direction);
+            match (brute, found) {
+                (None, None) => (),
+                (Some(t), Some((index, found))) => {
+                    assert!((t - found).abs() < 1e-5);
+                    assert!(mesh.tris[index].intersect_ray(origin, direction).is_some());
+                }
+                _ => panic!("ray {} hit differently", i),
+            }
+        }
+    }
+
+    #[test]
+    fn closest_point_matches_brute_force() {
+        let mesh = sphere();
+        let bvh = Bvh::build(&mesh.tris);
+        for i in 0..100 {
+            let p = point(i, 0.5 + (i % 5) as f32 * 0.5);
+            let brute = mesh
+                .tris
+                .iter()
+                .map(|tri| (tri.closest_point(p) - p).length())
+                .fold(f32::INFINITY, f32::min);
+            let (_, found) = bvh.closest_point(&mesh.tris, p).unwrap();
+            assert!(((found - p).length() - brute).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn refit_follows_the_triangles() {
+        let mut mesh = sphere();
+        let mut bvh = Bvh::build(&mesh.tris);
+        for tri in mesh.tris.iter_mut() {
+            tri.increment_x(5.0);
+        }
+        bvh.refit(&mesh.tris);
+        let origin = Vec3d::new(5.03, 0.02, -3.0);
+        let (_, t) = bvh
+            .intersect_ray(&mesh.tris, origin, Vec3d::new(0.0, 0.0, 1.0))
+            .unwrap();
+        assert!((t - 2.0).abs() < 0.05);
+    }
+}
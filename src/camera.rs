@@ -1,4 +1,6 @@
-use crate::{matrix3x3::Matrix3x3, mesh::Mesh, triangle::Triangle, vec3d::Vec3d};
+use std::f32::consts::PI;
+
+use crate::{bounds::Aabb, matrix3x3::Matrix3x3, mesh::Mesh, triangle::Triangle, vec3d::Vec3d};
 
 use std::{sync::mpsc, thread};
 
@@ -17,6 +19,9 @@ pub struct Camera {
 }
 
 impl Camera {
+    /// How close to the camera something can be drawn.
+    pub const NEAR: f32 = 0.01;
+
     /// Creates a new camera object with a position and a roation.
     ///
     /// # Arguments
@@ -46,7 +51,7 @@ impl Camera {
     ///
     pub fn rotate_up(&mut self, n: f32) {
         let mut new_rt = self.rotation.x - n;
-        while new_rt > 180.0 || new_rt < -180.0 {
+        while !(-180.0..=180.0).contains(&new_rt) {
             if new_rt > 180.0 {
                 new_rt = -360.0 + new_rt;
             }
@@ -66,7 +71,7 @@ impl Camera {
     ///
     pub fn rotate_down(&mut self, n: f32) {
         let mut new_rt = self.rotation.x + n;
-        while new_rt > 180.0 || new_rt < -180.0 {
+        while !(-180.0..=180.0).contains(&new_rt) {
             if new_rt > 180.0 {
                 new_rt = -360.0 + new_rt;
             }
@@ -86,7 +91,7 @@ impl Camera {
     ///
     pub fn rotate_left(&mut self, n: f32) {
         let mut new_rt = self.rotation.y - n;
-        while new_rt > 180.0 || new_rt < -180.0 {
+        while !(-180.0..=180.0).contains(&new_rt) {
             if new_rt > 180.0 {
                 new_rt = -360.0 + new_rt;
             }
@@ -106,7 +111,7 @@ impl Camera {
     ///
     pub fn rotate_right(&mut self, n: f32) {
         let mut new_rt = self.rotation.y + n;
-        while new_rt > 180.0 || new_rt < -180.0 {
+        while !(-180.0..=180.0).contains(&new_rt) {
             if new_rt > 180.0 {
                 new_rt = -360.0 + new_rt;
             }
@@ -117,23 +122,32 @@ impl Camera {
         self.rotation.y = new_rt;
     }
 
-    /// Creates a vec and populates it with of all the projected triangles
-    /// implements multithreading
-    /// defines a rotation matrix
+    /// Turns the camera so it looks along a direction.
     ///
     /// # Arguments
     ///
-    /// * `self` - A mutable reference to the camera the function was called for
-    /// * `real_mesh` - The real mesh (not projected)
-    /// * `size` - The dimensions of the screen
+    /// * `self` - The Camera the function is being called for.
+    /// * `direction` - The direction to look along.
+    ///
+    pub fn look_along(&mut self, mut direction: Vec3d) {
+        direction.normalize();
+        let y = direction.y.clamp(-1.0, 1.0);
+        self.rotation.x = -f32::asin(y).to_degrees();
+        self.rotation.y = f32::atan2(direction.x, direction.z).to_degrees();
+    }
+
+    /// Creates the rotation matrices around the x and y axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
     ///
     /// # Return
     ///
-    /// A vec containing the projected triangles
+    /// The rotation matrices around the x axis and the y axis
     ///
-    pub fn get_projected_triangles(&mut self, real_mesh: Mesh, size: (f32, f32)) -> Vec<Triangle> {
-        // rotation martrix
-        let rotation = self.rotation * (3.14159265 / 180.0);
+    pub fn axis_rotations(&self) -> (Matrix3x3, Matrix3x3) {
+        let rotation = self.rotation * (PI / 180.0);
 
         let r_x = Matrix3x3 {
             m: [
@@ -149,6 +163,289 @@ impl Camera {
                 [f32::sin(rotation.y), 0.0, f32::cos(rotation.y)],
             ],
         };
+        (r_x, r_y)
+    }
+
+    /// Creates the rotation matrix of the camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    ///
+    /// # Return
+    ///
+    /// The rotation matrix
+    ///
+    pub fn rotation_matrix(&self) -> Matrix3x3 {
+        let (r_x, r_y) = self.axis_rotations();
+        r_x * r_y
+    }
+
+    /// Projects a point onto the screen in pixels. The z of the
+    /// result is 1 / the distance in front of the camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `real` - The real position
+    /// * `size` - The dimensions of the screen
+    /// * `r`    - The rotation matrix
+    ///
+    /// # Return
+    ///
+    /// The projected Vec3d
+    ///
+    pub fn project_point(&self, real: Vec3d, size: (f32, f32), r: Matrix3x3) -> Vec3d {
+        let mut projected = self.get_point_projection(real, size, r);
+        projected.x = (projected.x + 1.0) * 0.5 * size.0;
+        projected.y = (projected.y + 1.0) * 0.5 * size.1;
+        projected
+    }
+
+    /// Projects a line onto the screen in pixels. The part of the line
+    /// behind the camera is cut off.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `start` - The real position of the start of the line
+    /// * `end` - The real position of the end of the line
+    /// * `size` - The dimensions of the screen
+    /// * `r`    - The rotation matrix
+    ///
+    /// # Return
+    ///
+    /// The projected ends of the line or None if it is behind the camera
+    ///
+    pub fn project_line(
+        &self,
+        start: Vec3d,
+        end: Vec3d,
+        size: (f32, f32),
+        r: Matrix3x3,
+    ) -> Option<(Vec3d, Vec3d)> {
+        // λ is 1 / the distance in front of the camera which changes
+        // linearly along the line so the line can be cut where it is NEAR.
+        let depth_start = 1.0 / self.project_point(start, size, r.clone()).z;
+        let depth_end = 1.0 / self.project_point(end, size, r.clone()).z;
+        if !(depth_start >= Camera::NEAR) && !(depth_end >= Camera::NEAR) {
+            return None;
+        }
+
+        let cut = |inside: Vec3d, outside: Vec3d, depth_inside: f32, depth_outside: f32| {
+            let t = (depth_inside - Camera::NEAR) / (depth_inside - depth_outside);
+            inside + (outside - inside) * t
+        };
+        let (start, end) = if !(depth_start >= Camera::NEAR) {
+            (cut(end, start, depth_end, depth_start), end)
+        } else if !(depth_end >= Camera::NEAR) {
+            (start, cut(start, end, depth_start, depth_end))
+        } else {
+            (start, end)
+        };
+        Some((
+            self.project_point(start, size, r.clone()),
+            self.project_point(end, size, r),
+        ))
+    }
+
+    /// Creates the ray from the camera through a pixel on the screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `pixel` - The position on the screen in pixels
+    /// * `size` - The dimensions of the screen
+    ///
+    /// # Return
+    ///
+    /// The start of the ray and its direction with a length of 1
+    ///
+    pub fn ray_through(&self, pixel: (f32, f32), size: (f32, f32)) -> (Vec3d, Vec3d) {
+        // undo project_point for a point 1 in front of the camera
+        let x = (pixel.0 / size.0 * 2.0 - 1.0) * 0.5 * (size.0 / size.1);
+        let y = -(pixel.1 / size.1 * 2.0 - 1.0) * 0.5;
+        let mut direction = self.rotation_matrix().transpose() * Vec3d::new(x, y, 1.0);
+        (self.position, direction.normalize())
+    }
+
+    /// Moves a point into view space. The camera is at the origin looking
+    /// along z, x points right and y points up.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `real` - The real position
+    /// * `r`    - The rotation matrix
+    ///
+    /// # Return
+    ///
+    /// The position in view space
+    ///
+    pub fn view_position(&self, real: Vec3d, r: Matrix3x3) -> Vec3d {
+        r * (real - self.position)
+    }
+
+    /// Creates the planes around what the camera sees in view space:
+    /// the near plane and the left, right, top and bottom of the screen.
+    /// A point is inside a plane if normal.dot(point) + offset >= 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `size` - The dimensions of the screen
+    ///
+    /// # Return
+    ///
+    /// The unit normal and offset of each plane
+    ///
+    pub fn frustum_planes(&self, size: (f32, f32)) -> [(Vec3d, f32); 5] {
+        // a point is on the edge of the screen where x = ±w * z or y = ±h * z
+        let w = 0.5 * (size.0 / size.1);
+        let h = 0.5;
+        [
+            (Vec3d::new(0.0, 0.0, 1.0), -Camera::NEAR),
+            (Vec3d::new(1.0, 0.0, w).normalize(), 0.0),
+            (Vec3d::new(-1.0, 0.0, w).normalize(), 0.0),
+            (Vec3d::new(0.0, 1.0, h).normalize(), 0.0),
+            (Vec3d::new(0.0, -1.0, h).normalize(), 0.0),
+        ]
+    }
+
+    /// The distance from the Camera to a point.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `point` - The real position of the point
+    ///
+    /// # Return
+    ///
+    /// The distance
+    ///
+    pub fn distance_to(&self, point: Vec3d) -> f32 {
+        (point - self.position).length()
+    }
+
+    /// Estimates how big a Mesh is on the screen from the distance to
+    /// its bounding sphere. Turning the Camera does not change the size.
+    /// The Mesh is culled first like in `sees` and the distance to the
+    /// sphere from that test is used for the size.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `mesh` - The Mesh
+    /// * `size` - The dimensions of the screen
+    ///
+    /// # Return
+    ///
+    /// The width of the bounding sphere in pixels, infinite if the Camera is
+    /// inside it, or None if the Mesh can not be seen
+    ///
+    pub fn projected_size(&self, mesh: &Mesh, size: (f32, f32)) -> Option<f32> {
+        if mesh.aabb.is_empty() {
+            return None;
+        }
+        let r = self.rotation_matrix();
+        let planes = self.frustum_planes(size);
+        let sphere = mesh.world_sphere();
+        let center = self.view_position(sphere.center, r.clone());
+        if planes
+            .iter()
+            .any(|plane| plane.0.dot(center) + plane.1 < -sphere.radius)
+        {
+            return None;
+        }
+        if !self.sees_aabb(&mesh.world_aabb(), &planes, r.clone()) {
+            return None;
+        }
+
+        // turning into view space keeps the distance
+        let distance = center.length();
+        if distance <= sphere.radius {
+            return Some(f32::INFINITY);
+        }
+        // pixels per unit one unit in front of the camera
+        let forward = self.position + r.transpose() * Vec3d::new(0.0, 0.0, 1.0);
+        let right = r.transpose() * Vec3d::new(1.0, 0.0, 0.0);
+        let scale = self.project_point(forward + right, size, r.clone()).x
+            - self.project_point(forward, size, r.clone()).x;
+        Some(2.0 * sphere.radius * scale.abs() / distance)
+    }
+
+    /// Checks if any part of a Mesh can be seen. The bounding sphere is
+    /// tested against the frustum planes first and then the corners of
+    /// the bounding box.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `mesh` - The Mesh
+    /// * `size` - The dimensions of the screen
+    ///
+    /// # Return
+    ///
+    /// false if the Mesh is completely outside of the frustum
+    ///
+    pub fn sees(&self, mesh: &Mesh, size: (f32, f32)) -> bool {
+        self.projected_size(mesh, size).is_some()
+    }
+
+    /// Checks if any part of an Aabb can be seen. The Aabb is only
+    /// culled if all of its corners are outside one frustum plane, so
+    /// some Aabb's outside the frustum are still seen.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The Camera the function is being called for.
+    /// * `aabb` - The Aabb in the world
+    /// * `planes` - The frustum planes
+    /// * `r`    - The rotation matrix
+    ///
+    /// # Return
+    ///
+    /// false if the Aabb is completely outside of the frustum
+    ///
+    pub fn sees_aabb(&self, aabb: &Aabb, planes: &[(Vec3d, f32); 5], r: Matrix3x3) -> bool {
+        let mut corners = aabb.corners();
+        for corner in corners.iter_mut() {
+            *corner = self.view_position(*corner, r.clone());
+        }
+        !planes.iter().any(|plane| {
+            corners
+                .iter()
+                .all(|corner| plane.0.dot(*corner) + plane.1 < 0.0)
+        })
+    }
+
+    /// Creates a vec and populates it with of all the projected triangles
+    /// implements multithreading
+    /// defines a rotation matrix
+    ///
+    /// Triangles are culled in view space. Triangles outside one of the
+    /// frustum planes are dropped, back faces are dropped unless the Mesh
+    /// has back-face culling turned off or the Material is double sided,
+    /// triangles crossing the near plane are cut and the rest are clipped
+    /// to the screen. A Mesh with a Bvh only looks at the triangles in the
+    /// parts of the Bvh that can be seen. The Triangles of the level of
+    /// detail the Mesh picked are used.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the camera the function was called for
+    /// * `real_mesh` - The real mesh (not projected), it is not changed
+    /// * `size` - The dimensions of the screen
+    ///
+    /// # Return
+    ///
+    /// A vec containing the projected triangles
+    ///
+    pub fn get_projected_triangles(&mut self, real_mesh: &Mesh, size: (f32, f32)) -> Vec<Triangle> {
+        let real_tris = real_mesh.lod_tris();
+
+        // rotation martrix
+        let (r_x, r_y) = self.axis_rotations();
 
         let r = r_x.clone() * r_y.clone();
 
@@ -162,64 +459,95 @@ impl Camera {
         self.m_right = (r_y.clone() * Vec3d::new(1.0, 0.0, 0.0)).normalize();
 
         // deal with the triangles
-        let this = self.clone();
+        let this = *self;
+        let planes = self.frustum_planes(size);
         let mut tris: Vec<Triangle> = Vec::new();
-        let s = real_mesh.tris.len();
+        // the Bvh skips groups of triangles outside the frustum
+        // the Bvh is for the full Triangles
+        let candidates: Vec<usize> = match (&real_mesh.bvh, real_mesh.lod) {
+            (Some(bvh), 0) => bvh.query_frustum(self, size, &real_mesh.transform),
+            _ => (0..real_tris.len()).collect(),
+        };
+        let moved = !real_mesh.transform.is_identity();
+        let s = candidates.len();
         let (tx, rx) = mpsc::channel();
-        for i in 0..s {
-            let tri = real_mesh.tris[i];
+        for i in candidates {
+            let tri = if moved {
+                real_mesh.transform.apply_triangle(&real_tris[i])
+            } else {
+                real_tris[i]
+            };
+            let alpha = real_mesh.alpha(tri.material);
+            let cull = real_mesh.cull_back_faces
+                && !real_mesh
+                    .materials
+                    .get(tri.material)
+                    .is_some_and(|material| material.double_sided);
             let tx = tx.clone();
 
             let n_r = r.clone();
             thread::spawn(move || {
-                let look: Vec3d = tri.center - this.position;
-                let look_len =
-                    f64::sqrt((look.x * look.x + look.y * look.y + look.z * look.z).into());
-                let lx = (look.x as f64) / look_len;
-                let ly = (look.y as f64) / look_len;
-                let lz = (look.z as f64) / look_len;
-
-                let dot_product_normals: f64 = (tri.normal.x as f64 * lx)
-                    + (tri.normal.y as f64 * ly)
-                    + (tri.normal.z as f64 * lz); // fix this
-                let dot_product_look: f64 = -(this.r_forward.x as f64 * lx)
-                    + (this.r_forward.y as f64 * ly).abs()
-                    + (this.r_forward.z as f64 * lz); // fix this
-                if dot_product_normals < 0.0 && dot_product_look > 0.4 {
-                    //         /\/\/\ this should not be necessary
-                    let mut tri_projected: Triangle = Triangle::new(
-                        this.get_point_projection(tri.verticies.0, size, n_r.clone()),
-                        this.get_point_projection(tri.verticies.1, size, n_r.clone()),
-                        this.get_point_projection(tri.verticies.2, size, n_r.clone()),
-                    );
-
-                    tri_projected.color = tri.color;
-                    tri_projected.center = Triangle::calculate_center(tri_projected.verticies);
+                let view = (
+                    this.view_position(tri.verticies.0, n_r.clone()),
+                    this.view_position(tri.verticies.1, n_r.clone()),
+                    this.view_position(tri.verticies.2, n_r.clone()),
+                );
 
-                    tri_projected.verticies.0.x += 1.0;
-                    tri_projected.verticies.0.y += 1.0;
-                    tri_projected.verticies.1.x += 1.0;
-                    tri_projected.verticies.1.y += 1.0;
-                    tri_projected.verticies.2.x += 1.0;
-                    tri_projected.verticies.2.y += 1.0;
+                // frustum
+                let distance = |plane: &(Vec3d, f32), v: Vec3d| plane.0.dot(v) + plane.1;
+                let outside = planes.iter().any(|plane| {
+                    distance(plane, view.0) < 0.0
+                        && distance(plane, view.1) < 0.0
+                        && distance(plane, view.2) < 0.0
+                });
+                if outside {
+                    tx.send(Vec::new()).unwrap();
+                    return;
+                }
 
-                    tri_projected.verticies.0.x *= 0.5 * size.0;
-                    tri_projected.verticies.0.y *= 0.5 * size.1;
-                    tri_projected.verticies.1.x *= 0.5 * size.0;
-                    tri_projected.verticies.1.y *= 0.5 * size.1;
-                    tri_projected.verticies.2.x *= 0.5 * size.0;
-                    tri_projected.verticies.2.y *= 0.5 * size.1;
+                // back faces look away from the camera at the origin
+                let mut tri = tri;
+                let back = Triangle::calculate_normal(view).dot(view.0) >= 0.0;
+                if back {
+                    if cull {
+                        tx.send(Vec::new()).unwrap();
+                        return;
+                    }
+                    // light the back of the face
+                    tri.vertex_normals = (
+                        tri.vertex_normals.0 * -1.0,
+                        tri.vertex_normals.1 * -1.0,
+                        tri.vertex_normals.2 * -1.0,
+                    );
+                }
 
-                    let mut projected_tris = tri_projected.clip(size.0, size.1, 0);
+                let look_len = this.distance_to(tri.center);
+                let near = planes[0];
+                let mut projected_tris: Vec<Triangle> = Vec::new();
+                for piece in tri.clip_to_plane((
+                    distance(&near, view.0),
+                    distance(&near, view.1),
+                    distance(&near, view.2),
+                )) {
+                    let mut tri_projected: Triangle = Triangle::new(
+                        this.project_point(piece.verticies.0, size, n_r.clone()),
+                        this.project_point(piece.verticies.1, size, n_r.clone()),
+                        this.project_point(piece.verticies.2, size, n_r.clone()),
+                    );
 
-                    for tri_n in 0..projected_tris.len() {
-                        projected_tris[tri_n].dist = look_len as f32;
-                    }
+                    tri_projected.material = tri.material;
+                    tri_projected.face = i;
+                    tri_projected.alpha = alpha;
+                    tri_projected.vertex_colors = piece.vertex_colors;
+                    tri_projected.vertex_normals = piece.vertex_normals;
+                    tri_projected.world_verticies = piece.verticies;
+                    tri_projected.uvs = piece.uvs;
+                    tri_projected.dist = look_len;
 
-                    tx.send(projected_tris).unwrap();
-                } else {
-                    tx.send(Vec::new()).unwrap();
+                    projected_tris.append(&mut tri_projected.clip(size.0, size.1));
                 }
+
+                tx.send(projected_tris).unwrap();
             });
         }
 
@@ -304,3 +632,84 @@ impl Camera {
         inverse_mat * (v_f * -1.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{camera::Camera, mesh::Mesh, vec3d::Vec3d};
+
+    const SIZE: (f32, f32) = (200.0, 100.0);
+
+    fn camera() -> Camera {
+        Camera::new(Vec3d::new(0.0, 0.0, -4.0), Vec3d::new(0.0, 0.0, 0.0))
+    }
+
+    fn cube(x: f32, y: f32, z: f32) -> Mesh {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        mesh.increment_x(x);
+        mesh.increment_y(y);
+        mesh.increment_z(z);
+        mesh
+    }
+
+    #[test]
+    fn back_faces_are_culled_unless_turned_off() {
+        let mut camera = camera();
+        let mut mesh = cube(0.0, 0.0, 0.0);
+        // only the front of a cube straight ahead faces the camera
+        assert_eq!(camera.get_projected_triangles(&mesh, SIZE).len(), 2);
+        mesh.cull_back_faces = false;
+        assert_eq!(camera.get_projected_triangles(&mesh, SIZE).len(), 12);
+        mesh.cull_back_faces = true;
+        mesh.materials[0].double_sided = true;
+        assert_eq!(camera.get_projected_triangles(&mesh, SIZE).len(), 12);
+    }
+
+    #[test]
+    fn meshes_outside_the_frustum_are_not_seen() {
+        let camera = camera();
+        assert!(camera.sees(&cube(0.0, 0.0, 0.0), SIZE));
+        assert!(!camera.sees(&cube(0.0, 0.0, -10.0), SIZE));
+        assert!(!camera.sees(&cube(20.0, 0.0, 0.0), SIZE));
+        assert!(!camera.sees(&cube(0.0, -10.0, 0.0), SIZE));
+        // partly on the screen
+        assert!(camera.sees(&cube(4.3, 0.0, 0.0), SIZE));
+    }
+
+    #[test]
+    fn triangles_crossing_the_near_plane_are_cut() {
+        let mut camera = camera();
+        // the camera is inside the bottom half of a big cube
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        mesh.scale(Vec3d::new(20.0, 20.0, 20.0), Vec3d::new(0.0, 0.0, 0.0));
+        mesh.increment_z(-4.0);
+        mesh.increment_y(9.0);
+        mesh.cull_back_faces = false;
+        let tris = camera.get_projected_triangles(&mesh, SIZE);
+        assert!(tris.len() > 0);
+        for tri in tris.iter() {
+            for vertex in [tri.verticies.0, tri.verticies.1, tri.verticies.2].iter() {
+                assert!(vertex.z > 0.0 && vertex.z <= 1.0 / Camera::NEAR + 1e-3);
+                assert!(vertex.x >= -1e-3 && vertex.x <= SIZE.0 + 1e-3);
+                assert!(vertex.y >= -1e-3 && vertex.y <= SIZE.1 + 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn ray_through_a_pixel_hits_what_projects_there() {
+        let mut camera = camera();
+        camera.rotate_left(20.0);
+        camera.rotate_up(10.0);
+        let r = camera.rotation_matrix();
+        let point = Vec3d::new(0.7, -0.4, 1.5);
+        let projected = camera.project_point(point, SIZE, r);
+        let (origin, direction) = camera.ray_through((projected.x, projected.y), SIZE);
+        assert!((direction.length() - 1.0).abs() < 1e-5);
+        // the point is on the ray
+        let along = (point - origin).dot(direction);
+        assert!(along > 0.0);
+        assert!((origin + direction * along - point).length() < 1e-3);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{mesh::Mesh, triangle::Triangle, vec3d::Vec3d};
+
+/// How the projected Triangles are colored before they are drawn.
+///  - Shaded     Materials lit by the Lights
+///  - Palette    the vertex colors without lighting
+///  - Normal     the direction of the surface normal as rgb
+///  - Height     a color ramp along an axis
+///  - Distance   a color ramp by the distance to the Camera
+///  - FaceIndex  a color hashed from the index of the face
+///  - Uniform    one color for everything
+#[derive(Clone)]
+pub enum ColorMode {
+    Shaded,
+    Palette,
+    Normal,
+    Height {
+        axis: Vec3d,
+        ramp: Vec<(f32, (f32, f32, f32))>,
+    },
+    Distance {
+        ramp: Vec<(f32, (f32, f32, f32))>,
+    },
+    FaceIndex {
+        seed: u64,
+    },
+    Uniform((f32, f32, f32)),
+}
+
+impl ColorMode {
+    /// Checks if the Materials are lit by the Lights.
+    ///
+    /// # Arguments
+    /// * `self` - The ColorMode the function was called for.
+    ///
+    /// # Return
+    /// If the mode is Shaded
+    ///
+    pub fn is_shaded(&self) -> bool {
+        matches!(self, ColorMode::Shaded)
+    }
+
+    /// A color ramp from blue through green to red.
+    ///
+    /// # Return
+    /// The stops of the ramp from 0 to 1
+    ///
+    pub fn default_ramp() -> Vec<(f32, (f32, f32, f32))> {
+        vec![
+            (0.0, (0.1, 0.1, 0.9)),
+            (0.25, (0.1, 0.8, 0.9)),
+            (0.5, (0.1, 0.9, 0.2)),
+            (0.75, (0.95, 0.9, 0.1)),
+            (1.0, (0.9, 0.1, 0.1)),
+        ]
+    }
+
+    /// Looks up a color on a color ramp.
+    ///
+    /// # Arguments
+    /// * `ramp` - The stops of the ramp sorted by position.
+    /// * `t` - The position on the ramp.
+    ///
+    /// # Return
+    /// The color blended between the two closest stops
+    ///
+    pub fn sample_ramp(ramp: &[(f32, (f32, f32, f32))], t: f32) -> (f32, f32, f32) {
+        if ramp.len() == 0 {
+            return (1.0, 1.0, 1.0);
+        }
+        if t <= ramp[0].0 {
+            return ramp[0].1;
+        }
+        for i in 1..ramp.len() {
+            let (t1, c1) = ramp[i];
+            if t <= t1 {
+                let (t0, c0) = ramp[i - 1];
+                let f = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+                return (
+                    c0.0 + (c1.0 - c0.0) * f,
+                    c0.1 + (c1.1 - c0.1) * f,
+                    c0.2 + (c1.2 - c0.2) * f,
+                );
+            }
+        }
+        ramp[ramp.len() - 1].1
+    }
+
+    /// The coloring stage. Colors the projected Triangles of a Mesh
+    /// after they have been projected and before they are drawn.
+    /// Does nothing when the mode is Shaded.
+    ///
+    /// # Arguments
+    /// * `self` - The ColorMode the function was called for.
+    /// * `tris` - The projected Triangles of the Mesh.
+    /// * `mesh` - The Mesh the Triangles were projected from.
+    /// * `eye` - The position of the Camera.
+    ///
+    pub fn apply(&self, tris: &mut Vec<Triangle>, mesh: &Mesh, eye: Vec3d) {
+        match self {
+            ColorMode::Shaded | ColorMode::Palette => (),
+            ColorMode::Normal => {
+                for tri in tris.iter_mut() {
+                    let n = Triangle::calculate_normal(tri.world_verticies);
+                    tri.set_color((n.x * 0.5 + 0.5, n.y * 0.5 + 0.5, n.z * 0.5 + 0.5));
+                }
+            }
+            ColorMode::Height { axis, ramp } => {
+                let mut axis = *axis;
+                let axis = axis.normalize();
+                let (min, max) = ColorMode::range(mesh, |v| v.dot(axis));
+                for tri in tris.iter_mut() {
+                    let t = |v: Vec3d| (v.dot(axis) - min) / (max - min);
+                    tri.vertex_colors = (
+                        ColorMode::sample_ramp(ramp, t(tri.world_verticies.0)),
+                        ColorMode::sample_ramp(ramp, t(tri.world_verticies.1)),
+                        ColorMode::sample_ramp(ramp, t(tri.world_verticies.2)),
+                    );
+                }
+            }
+            ColorMode::Distance { ramp } => {
+                let (min, max) = ColorMode::range(mesh, |v| (v - eye).length());
+                for tri in tris.iter_mut() {
+                    let t = |v: Vec3d| ((v - eye).length() - min) / (max - min);
+                    tri.vertex_colors = (
+                        ColorMode::sample_ramp(ramp, t(tri.world_verticies.0)),
+                        ColorMode::sample_ramp(ramp, t(tri.world_verticies.1)),
+                        ColorMode::sample_ramp(ramp, t(tri.world_verticies.2)),
+                    );
+                }
+            }
+            ColorMode::FaceIndex { seed } => {
+                for tri in tris.iter_mut() {
+                    tri.set_color(Mesh::face_color(*seed, tri.face));
+                }
+            }
+            ColorMode::Uniform(color) => {
+                for tri in tris.iter_mut() {
+                    tri.set_color(*color);
+                }
+            }
+        }
+    }
+
+    /// Finds the smallest and largest value of a measure over the
+    /// verticies of a Mesh.
+    ///
+    /// # Arguments
+    /// * `mesh` - The Mesh.
+    /// * `measure` - Measures a vertex.
+    ///
+    /// # Return
+    /// The smallest and largest value, never equal
+    ///
+    fn range<F>(mesh: &Mesh, measure: F) -> (f32, f32)
+    where
+        F: Fn(Vec3d) -> f32,
+    {
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+        for tri in mesh.tris.iter() {
+            for vertex in [tri.verticies.0, tri.verticies.1, tri.verticies.2].iter() {
+                let value = measure(mesh.transform.apply(*vertex));
+                min = min.min(value);
+                max = max.max(value);
+            }
+        }
+        if !(max > min) {
+            return (min, min + 1.0);
+        }
+        (min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{color_mode::ColorMode, mesh::Mesh, triangle::Triangle, vec3d::Vec3d};
+
+    fn cube() -> (Mesh, Vec<Triangle>) {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        let mut tris = mesh.tris.clone();
+        for tri in tris.iter_mut() {
+            tri.world_verticies = tri.verticies;
+        }
+        (mesh, tris)
+    }
+
+    #[test]
+    fn ramp_blends_between_its_stops() {
+        let ramp = vec![(0.0, (0.0, 0.0, 0.0)), (1.0, (1.0, 0.5, 0.0))];
+        assert_eq!(ColorMode::sample_ramp(&ramp, -1.0), (0.0, 0.0, 0.0));
+        assert_eq!(ColorMode::sample_ramp(&ramp, 0.5), (0.5, 0.25, 0.0));
+        assert_eq!(ColorMode::sample_ramp(&ramp, 2.0), (1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn height_runs_from_the_bottom_to_the_top() {
+        let (mesh, mut tris) = cube();
+        let ramp = vec![(0.0, (0.0, 0.0, 0.0)), (1.0, (1.0, 1.0, 1.0))];
+        let mode = ColorMode::Height {
+            axis: Vec3d::new(0.0, 2.0, 0.0),
+            ramp: ramp,
+        };
+        mode.apply(&mut tris, &mesh, Vec3d::new(0.0, 0.0, -4.0));
+        for tri in tris.iter() {
+            let verticies = [
+                tri.world_verticies.0,
+                tri.world_verticies.1,
+                tri.world_verticies.2,
+            ];
+            let colors = [
+                tri.vertex_colors.0,
+                tri.vertex_colors.1,
+                tri.vertex_colors.2,
+            ];
+            for k in 0..3 {
+                assert!((colors[k].0 - (verticies[k].y + 0.5)).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn normal_colors_every_side_of_a_cube_differently() {
+        let (mesh, mut tris) = cube();
+        ColorMode::Normal.apply(&mut tris, &mesh, Vec3d::new(0.0, 0.0, -4.0));
+        for tri in tris.iter() {
+            let n = tri.normal;
+            let expected = (n.x * 0.5 + 0.5, n.y * 0.5 + 0.5, n.z * 0.5 + 0.5);
+            let color = tri.vertex_colors.0;
+            assert!((color.0 - expected.0).abs() < 1e-5);
+            assert!((color.1 - expected.1).abs() < 1e-5);
+            assert!((color.2 - expected.2).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn face_index_and_uniform_colors_are_stable() {
+        let (mesh, mut tris) = cube();
+        let eye = Vec3d::new(0.0, 0.0, -4.0);
+        ColorMode::FaceIndex { seed: 7 }.apply(&mut tris, &mesh, eye);
+        let first: Vec<(f32, f32, f32)> = tris.iter().map(|tri| tri.vertex_colors.0).collect();
+        ColorMode::FaceIndex { seed: 7 }.apply(&mut tris, &mesh, eye);
+        for i in 0..tris.len() {
+            assert_eq!(tris[i].vertex_colors.0, first[i]);
+            assert_eq!(tris[i].vertex_colors.0, Mesh::face_color(7, tris[i].face));
+        }
+        ColorMode::Uniform((0.2, 0.4, 0.6)).apply(&mut tris, &mesh, eye);
+        assert!(tris
+            .iter()
+            .all(|tri| tri.vertex_colors.2 == (0.2, 0.4, 0.6)));
+    }
+}
@@ -0,0 +1,509 @@
+use std::f32::consts::PI;
+
+use crate::{camera::Camera, mesh::Mesh, vec3d::Vec3d};
+
+/// What a Gizmo changes.
+///  - Move    moves the Mesh along an axis
+///  - Rotate  turns the Mesh around an axis
+///  - Scale   stretches the Mesh along one of its own axes
+#[derive(Copy, Clone, PartialEq)]
+pub enum GizmoMode {
+    Move,
+    Rotate,
+    Scale,
+}
+
+/// A change made by dragging a Gizmo.
+///  - Move    by how much
+///  - Rotate  the axis, how far in degrees and the pivot point
+///  - Scale   the scale along each axis and the pivot point
+#[derive(Copy, Clone)]
+pub enum GizmoChange {
+    Move(Vec3d),
+    Rotate(usize, f32, Vec3d),
+    Scale(Vec3d, Vec3d),
+}
+
+impl GizmoChange {
+    /// Changes the Transform of a Mesh. The verticies stay where they
+    /// are until the Transform is applied.
+    ///
+    /// # Arguments
+    /// * `self` - The GizmoChange the function was called for.
+    /// * `mesh` - The Mesh.
+    ///
+    pub fn apply(&self, mesh: &mut Mesh) {
+        match *self {
+            GizmoChange::Move(by) => mesh.transform.position += by,
+            GizmoChange::Rotate(axis, r, pivot) => mesh.transform.rotate(axis, r, pivot),
+            GizmoChange::Scale(factor, pivot) => mesh.transform.scale_by(factor, pivot),
+        }
+        mesh.mark_changed();
+    }
+
+    /// Changes a point.
+    ///
+    /// # Arguments
+    /// * `self` - The GizmoChange the function was called for.
+    /// * `point` - The point.
+    ///
+    pub fn apply_to_point(&self, point: &mut Vec3d) {
+        match *self {
+            GizmoChange::Move(by) => *point += by,
+            GizmoChange::Rotate(0, r, pivot) => point.x_axis_rotation(r, pivot.y, pivot.z),
+            GizmoChange::Rotate(1, r, pivot) => point.y_axis_rotation(r, pivot.x, pivot.z),
+            GizmoChange::Rotate(_, r, pivot) => point.z_axis_rotation(r, pivot.x, pivot.y),
+            GizmoChange::Scale(factor, pivot) => *point = pivot + (*point - pivot) * factor,
+        }
+    }
+}
+
+/// A drag of one axis of a Gizmo. The pivot and the direction of the
+/// axis are kept from the start of the drag and the amount is measured
+/// from where the mouse started, so only the difference to what was
+/// applied so far changes the Mesh. `angle` is where a ring was grabbed
+/// in radians.
+#[derive(Copy, Clone)]
+pub struct GizmoDrag {
+    pub axis: usize,
+    pub direction: Vec3d,
+    pub pivot: Vec3d,
+    pub start: (f32, f32),
+    pub angle: f32,
+    pub applied: f32,
+}
+
+/// A Gizmo is drawn on the selected Mesh or verticies. It has an arrow,
+/// a ring or a handle for each axis that can be dragged with the mouse.
+/// The arrows and handles point along the axes they are given, so scale
+/// handles can follow the axes of the Mesh. The rings always turn around
+/// the axes of the world. With snapping on the change goes in steps.
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub snap: bool,
+    pub move_step: f32,
+    pub rotate_step: f32,
+    pub scale_step: f32,
+    pub drag: Option<GizmoDrag>,
+}
+
+impl Gizmo {
+    /// The colors of the x, y and z axis.
+    pub const COLORS: [(f32, f32, f32); 3] = [(0.9, 0.2, 0.2), (0.2, 0.85, 0.2), (0.25, 0.4, 1.0)];
+
+    /// The color of the axis being dragged.
+    pub const ACTIVE_COLOR: (f32, f32, f32) = (1.0, 0.9, 0.1);
+
+    /// How far from an axis in pixels it can be grabbed.
+    pub const GRAB_DISTANCE: f32 = 8.0;
+
+    /// How long the axes are compared to the distance to the camera.
+    pub const SIZE: f32 = 0.2;
+
+    /// How many line segments a ring is made of.
+    pub const RING_SEGMENTS: usize = 48;
+
+    /// Which way the rotations of Mesh turn a ring: x turns y to z and
+    /// z turns x to y but y turns x to z.
+    const RING_TURN: [f32; 3] = [1.0, -1.0, 1.0];
+
+    /// Creates a new Gizmo that moves without snapping.
+    ///
+    /// # Return
+    /// A new Gizmo
+    ///
+    pub fn new() -> Gizmo {
+        Gizmo {
+            mode: GizmoMode::Move,
+            snap: false,
+            move_step: 0.25,
+            rotate_step: 15.0,
+            scale_step: 0.1,
+            drag: None,
+        }
+    }
+
+    /// The direction of an axis.
+    ///
+    /// # Arguments
+    /// * `axis` - 0 for x, 1 for y and 2 for z.
+    ///
+    /// # Return
+    /// The direction
+    ///
+    pub fn axis(axis: usize) -> Vec3d {
+        match axis {
+            0 => Vec3d::new(1.0, 0.0, 0.0),
+            1 => Vec3d::new(0.0, 1.0, 0.0),
+            _ => Vec3d::new(0.0, 0.0, 1.0),
+        }
+    }
+
+    /// The directions of the x, y and z axis of the world.
+    ///
+    /// # Return
+    /// The directions
+    ///
+    pub fn world_axes() -> [Vec3d; 3] {
+        [Gizmo::axis(0), Gizmo::axis(1), Gizmo::axis(2)]
+    }
+
+    /// Where the Gizmo is drawn. It follows what it moves and stays
+    /// where the drag started while it turns or scales.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    /// * `center` - The center of what the Gizmo changes.
+    ///
+    /// # Return
+    /// The pivot point
+    ///
+    pub fn pivot(&self, center: Vec3d) -> Vec3d {
+        match self.drag {
+            Some(drag) if self.mode == GizmoMode::Move => {
+                drag.pivot + drag.direction * drag.applied
+            }
+            Some(drag) => drag.pivot,
+            None => center,
+        }
+    }
+
+    /// Projects the lines of the Gizmo onto the screen.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    /// * `center` - The center of what the Gizmo changes.
+    /// * `axes` - The directions of the arrows.
+    /// * `camera` - The Camera.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// The projected ends, the axis and the color of every line
+    ///
+    pub fn lines(
+        &self,
+        center: Vec3d,
+        axes: [Vec3d; 3],
+        camera: &Camera,
+        size: (f32, f32),
+    ) -> Vec<(Vec3d, Vec3d, usize, (f32, f32, f32))> {
+        let pivot = self.pivot(center);
+        let length = (pivot - camera.position).length() * Gizmo::SIZE;
+        let r = camera.rotation_matrix();
+        let mut lines = Vec::new();
+        for axis in 0..3 {
+            let color = self.color(axis);
+            let mut segments: Vec<(Vec3d, Vec3d)> = Vec::new();
+            if self.mode == GizmoMode::Rotate {
+                for i in 0..Gizmo::RING_SEGMENTS {
+                    segments.push((
+                        Gizmo::ring_point(pivot, length, axis, Gizmo::ring_angle(i)),
+                        Gizmo::ring_point(pivot, length, axis, Gizmo::ring_angle(i + 1)),
+                    ));
+                }
+            } else {
+                segments.push((pivot, pivot + axes[axis] * length));
+            }
+            for (start, end) in segments {
+                if let Some((start, end)) = camera.project_line(start, end, size, r.clone()) {
+                    lines.push((start, end, axis, color));
+                }
+            }
+        }
+        lines
+    }
+
+    /// Projects the handles at the ends of the arrows onto the screen.
+    /// Rings have no handles.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    /// * `center` - The center of what the Gizmo changes.
+    /// * `axes` - The directions of the arrows.
+    /// * `camera` - The Camera.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// The projected center and the color of every handle
+    ///
+    pub fn handles(
+        &self,
+        center: Vec3d,
+        axes: [Vec3d; 3],
+        camera: &Camera,
+        size: (f32, f32),
+    ) -> Vec<(Vec3d, (f32, f32, f32))> {
+        if self.mode == GizmoMode::Rotate {
+            return Vec::new();
+        }
+        let pivot = self.pivot(center);
+        let length = (pivot - camera.position).length() * Gizmo::SIZE;
+        let r = camera.rotation_matrix();
+        let mut handles = Vec::new();
+        for axis in 0..3 {
+            let end = camera.project_point(pivot + axes[axis] * length, size, r.clone());
+            if end.z > 0.0 {
+                handles.push((end, self.color(axis)));
+            }
+        }
+        handles
+    }
+
+    /// Finds the axis of the Gizmo under a pixel.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    /// * `center` - The center of what the Gizmo changes.
+    /// * `axes` - The directions of the arrows.
+    /// * `camera` - The Camera.
+    /// * `pixel` - The position on the screen in pixels.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// The closest axis within the grab distance or None
+    ///
+    pub fn axis_at(
+        &self,
+        center: Vec3d,
+        axes: [Vec3d; 3],
+        camera: &Camera,
+        pixel: (f32, f32),
+        size: (f32, f32),
+    ) -> Option<usize> {
+        let mut closest: Option<(usize, f32)> = None;
+        for (start, end, axis, _) in self.lines(center, axes, camera, size) {
+            // the distance from the pixel to the closest point on the line
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let length = dx * dx + dy * dy;
+            let t = if length > 0.0 {
+                (((pixel.0 - start.x) * dx + (pixel.1 - start.y) * dy) / length).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (x, y) = (start.x + dx * t - pixel.0, start.y + dy * t - pixel.1);
+            let distance = f32::sqrt(x * x + y * y);
+            if distance <= Gizmo::GRAB_DISTANCE && closest.is_none_or(|c| distance < c.1) {
+                closest = Some((axis, distance));
+            }
+        }
+        closest.map(|(axis, _)| axis)
+    }
+
+    /// Starts dragging the axis under a pixel.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    /// * `center` - The center of what the Gizmo changes.
+    /// * `axes` - The directions of the arrows.
+    /// * `camera` - The Camera.
+    /// * `pixel` - The position of the mouse in pixels.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// If an axis was grabbed
+    ///
+    pub fn start(
+        &mut self,
+        center: Vec3d,
+        axes: [Vec3d; 3],
+        camera: &Camera,
+        pixel: (f32, f32),
+        size: (f32, f32),
+    ) -> bool {
+        let axis = match self.axis_at(center, axes, camera, pixel, size) {
+            Some(axis) => axis,
+            None => return false,
+        };
+        let pivot = self.pivot(center);
+        let length = (pivot - camera.position).length() * Gizmo::SIZE;
+        let r = camera.rotation_matrix();
+
+        // the corner of the ring closest to the mouse
+        let mut angle = 0.0;
+        let mut closest = f32::INFINITY;
+        for i in 0..Gizmo::RING_SEGMENTS {
+            let point = Gizmo::ring_point(pivot, length, axis, Gizmo::ring_angle(i));
+            let projected = camera.project_point(point, size, r.clone());
+            let (dx, dy) = (projected.x - pixel.0, projected.y - pixel.1);
+            if projected.z > 0.0 && dx * dx + dy * dy < closest {
+                closest = dx * dx + dy * dy;
+                angle = Gizmo::ring_angle(i);
+            }
+        }
+
+        self.drag = Some(GizmoDrag {
+            axis: axis,
+            direction: axes[axis],
+            pivot: pivot,
+            start: pixel,
+            angle: angle,
+            applied: if self.mode == GizmoMode::Scale {
+                1.0
+            } else {
+                0.0
+            },
+        });
+        true
+    }
+
+    /// Follows the mouse while an axis is dragged.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    /// * `camera` - The Camera.
+    /// * `pixel` - The position of the mouse in pixels.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// The change since the mouse last moved or None
+    ///
+    pub fn drag_to(
+        &mut self,
+        camera: &Camera,
+        pixel: (f32, f32),
+        size: (f32, f32),
+    ) -> Option<GizmoChange> {
+        let mut drag = self.drag?;
+        let direction = drag.direction;
+        let pivot = drag.pivot;
+
+        let length = (pivot - camera.position).length() * Gizmo::SIZE;
+        let r = camera.rotation_matrix();
+
+        if self.mode == GizmoMode::Rotate {
+            // how far the mouse moved along the ring where it was grabbed in radians
+            let step = 0.01;
+            let start = camera.project_point(
+                Gizmo::ring_point(pivot, length, drag.axis, drag.angle),
+                size,
+                r.clone(),
+            );
+            let end = camera.project_point(
+                Gizmo::ring_point(pivot, length, drag.axis, drag.angle + step),
+                size,
+                r,
+            );
+            let (dx, dy) = ((end.x - start.x) / step, (end.y - start.y) / step);
+            let screen_length = dx * dx + dy * dy;
+            if !(start.z > 0.0 && end.z > 0.0 && screen_length > 1.0) {
+                return None;
+            }
+            let along = ((pixel.0 - drag.start.0) * dx + (pixel.1 - drag.start.1) * dy)
+                / screen_length
+                * (180.0 / PI);
+
+            let target = self.snapped(along * Gizmo::RING_TURN[drag.axis], self.rotate_step);
+            let change = GizmoChange::Rotate(drag.axis, target - drag.applied, pivot);
+            drag.applied = target;
+            self.drag = Some(drag);
+            return Some(change);
+        }
+
+        // how far the mouse moved along the projected axis in world units
+        let start = camera.project_point(pivot, size, r.clone());
+        let end = camera.project_point(pivot + direction * length, size, r);
+        let (dx, dy) = (end.x - start.x, end.y - start.y);
+        let screen_length = dx * dx + dy * dy;
+        if !(start.z > 0.0 && end.z > 0.0 && screen_length > 1.0) {
+            return None;
+        }
+        let along = ((pixel.0 - drag.start.0) * dx + (pixel.1 - drag.start.1) * dy) / screen_length
+            * length;
+
+        let change = if self.mode == GizmoMode::Move {
+            let target = self.snapped(along, self.move_step);
+            let change = GizmoChange::Move(direction * (target - drag.applied));
+            drag.applied = target;
+            change
+        } else {
+            // dragging the handle one axis length doubles the size
+            let target = self
+                .snapped(1.0 + along / length, self.scale_step)
+                .max(self.scale_step.max(0.01));
+            let ratio = target / drag.applied;
+            let mut factor = Vec3d::new(1.0, 1.0, 1.0);
+            match drag.axis {
+                0 => factor.x = ratio,
+                1 => factor.y = ratio,
+                _ => factor.z = ratio,
+            }
+            drag.applied = target;
+            GizmoChange::Scale(factor, pivot)
+        };
+        self.drag = Some(drag);
+        Some(change)
+    }
+
+    /// Stops dragging.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    ///
+    pub fn end(&mut self) {
+        self.drag = None;
+    }
+
+    /// The color of an axis. The axis being dragged is highlighted.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    /// * `axis` - The axis.
+    ///
+    /// # Return
+    /// The color
+    ///
+    fn color(&self, axis: usize) -> (f32, f32, f32) {
+        match self.drag {
+            Some(drag) if drag.axis == axis => Gizmo::ACTIVE_COLOR,
+            _ => Gizmo::COLORS[axis],
+        }
+    }
+
+    /// Rounds a value to a step if snapping is on.
+    ///
+    /// # Arguments
+    /// * `self` - The Gizmo the function was called for.
+    /// * `value` - The value.
+    /// * `step` - The step.
+    ///
+    /// # Return
+    /// The snapped value
+    ///
+    fn snapped(&self, value: f32, step: f32) -> f32 {
+        if self.snap && step > 0.0 {
+            (value / step).round() * step
+        } else {
+            value
+        }
+    }
+
+    /// The angle of a corner of a ring.
+    ///
+    /// # Arguments
+    /// * `corner` - The index of the corner.
+    ///
+    /// # Return
+    /// The angle in radians
+    ///
+    fn ring_angle(corner: usize) -> f32 {
+        corner as f32 / Gizmo::RING_SEGMENTS as f32 * 2.0 * PI
+    }
+
+    /// A point on the ring around an axis. The angle starts at the next
+    /// axis and turns towards the one after it.
+    ///
+    /// # Arguments
+    /// * `pivot` - The center of the ring.
+    /// * `radius` - The radius of the ring.
+    /// * `axis` - The axis.
+    /// * `angle` - The angle in radians.
+    ///
+    /// # Return
+    /// The point
+    ///
+    fn ring_point(pivot: Vec3d, radius: f32, axis: usize, angle: f32) -> Vec3d {
+        pivot
+            + Gizmo::axis((axis + 1) % 3) * (radius * f32::cos(angle))
+            + Gizmo::axis((axis + 2) % 3) * (radius * f32::sin(angle))
+    }
+}
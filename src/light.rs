@@ -0,0 +1,631 @@
+use crate::{material::Material, mesh::Mesh, shadow::ShadowMap, vec3d::Vec3d};
+
+/// How the Triangles are shaded.
+///  - Flat      one color per Triangle from the surface normal
+///  - Gouraud   colors calculated at the verticies and blended across the Triangle
+///  - Phong     Blinn-Phong lighting calculated for every pixel by the Rasterizer
+#[derive(Copy, Clone, PartialEq)]
+pub enum Shading {
+    Flat,
+    Gouraud,
+    Phong,
+}
+
+/// The kinds of Light.
+///  - Ambient       lights every surface evenly
+///  - Directional   shines from the same direction everywhere (like the sun)
+///  - Point         shines in every direction from a position
+///  - Spot          shines in a cone from a position
+#[derive(Copy, Clone, PartialEq)]
+pub enum LightKind {
+    Ambient,
+    Directional,
+    Point,
+    Spot,
+}
+
+/// How the light of a point or spot Light fades with distance.
+///  - Constant        full strength up to the range
+///  - Linear          fades linearly to nothing at the range
+///  - InverseSquare   physically based falloff smoothly windowed to nothing at the range
+#[derive(Copy, Clone, PartialEq)]
+pub enum Attenuation {
+    Constant,
+    Linear,
+    InverseSquare,
+}
+
+/// A Light shining on the Scene. Positions and directions are in
+/// the same world coordinates as the Meshes and the Camera.
+#[derive(Copy, Clone)]
+pub struct Light {
+    pub kind: LightKind,
+    pub position: Vec3d,
+    pub direction: Vec3d,
+    pub color: (f32, f32, f32),
+    pub intensity: f32,
+    pub range: f32,
+    pub attenuation: Attenuation,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub casts_shadows: bool,
+    pub shadow_bias: f32,
+    pub shadow_pcf_radius: i32,
+}
+
+impl Light {
+    /// Creates a new ambient Light.
+    ///
+    /// # Arguments
+    /// * `color` - The color of the light.
+    /// * `intensity` - How bright the light is.
+    ///
+    /// # Return
+    /// A new Light
+    ///
+    pub fn ambient(color: (f32, f32, f32), intensity: f32) -> Light {
+        Light {
+            kind: LightKind::Ambient,
+            position: Vec3d::new(0.0, 0.0, 0.0),
+            direction: Vec3d::new(0.0, 0.0, 0.0),
+            color: color,
+            intensity: intensity,
+            range: f32::INFINITY,
+            attenuation: Attenuation::Constant,
+            inner_angle: 180.0,
+            outer_angle: 180.0,
+            casts_shadows: false,
+            shadow_bias: 0.05,
+            shadow_pcf_radius: 1,
+        }
+    }
+
+    /// Creates a new directional Light.
+    ///
+    /// # Arguments
+    /// * `direction` - The direction the light is travelling in.
+    /// * `color` - The color of the light.
+    /// * `intensity` - How bright the light is.
+    ///
+    /// # Return
+    /// A new Light
+    ///
+    pub fn directional(mut direction: Vec3d, color: (f32, f32, f32), intensity: f32) -> Light {
+        Light {
+            kind: LightKind::Directional,
+            position: Vec3d::new(0.0, 0.0, 0.0),
+            direction: direction.normalize(),
+            color: color,
+            intensity: intensity,
+            range: f32::INFINITY,
+            attenuation: Attenuation::Constant,
+            inner_angle: 180.0,
+            outer_angle: 180.0,
+            casts_shadows: false,
+            shadow_bias: 0.05,
+            shadow_pcf_radius: 1,
+        }
+    }
+
+    /// Creates a new point Light.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the light.
+    /// * `color` - The color of the light.
+    /// * `intensity` - How bright the light is.
+    /// * `range` - The distance after which the light has no effect.
+    /// * `attenuation` - How the light fades with distance.
+    ///
+    /// # Return
+    /// A new Light
+    ///
+    pub fn point(
+        position: Vec3d,
+        color: (f32, f32, f32),
+        intensity: f32,
+        range: f32,
+        attenuation: Attenuation,
+    ) -> Light {
+        Light {
+            kind: LightKind::Point,
+            position: position,
+            direction: Vec3d::new(0.0, 0.0, 0.0),
+            color: color,
+            intensity: intensity,
+            range: range,
+            attenuation: attenuation,
+            inner_angle: 180.0,
+            outer_angle: 180.0,
+            casts_shadows: false,
+            shadow_bias: 0.05,
+            shadow_pcf_radius: 1,
+        }
+    }
+
+    /// Creates a new spot Light. Inside the inner angle the light has full
+    /// strength and it fades to nothing at the outer angle.
+    ///
+    /// # Arguments
+    /// * `position` - The position of the light.
+    /// * `direction` - The direction the light is pointing in.
+    /// * `color` - The color of the light.
+    /// * `intensity` - How bright the light is.
+    /// * `range` - The distance after which the light has no effect.
+    /// * `attenuation` - How the light fades with distance.
+    /// * `inner_angle` - The angle in degrees between the direction and the edge of the full strength cone.
+    /// * `outer_angle` - The angle in degrees between the direction and the edge of the cone.
+    ///
+    /// # Return
+    /// A new Light
+    ///
+    pub fn spot(
+        position: Vec3d,
+        mut direction: Vec3d,
+        color: (f32, f32, f32),
+        intensity: f32,
+        range: f32,
+        attenuation: Attenuation,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Light {
+        Light {
+            kind: LightKind::Spot,
+            position: position,
+            direction: direction.normalize(),
+            color: color,
+            intensity: intensity,
+            range: range,
+            attenuation: attenuation,
+            inner_angle: inner_angle,
+            outer_angle: outer_angle,
+            casts_shadows: false,
+            shadow_bias: 0.05,
+            shadow_pcf_radius: 1,
+        }
+    }
+
+    /// Makes a directional or spot Light cast shadows.
+    ///
+    /// # Arguments
+    /// * `self` - The Light the function was called for.
+    /// * `bias` - How much closer to the light a surface has to be to
+    ///            shadow a point. Stops surfaces from shadowing themselves.
+    /// * `pcf_radius` - How many shadow map pixels around a point are
+    ///                  sampled to soften the edges of the shadow.
+    ///
+    pub fn cast_shadows(&mut self, bias: f32, pcf_radius: i32) {
+        self.casts_shadows = self.kind == LightKind::Directional || self.kind == LightKind::Spot;
+        self.shadow_bias = bias;
+        self.shadow_pcf_radius = pcf_radius;
+    }
+
+    /// Calculates the direction from a point towards the Light and how
+    /// strong the Light is at that point after attenuation and the cone
+    /// of a spot Light.
+    ///
+    /// # Arguments
+    /// * `self` - The Light the function was called for.
+    /// * `position` - The position of the point in the world.
+    ///
+    /// # Return
+    /// The direction to the light and its strength
+    ///
+    pub fn incident(&self, position: Vec3d) -> (Vec3d, f32) {
+        match self.kind {
+            LightKind::Ambient => (Vec3d::new(0.0, 0.0, 0.0), self.intensity),
+            LightKind::Directional => (self.direction * -1.0, self.intensity),
+            LightKind::Point | LightKind::Spot => {
+                let mut to_light = self.position - position;
+                let dist = to_light.length();
+                if dist >= self.range || dist == 0.0 {
+                    return (Vec3d::new(0.0, 0.0, 0.0), 0.0);
+                }
+                to_light.normalize();
+
+                let mut strength = self.intensity * self.attenuate(dist);
+                if self.kind == LightKind::Spot {
+                    strength *= self.cone(to_light * -1.0);
+                }
+                (to_light, strength)
+            }
+        }
+    }
+
+    /// Calculates how much of the Light is left after a distance.
+    ///
+    /// # Arguments
+    /// * `self` - The Light the function was called for.
+    /// * `dist` - The distance from the Light.
+    ///
+    /// # Return
+    /// A factor between 0 and 1
+    ///
+    pub fn attenuate(&self, dist: f32) -> f32 {
+        match self.attenuation {
+            Attenuation::Constant => 1.0,
+            Attenuation::Linear => f32::max(0.0, 1.0 - dist / self.range),
+            Attenuation::InverseSquare => {
+                let ratio = dist / self.range;
+                let window = f32::max(0.0, 1.0 - ratio * ratio * ratio * ratio);
+                window * window / (1.0 + dist * dist)
+            }
+        }
+    }
+
+    /// Calculates how much of a spot Light goes in a direction. Smoothly
+    /// fades from the inner angle to the outer angle.
+    ///
+    /// # Arguments
+    /// * `self` - The Light the function was called for.
+    /// * `dir` - The direction away from the Light.
+    ///
+    /// # Return
+    /// A factor between 0 and 1
+    ///
+    pub fn cone(&self, dir: Vec3d) -> f32 {
+        let cos_theta = dir.dot(self.direction);
+        let cos_inner = f32::cos(self.inner_angle.to_radians());
+        let cos_outer = f32::cos(self.outer_angle.to_radians());
+        if cos_inner <= cos_outer {
+            return if cos_theta >= cos_outer { 1.0 } else { 0.0 };
+        }
+        let t = (cos_theta - cos_outer) / (cos_inner - cos_outer);
+        let t = t.clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /// Shades a color with all the Lights hitting a surface using
+    /// the Lambertian term max(0, n·l) for each Light.
+    ///
+    /// # Arguments
+    /// * `lights` - The Lights.
+    /// * `position` - The position of the point in the world.
+    /// * `normal` - The surface normal.
+    /// * `base` - The color of the surface.
+    /// * `shadows` - The ShadowMaps of the Lights, in the same order as the Lights.
+    ///
+    /// # Return
+    /// The shaded color
+    ///
+    pub fn flat_shade(
+        lights: &[Light],
+        position: Vec3d,
+        normal: Vec3d,
+        base: (f32, f32, f32),
+        shadows: &[Option<ShadowMap>],
+    ) -> (f32, f32, f32) {
+        let mut sum = (0.0, 0.0, 0.0);
+        for i in 0..lights.len() {
+            let light = &lights[i];
+            let (to_light, mut strength) = light.incident(position);
+            if let Some(Some(shadow)) = shadows.get(i) {
+                if strength > 0.0 {
+                    strength *= shadow.visibility(position);
+                }
+            }
+            let diffuse = if light.kind == LightKind::Ambient {
+                strength
+            } else {
+                f32::max(0.0, normal.dot(to_light)) * strength
+            };
+            sum.0 += light.color.0 * diffuse;
+            sum.1 += light.color.1 * diffuse;
+            sum.2 += light.color.2 * diffuse;
+        }
+        (
+            f32::min(1.0, base.0 * sum.0),
+            f32::min(1.0, base.1 * sum.1),
+            f32::min(1.0, base.2 * sum.2),
+        )
+    }
+
+    /// Shades a point on a surface with all the Lights using the Blinn-Phong
+    /// model. The specular highlight uses the half vector between the
+    /// direction to the light and the direction to the viewer.
+    ///
+    /// # Arguments
+    /// * `lights` - The Lights.
+    /// * `position` - The position of the point in the world.
+    /// * `normal` - The surface normal at the point.
+    /// * `eye` - The position of the viewer (the Camera).
+    /// * `base` - The color of the surface.
+    /// * `material` - The Material of the surface.
+    /// * `shadows` - The ShadowMaps of the Lights, in the same order as the Lights.
+    ///
+    /// # Return
+    /// The shaded color
+    ///
+    pub fn blinn_phong(
+        lights: &[Light],
+        position: Vec3d,
+        normal: Vec3d,
+        eye: Vec3d,
+        base: (f32, f32, f32),
+        material: &Material,
+        shadows: &[Option<ShadowMap>],
+    ) -> (f32, f32, f32) {
+        let view = (eye - position).normalize();
+        let mut diffuse_sum = (0.0, 0.0, 0.0);
+        let mut specular_sum = (0.0, 0.0, 0.0);
+        for i in 0..lights.len() {
+            let light = &lights[i];
+            let (to_light, mut strength) = light.incident(position);
+            if let Some(Some(shadow)) = shadows.get(i) {
+                if strength > 0.0 {
+                    strength *= shadow.visibility(position);
+                }
+            }
+            if light.kind == LightKind::Ambient {
+                diffuse_sum.0 += light.color.0 * strength;
+                diffuse_sum.1 += light.color.1 * strength;
+                diffuse_sum.2 += light.color.2 * strength;
+                continue;
+            }
+
+            let diffuse = f32::max(0.0, normal.dot(to_light)) * strength;
+            if diffuse <= 0.0 {
+                continue;
+            }
+            diffuse_sum.0 += light.color.0 * diffuse;
+            diffuse_sum.1 += light.color.1 * diffuse;
+            diffuse_sum.2 += light.color.2 * diffuse;
+
+            let mut half = view + to_light;
+            if half.length() != 0.0 {
+                let n_dot_h = f32::max(0.0, normal.dot(half.normalize()));
+                let specular = n_dot_h.powf(material.shininess) * strength;
+                specular_sum.0 += light.color.0 * specular;
+                specular_sum.1 += light.color.1 * specular;
+                specular_sum.2 += light.color.2 * specular;
+            }
+        }
+        (
+            f32::min(
+                1.0,
+                base.0 * diffuse_sum.0 + material.specular.0 * specular_sum.0,
+            ),
+            f32::min(
+                1.0,
+                base.1 * diffuse_sum.1 + material.specular.1 * specular_sum.1,
+            ),
+            f32::min(
+                1.0,
+                base.2 * diffuse_sum.2 + material.specular.2 * specular_sum.2,
+            ),
+        )
+    }
+
+    /// Adds the light given off by a surface to its shaded color.
+    ///
+    /// # Arguments
+    /// * `lit` - The shaded color.
+    /// * `emission` - The emitted color.
+    ///
+    /// # Return
+    /// The final color
+    ///
+    pub fn add_emission(lit: (f32, f32, f32), emission: (f32, f32, f32)) -> (f32, f32, f32) {
+        (
+            f32::min(1.0, lit.0 + emission.0),
+            f32::min(1.0, lit.1 + emission.1),
+            f32::min(1.0, lit.2 + emission.2),
+        )
+    }
+
+    /// Flat shades every Triangle of a Mesh using its surface normal.
+    /// The Material is sampled at the center of each Triangle.
+    ///
+    /// # Arguments
+    /// * `lights` - The Lights.
+    /// * `mesh` - The Mesh being shaded, already moved into the world.
+    /// * `shadows` - The ShadowMaps of the Lights, in the same order as the Lights.
+    ///
+    pub fn shade_mesh(lights: &[Light], mesh: &mut Mesh, shadows: &[Option<ShadowMap>]) {
+        let third = (1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0);
+        for i in 0..mesh.tris.len() {
+            let tri = mesh.tris[i];
+            let material = &mesh.materials[tri.material];
+            let uv = tri.interpolate_uv(third);
+            let base = material.albedo(tri.interpolate_color(third), uv, 0.0);
+            let lit = Light::flat_shade(lights, tri.center, tri.normal, base, shadows);
+            mesh.tris[i].set_color(Light::add_emission(lit, material.emission(uv, 0.0)));
+        }
+    }
+
+    /// Shades the verticies of every Triangle of a Mesh using the vertex
+    /// normals. The colors are blended across each Triangle when drawn.
+    /// Textures are left to be multiplied in when drawing.
+    ///
+    /// # Arguments
+    /// * `lights` - The Lights.
+    /// * `mesh` - The Mesh being shaded, already moved into the world.
+    /// * `shadows` - The ShadowMaps of the Lights, in the same order as the Lights.
+    ///
+    pub fn gouraud_shade_mesh(lights: &[Light], mesh: &mut Mesh, shadows: &[Option<ShadowMap>]) {
+        for i in 0..mesh.tris.len() {
+            let tri = mesh.tris[i];
+            let material = &mesh.materials[tri.material];
+            let shade = |position: Vec3d, normal: Vec3d, color: (f32, f32, f32)| {
+                Light::add_emission(
+                    Light::flat_shade(lights, position, normal, material.tint(color), shadows),
+                    material.emissive,
+                )
+            };
+            mesh.tris[i].vertex_colors = (
+                shade(tri.verticies.0, tri.vertex_normals.0, tri.vertex_colors.0),
+                shade(tri.verticies.1, tri.vertex_normals.1, tri.vertex_colors.1),
+                shade(tri.verticies.2, tri.vertex_normals.2, tri.vertex_colors.2),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        light::{Attenuation, Light},
+        material::Material,
+        mesh::Mesh,
+        vec3d::Vec3d,
+    };
+    use std::f32::consts::FRAC_PI_3;
+
+    fn close(a: (f32, f32, f32), b: (f32, f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4 && (a.2 - b.2).abs() < 1e-4
+    }
+
+    #[test]
+    fn flat_shade_follows_the_normal() {
+        let sun = Light::directional(Vec3d::new(0.0, -1.0, 0.0), (1.0, 1.0, 1.0), 1.0);
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        let base = (0.5, 0.4, 0.2);
+        let up = Light::flat_shade(&[sun], origin, Vec3d::new(0.0, 1.0, 0.0), base, &[]);
+        assert!(close(up, base));
+        let down = Light::flat_shade(&[sun], origin, Vec3d::new(0.0, -1.0, 0.0), base, &[]);
+        assert!(close(down, (0.0, 0.0, 0.0)));
+        // 60 degrees away from the light gets half of it
+        let mut tilted = Vec3d::new(f32::sin(FRAC_PI_3), f32::cos(FRAC_PI_3), 0.0);
+        let half = Light::flat_shade(&[sun], origin, tilted.normalize(), base, &[]);
+        assert!(close(half, (0.25, 0.2, 0.1)));
+    }
+
+    #[test]
+    fn ambient_lights_every_side() {
+        let ambient = Light::ambient((1.0, 1.0, 1.0), 0.25);
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        let base = (1.0, 1.0, 1.0);
+        let down = Light::flat_shade(&[ambient], origin, Vec3d::new(0.0, -1.0, 0.0), base, &[]);
+        assert!(close(down, (0.25, 0.25, 0.25)));
+    }
+
+    #[test]
+    fn shade_mesh_lights_the_top_of_a_cube() {
+        let sun = Light::directional(Vec3d::new(0.0, -1.0, 0.0), (1.0, 1.0, 1.0), 1.0);
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        for tri in mesh.tris.iter_mut() {
+            tri.set_color((1.0, 1.0, 1.0));
+        }
+        mesh.materials[0].base_color = (1.0, 1.0, 1.0, 1.0);
+        Light::shade_mesh(&[sun], &mut mesh, &[]);
+        for tri in mesh.tris.iter() {
+            let color = tri.interpolate_color((1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0));
+            if tri.normal.y > 0.5 {
+                assert!(close(color, (1.0, 1.0, 1.0)));
+            } else {
+                assert!(close(color, (0.0, 0.0, 0.0)));
+            }
+        }
+    }
+
+    #[test]
+    fn gouraud_shades_each_vertex_by_its_normal() {
+        let sun = Light::directional(Vec3d::new(0.0, -1.0, 0.0), (1.0, 1.0, 1.0), 1.0);
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_icosphere(1.0, 2, false);
+        for tri in mesh.tris.iter_mut() {
+            tri.set_color((1.0, 1.0, 1.0));
+        }
+        mesh.materials[0].base_color = (1.0, 1.0, 1.0, 1.0);
+        mesh.calculate_vertex_normals(180.0);
+        Light::gouraud_shade_mesh(&[sun], &mut mesh, &[]);
+        for tri in mesh.tris.iter() {
+            let verticies = [tri.verticies.0, tri.verticies.1, tri.verticies.2];
+            let colors = [
+                tri.vertex_colors.0,
+                tri.vertex_colors.1,
+                tri.vertex_colors.2,
+            ];
+            for k in 0..3 {
+                // the smooth normal of a sphere points away from its center
+                let expected = f32::max(0.0, verticies[k].y);
+                assert!((colors[k].0 - expected).abs() < 0.05);
+            }
+        }
+    }
+
+    #[test]
+    fn blinn_phong_highlight_follows_the_half_vector() {
+        let sun = Light::directional(Vec3d::new(0.0, -1.0, 0.0), (1.0, 1.0, 1.0), 1.0);
+        let material = Material::new((1.0, 1.0, 1.0), 32.0);
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        let up = Vec3d::new(0.0, 1.0, 0.0);
+        let black = (0.0, 0.0, 0.0);
+        // looking straight down the reflection gets the full highlight
+        let eye = Vec3d::new(0.0, 5.0, 0.0);
+        let lit = Light::blinn_phong(&[sun], origin, up, eye, black, &material, &[]);
+        assert!(close(lit, (1.0, 1.0, 1.0)));
+        // a grazing view gets almost none
+        let eye = Vec3d::new(5.0, 0.5, 0.0);
+        let lit = Light::blinn_phong(&[sun], origin, up, eye, black, &material, &[]);
+        assert!(lit.0 < 0.01);
+        // a surface facing away gets no highlight at all
+        let lit = Light::blinn_phong(&[sun], origin, up * -1.0, eye, black, &material, &[]);
+        assert!(close(lit, black));
+    }
+
+    #[test]
+    fn blinn_phong_diffuse_matches_flat_shading() {
+        let sun = Light::directional(Vec3d::new(1.0, -1.0, 0.0), (1.0, 1.0, 1.0), 1.0);
+        let material = Material::new((0.0, 0.0, 0.0), 32.0);
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        let up = Vec3d::new(0.0, 1.0, 0.0);
+        let base = (0.6, 0.6, 0.6);
+        let eye = Vec3d::new(0.0, 5.0, 5.0);
+        let phong = Light::blinn_phong(&[sun], origin, up, eye, base, &material, &[]);
+        assert!(close(
+            phong,
+            Light::flat_shade(&[sun], origin, up, base, &[])
+        ));
+    }
+
+    #[test]
+    fn attenuation_fades_to_nothing_at_the_range() {
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        let white = (1.0, 1.0, 1.0);
+        for attenuation in [
+            Attenuation::Constant,
+            Attenuation::Linear,
+            Attenuation::InverseSquare,
+        ]
+        .iter()
+        {
+            let light = Light::point(origin, white, 1.0, 10.0, *attenuation);
+            let mut last = light.attenuate(0.0);
+            assert!((last - 1.0).abs() < 1e-6);
+            for step in 1..10 {
+                let strength = light.attenuate(step as f32);
+                assert!(strength <= last);
+                last = strength;
+            }
+            assert_eq!(light.incident(Vec3d::new(0.0, 10.0, 0.0)).1, 0.0);
+        }
+        let linear = Light::point(origin, white, 1.0, 10.0, Attenuation::Linear);
+        assert!((linear.attenuate(5.0) - 0.5).abs() < 1e-6);
+        let inverse_square = Light::point(origin, white, 1.0, 1000.0, Attenuation::InverseSquare);
+        assert!((inverse_square.attenuate(3.0) - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn spot_light_fades_between_its_angles() {
+        let light = Light::spot(
+            Vec3d::new(0.0, 0.0, 0.0),
+            Vec3d::new(0.0, -1.0, 0.0),
+            (1.0, 1.0, 1.0),
+            1.0,
+            100.0,
+            Attenuation::Constant,
+            10.0,
+            30.0,
+        );
+        let at = |degrees: f32| {
+            let radians = degrees.to_radians();
+            light.cone(Vec3d::new(f32::sin(radians), -f32::cos(radians), 0.0))
+        };
+        assert!((at(0.0) - 1.0).abs() < 1e-6);
+        assert!((at(10.0) - 1.0).abs() < 1e-4);
+        assert!(at(20.0) > 0.0 && at(20.0) < 1.0);
+        assert!(at(30.0) < 1e-4);
+        assert_eq!(at(45.0), 0.0);
+    }
+}
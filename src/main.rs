@@ -1,18 +1,55 @@
+// the engine keeps these styles throughout
+#![allow(
+    clippy::assign_op_pattern,
+    clippy::doc_overindented_list_items,
+    clippy::excessive_precision,
+    clippy::len_zero,
+    clippy::needless_range_loop,
+    clippy::neg_cmp_op_on_partial_ord,
+    clippy::ptr_arg,
+    clippy::redundant_field_names,
+    clippy::too_many_arguments,
+    clippy::type_complexity,
+    clippy::wrong_self_convention
+)]
+
+mod bounds;
+mod bvh;
 mod camera;
+mod color_mode;
+mod gizmo;
+mod light;
+mod material;
 mod matrix3x3;
 mod mesh;
+mod primitives;
+mod rasterizer;
 mod scene;
+mod shadow;
+mod simplify;
+mod texture;
+mod transform;
 mod triangle;
+mod validation;
 mod vec3d;
+mod vertex_editor;
+mod weld;
 
 use camera::Camera;
+use color_mode::ColorMode;
+use gizmo::GizmoMode;
+use light::{Light, Shading};
 use mesh::Mesh;
-use scene::Scene;
+use scene::{RenderMode, Scene};
+use texture::Texture;
+use triangle::Triangle;
 use vec3d::Vec3d;
 
+use std::{cmp::Ordering, sync::Arc};
+
 use ggez::{
-    self, event, event::KeyCode, graphics, graphics::Vertex, input::keyboard, nalgebra as na,
-    timer::delta,
+    self, event, event::KeyCode, graphics, graphics::Vertex, input::keyboard, input::mouse,
+    nalgebra as na, timer::delta,
 };
 
 /// Event Handler for a Mesh
@@ -36,6 +73,42 @@ impl event::EventHandler for Scene {
     ///  - D_KEY        Move Right
     ///  - A_KEY        Move Left
     ///
+    ///  - F1_KEY       Flat shading
+    ///  - F2_KEY       Smooth (Gouraud) shading
+    ///  - F3_KEY       Per pixel (Blinn-Phong) shading
+    ///
+    ///  - 1_KEY        Shaded Materials
+    ///  - 2_KEY        Vertex color palette without lighting
+    ///  - 3_KEY        Color by normal direction
+    ///  - 4_KEY        Color by height
+    ///  - 5_KEY        Color by distance to the camera
+    ///  - 6_KEY        Color by face index
+    ///  - 7_KEY        One uniform color
+    ///
+    ///  - F5_KEY       Filled triangles
+    ///  - F6_KEY       Wireframe
+    ///  - F7_KEY       Hidden lines
+    ///
+    ///  - X_KEY        Toggle x-ray (see key_down_event)
+    ///  - P_KEY        Toggle point mode (see key_down_event)
+    ///  - B_KEY        Toggle back-face culling (see key_down_event)
+    ///
+    ///  - MOUSE        Hover over a Mesh to highlight it
+    ///  - LEFT_MOUSE   Select the Mesh under the mouse (see mouse_button_down_event)
+    ///                 -> drag an axis of the Gizmo to change the selected Mesh
+    ///  - T_KEY        Move Gizmo (see key_down_event)
+    ///  - R_KEY        Rotate Gizmo (see key_down_event)
+    ///  - E_KEY        Scale Gizmo (see key_down_event)
+    ///  - N_KEY        Toggle snapping (see key_down_event)
+    ///  - V_KEY        Toggle vertex mode (see key_down_event)
+    ///                 -> click or drag a box to select verticies, CTRL adds
+    ///                 -> drag a selected vertex to move the selection
+    ///  - M_KEY        Midpoint subdivide the selection (see key_down_event)
+    ///  - L_KEY        Loop subdivide the selection (see key_down_event)
+    ///  - H_KEY        Halve the triangles of the selected Mesh (see key_down_event)
+    ///  - I_KEY        Validate the selected Mesh (see key_down_event)
+    ///  - K_KEY        Apply the Gizmo changes to the verticies (see key_down_event)
+    ///
     /// NOTE: Controls may be subject to change
     ///
     /// # Arguments
@@ -81,16 +154,195 @@ impl event::EventHandler for Scene {
             self.camera.position =
                 self.camera.position + Vec3d::new(0.0, 1.0, 0.0).set_length(time_factor);
         }
-        if keyboard::is_key_pressed(ctx, KeyCode::LShift)
-            || keyboard::is_key_pressed(ctx, KeyCode::LShift)
-        {
+        if keyboard::is_key_pressed(ctx, KeyCode::LShift) {
             self.camera.position =
                 self.camera.position - Vec3d::new(0.0, 1.0, 0.0).set_length(time_factor);
         }
 
+        if keyboard::is_key_pressed(ctx, KeyCode::F1) {
+            self.shading = Shading::Flat;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::F2) {
+            self.shading = Shading::Gouraud;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::F3) {
+            self.shading = Shading::Phong;
+        }
+
+        if keyboard::is_key_pressed(ctx, KeyCode::Key1) {
+            self.color_mode = ColorMode::Shaded;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Key2) {
+            self.color_mode = ColorMode::Palette;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Key3) {
+            self.color_mode = ColorMode::Normal;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Key4) {
+            self.color_mode = ColorMode::Height {
+                axis: Vec3d::new(0.0, 1.0, 0.0),
+                ramp: ColorMode::default_ramp(),
+            };
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Key5) {
+            self.color_mode = ColorMode::Distance {
+                ramp: ColorMode::default_ramp(),
+            };
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Key6) {
+            self.color_mode = ColorMode::FaceIndex { seed: 1 };
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Key7) {
+            self.color_mode = ColorMode::Uniform((0.8, 0.8, 0.8));
+        }
+
+        if keyboard::is_key_pressed(ctx, KeyCode::F5) {
+            self.render_mode = RenderMode::Filled;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::F6) {
+            self.render_mode = RenderMode::Wireframe;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::F7) {
+            self.render_mode = RenderMode::HiddenLine;
+        }
+
+        // the mesh under the mouse changes when the camera moves as well
+        let mouse = mouse::position(ctx);
+        self.hover((mouse.x, mouse.y), graphics::drawable_size(ctx));
+
         Ok(())
     }
 
+    /// Grabs the Gizmo or selects the Mesh under the mouse and holds it
+    /// while the left button is down. In vertex mode the verticies are
+    /// selected instead and holding CTRL adds to the selection.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene
+    /// * `ctx` - GGez's Context
+    /// * `button` - The mouse button that was pressed
+    /// * `x` - The x position of the mouse
+    /// * `y` - The y position of the mouse
+    ///
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut ggez::Context,
+        button: event::MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        if button == event::MouseButton::Left {
+            let add = keyboard::active_mods(ctx).contains(event::KeyMods::CTRL);
+            self.press((x, y), graphics::drawable_size(ctx), add);
+        }
+    }
+
+    /// Drags the Gizmo, the selected verticies or the selection box.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene
+    /// * `ctx` - GGez's Context
+    /// * `x` - The x position of the mouse
+    /// * `y` - The y position of the mouse
+    /// * `_dx` - How far the mouse moved along x
+    /// * `_dy` - How far the mouse moved along y
+    ///
+    fn mouse_motion_event(&mut self, ctx: &mut ggez::Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        self.drag((x, y), graphics::drawable_size(ctx));
+    }
+
+    /// Lets go of the held Mesh, the Gizmo and the verticies.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene
+    /// * `ctx` - GGez's Context
+    /// * `button` - The mouse button that was released
+    /// * `_x` - The x position of the mouse
+    /// * `_y` - The y position of the mouse
+    ///
+    fn mouse_button_up_event(
+        &mut self,
+        ctx: &mut ggez::Context,
+        button: event::MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+        if button == event::MouseButton::Left {
+            self.release(graphics::drawable_size(ctx));
+        }
+    }
+
+    /// Handles keys that toggle something once per press.
+    ///  - ESCAPE_KEY   Quit
+    ///  - X_KEY        Toggle x-ray on every Mesh
+    ///  - P_KEY        Toggle point mode on every Mesh
+    ///  - B_KEY        Toggle back-face culling on every Mesh
+    ///  - T_KEY        The Gizmo moves the selected Mesh
+    ///  - R_KEY        The Gizmo rotates the selected Mesh
+    ///  - E_KEY        The Gizmo scales the selected Mesh
+    ///  - N_KEY        Toggle snapping of the Gizmo to steps
+    ///  - V_KEY        Toggle editing the verticies of the selected Mesh
+    ///  - M_KEY        Split the faces of the selected Mesh in four
+    ///  - L_KEY        Split and smooth the faces of the selected Mesh
+    ///  - H_KEY        Simplify the selected Mesh to half its Triangles
+    ///  - I_KEY        Show the problems and the MeshReport of the selected Mesh
+    ///  - K_KEY        Apply the Transform of the selected Mesh to its verticies
+    ///
+    /// # Arguments
+    /// * `self` - The Scene
+    /// * `ctx` - GGez's Context
+    /// * `keycode` - The key that was pressed
+    /// * `_keymods` - The modifier keys held down
+    /// * `repeat` - If the key is being held down
+    ///
+    fn key_down_event(
+        &mut self,
+        ctx: &mut ggez::Context,
+        keycode: KeyCode,
+        _keymods: event::KeyMods,
+        repeat: bool,
+    ) {
+        if keycode == KeyCode::Escape {
+            event::quit(ctx);
+        }
+        if repeat {
+            return;
+        }
+        if keycode == KeyCode::X {
+            let x_ray = !self.mesh_vec.iter().any(|mesh| mesh.x_ray);
+            for mesh in self.mesh_vec.iter_mut() {
+                mesh.x_ray = x_ray;
+            }
+        }
+        if keycode == KeyCode::P {
+            let point_mode = !self.mesh_vec.iter().any(|mesh| mesh.point_mode);
+            for mesh in self.mesh_vec.iter_mut() {
+                mesh.set_point_mode(point_mode);
+            }
+        }
+        if keycode == KeyCode::B {
+            let cull = !self.mesh_vec.iter().any(|mesh| mesh.cull_back_faces);
+            for mesh in self.mesh_vec.iter_mut() {
+                mesh.cull_back_faces = cull;
+            }
+        }
+        if self.gizmo.drag.is_none() {
+            match keycode {
+                KeyCode::T => self.gizmo.mode = GizmoMode::Move,
+                KeyCode::R => self.gizmo.mode = GizmoMode::Rotate,
+                KeyCode::E => self.gizmo.mode = GizmoMode::Scale,
+                KeyCode::N => self.gizmo.snap = !self.gizmo.snap,
+                KeyCode::V => self.toggle_vertex_mode(),
+                KeyCode::M => self.subdivide_selected(false),
+                KeyCode::L => self.subdivide_selected(true),
+                KeyCode::H => self.simplify_selected(0.5),
+                KeyCode::I => self.validate_selected(),
+                KeyCode::K => self.apply_transform_selected(),
+                _ => (),
+            }
+        }
+    }
+
     /// Project the mesh and render it.
     ///
     /// # Arguments
@@ -101,52 +353,306 @@ impl event::EventHandler for Scene {
     /// A GameResult
     ///
     fn draw(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
-        graphics::clear(ctx, [0.1, 0.2, 0.3, 1.0].into()); // clear
+        let background = self.background;
+        graphics::clear(ctx, [background.0, background.1, background.2, 1.0].into()); // clear
 
         let size: (f32, f32) = ggez::graphics::drawable_size(ctx);
 
+        let points = self.mesh_vec.iter().any(|mesh| mesh.point_mode);
+        if self.render_mode == RenderMode::Wireframe && !points {
+            let color = self.line_color;
+            let mut builder = graphics::MeshBuilder::new();
+            let mut count = 0;
+            let visible = self.visible_meshes(size);
+            for (mut start, mut end) in self.project_edges(size, &visible) {
+                if (end - start).length() < 0.01 {
+                    continue;
+                }
+                builder.line(
+                    &[start.form_point2(), end.form_point2()],
+                    self.line_width,
+                    graphics::Color::new(color.0, color.1, color.2, 1.0),
+                )?;
+                count += 1;
+            }
+            if count > 0 {
+                let lines = builder.build(ctx)?;
+                graphics::draw(ctx, &lines, (na::Point2::new(0.0, 0.0),))?;
+            }
+            self.draw_overlay(ctx, size)?;
+
+            self.draw_hud(ctx)?;
+            graphics::present(ctx)?;
+            return Ok(());
+        }
+
+        if self.shading == Shading::Phong
+            || !self.color_mode.is_shaded()
+            || self.render_mode == RenderMode::HiddenLine
+            || self.render_mode == RenderMode::Wireframe
+            || points
+        {
+            // Light every pixel on the CPU and draw the result as an image.
+            // The other color modes need no lighting and hidden lines and points
+            // need the depth of every pixel so they are drawn the same way.
+            let rasterizer = self.rasterize(size);
+            let image = graphics::Image::from_rgba8(
+                ctx,
+                rasterizer.width as u16,
+                rasterizer.height as u16,
+                &rasterizer.to_rgba8(),
+            )?;
+            graphics::draw(ctx, &image, (na::Point2::new(0.0, 0.0),))?;
+
+            self.draw_hud(ctx)?;
+            graphics::present(ctx)?;
+            return Ok(());
+        }
+
+        self.update_shadows();
+        let visible = self.visible_meshes(size);
+        let mut transparent: Vec<(usize, Triangle)> = vec![];
         for i in 0..self.mesh_vec.len() {
-            self.mesh_vec[i].is_over = false;
-            // Get the projected triangles.
-            let tris = self
-                .camera
-                .get_projected_triangles(self.mesh_vec[i].clone(), size);
-
-            let mut raw: Vec<Vertex> = vec![];
-            // Draw all the triangles
-            for j in 0..tris.len() {
-                let mut tri = tris[j];
+            if !visible[i] {
+                continue;
+            }
+            // Light the mesh.
+            let mut mesh = self.mesh_vec[i].clone();
+            mesh.use_lod();
+            mesh.apply_transform();
+            if self.shading == Shading::Flat {
+                Light::shade_mesh(&self.lights, &mut mesh, &self.shadows);
+            } else {
+                Light::gouraud_shade_mesh(&self.lights, &mut mesh, &self.shadows);
+            }
+            for tri in mesh.tris.iter_mut() {
+                let (c0, c1, c2) = tri.vertex_colors;
+                tri.vertex_colors = (
+                    self.highlight(i, c0),
+                    self.highlight(i, c1),
+                    self.highlight(i, c2),
+                );
+            }
+
+            // Get the projected triangles. The opaque ones come first.
+            let tris = self.camera.get_projected_triangles(&mesh, size);
+            let opaque = tris.iter().take_while(|tri| tri.alpha >= 1.0).count();
+
+            // Draw the opaque triangles and keep the transparent ones for later
+            let opaque_tris: Vec<(usize, Triangle)> =
+                tris[..opaque].iter().map(|tri| (i, *tri)).collect();
+            self.draw_tris(ctx, &opaque_tris)?;
+            transparent.extend(tris[opaque..].iter().map(|tri| (i, *tri)));
+        }
+
+        // Blend the transparent triangles of all the meshes from back to front.
+        transparent.sort_by(|a, b| b.1.dist.partial_cmp(&a.1.dist).unwrap_or(Ordering::Equal));
+        self.draw_tris(ctx, &transparent)?;
+        self.draw_overlay(ctx, size)?;
+        self.draw_hud(ctx)?;
+
+        graphics::present(ctx)?;
+        Ok(())
+    }
+}
+
+impl Scene {
+    /// Draw the handles of the verticies, the Gizmo and the selection
+    /// box on top of everything else.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene being drawn
+    /// * `ctx` - GGez's Context
+    /// * `size` - The dimensions of the screen
+    ///
+    /// # Return
+    /// A GameResult
+    ///
+    fn draw_overlay(&self, ctx: &mut ggez::Context, size: (f32, f32)) -> ggez::GameResult {
+        let (lines, handles) = self.project_overlay(size);
+        if lines.len() == 0 && handles.len() == 0 {
+            return Ok(());
+        }
+        let mut builder = graphics::MeshBuilder::new();
+        for (center, width, color) in handles {
+            let half = width * 0.5;
+            builder.rectangle(
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(center.x - half, center.y - half, width, width),
+                graphics::Color::new(color.0, color.1, color.2, 1.0),
+            );
+        }
+        for (mut start, mut end, width, color) in lines {
+            if (end - start).length() < 0.01 {
+                continue;
+            }
+            builder.line(
+                &[start.form_point2(), end.form_point2()],
+                width,
+                graphics::Color::new(color.0, color.1, color.2, 1.0),
+            )?;
+        }
+        let overlay = builder.build(ctx)?;
+        graphics::draw(ctx, &overlay, (na::Point2::new(0.0, 0.0),))
+    }
+
+    /// Write how many meshes are drawn and how many the Camera culled
+    /// in the corner of the screen, and the summary of the MeshReport
+    /// below it while the problems of a Mesh are shown.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene being drawn
+    /// * `ctx` - GGez's Context
+    ///
+    /// # Return
+    /// A GameResult
+    ///
+    fn draw_hud(&self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let mut hud = format!(
+            "meshes: {}  culled: {}",
+            self.mesh_vec.len() - self.culled_meshes,
+            self.culled_meshes
+        );
+        if let Some((_, report)) = &self.report {
+            hud.push('\n');
+            hud.push_str(&report.summary());
+        }
+        let color = self.line_color;
+        let text = graphics::Text::new(hud);
+        graphics::draw(
+            ctx,
+            &text,
+            (
+                na::Point2::new(10.0, 10.0),
+                graphics::Color::new(color.0, color.1, color.2, 1.0),
+            ),
+        )
+    }
+
+    /// Draw projected Triangles in order. Gouraud shaded Triangles that
+    /// share a Material are drawn in one batch.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene being drawn
+    /// * `ctx` - GGez's Context
+    /// * `tris` - The index of the Mesh and the projected Triangle.
+    ///
+    /// # Return
+    /// A GameResult
+    ///
+    fn draw_tris(
+        &mut self,
+        ctx: &mut ggez::Context,
+        tris: &Vec<(usize, Triangle)>,
+    ) -> ggez::GameResult {
+        let mut raw: Vec<Vertex> = vec![];
+        let mut batch = (0, 0);
+        for j in 0..tris.len() {
+            let (mesh, mut tri) = tris[j];
+            if self.shading == Shading::Gouraud {
+                if (mesh, tri.material) != batch {
+                    self.draw_raw(ctx, &raw, batch.1, batch.0)?;
+                    raw.clear();
+                    batch = (mesh, tri.material);
+                }
                 let pt_list = tri.form_vertexlist();
                 raw.push(pt_list[0]);
                 raw.push(pt_list[1]);
                 raw.push(pt_list[2]);
-
+            } else {
+                let color = tri.vertex_colors.0;
                 let triangle = graphics::Mesh::from_triangles(
                     ctx,
                     &tri.form_pointlist(),
-                    graphics::Color::new(tri.color.0, tri.color.1, tri.color.2, 1.0),
+                    graphics::Color::new(color.0, color.1, color.2, tri.alpha),
                 )?;
                 graphics::draw(ctx, &triangle, (na::Point2::new(0.0, 0.0),))?;
             }
-            // let triangle = graphics::Mesh::from_raw (
-            //     ctx,
-            //     &raw,
-            //     &vec![],
-            //     None,
-            // )?;
-            // graphics::draw(ctx, &triangle, (na::Point2::new(0.0, 0.0),))?;
         }
+        self.draw_raw(ctx, &raw, batch.1, batch.0)
+    }
 
-        graphics::present(ctx)?;
-        Ok(())
+    /// Draw Gouraud shaded verticies that share a Material in one batch
+    /// with the base color texture of the Material.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene being drawn
+    /// * `ctx` - GGez's Context
+    /// * `raw` - The verticies, 3 for every Triangle.
+    /// * `material` - The index of the Material.
+    /// * `mesh` - The index of the Mesh the verticies belong to.
+    ///
+    /// # Return
+    /// A GameResult
+    ///
+    fn draw_raw(
+        &mut self,
+        ctx: &mut ggez::Context,
+        raw: &Vec<Vertex>,
+        material: usize,
+        mesh: usize,
+    ) -> ggez::GameResult {
+        if raw.len() == 0 {
+            return Ok(());
+        }
+        let image = match self.mesh_vec[mesh].materials[material]
+            .base_color_texture
+            .clone()
+        {
+            Some(texture) => Some(self.texture_image(ctx, &texture)?),
+            None => None,
+        };
+        // The triangles are already sorted so drawing them in order keeps them sorted.
+        let indices: Vec<u32> = (0..raw.len() as u32).collect();
+        let triangle = graphics::Mesh::from_raw(ctx, raw, &indices, image)?;
+        graphics::draw(ctx, &triangle, (na::Point2::new(0.0, 0.0),))
+    }
+
+    /// Find the Image of a Texture on the graphics card. The Image is
+    /// made the first time the Texture is drawn and kept for as long as
+    /// a Material uses the Texture.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene being drawn
+    /// * `ctx` - GGez's Context
+    /// * `texture` - The Texture.
+    ///
+    /// # Return
+    /// The Image
+    ///
+    fn texture_image(
+        &mut self,
+        ctx: &mut ggez::Context,
+        texture: &Arc<Texture>,
+    ) -> ggez::GameResult<graphics::Image> {
+        if let Some((_, image)) = self
+            .texture_images
+            .iter()
+            .find(|(cached, _)| Arc::ptr_eq(cached, texture))
+        {
+            return Ok(image.clone());
+        }
+        // forget the Images of Textures only the cache still holds
+        self.texture_images
+            .retain(|(cached, _)| Arc::strong_count(cached) > 1);
+        let image = graphics::Image::from_rgba8(
+            ctx,
+            texture.levels[0].width as u16,
+            texture.levels[0].height as u16,
+            &texture.to_rgba8(),
+        )?;
+        self.texture_images.push((texture.clone(), image.clone()));
+        Ok(image)
     }
 }
 
 /// Main
 ///  - Generates a Context and an event loop
-///  - Creates a Camera with a position and a rotaion
-///  - Creates a Mesh with the Camera
-///  - Loads in a file or the cube
+///  - Loads the scene file given on the command line or
+///     - Creates a Camera with a position and a rotaion
+///     - Creates a Mesh with the Camera
+///     - Loads in a file or the cube
+///     - Creates the Lights shining on the Mesh
 ///  - Creates the window
 ///  - Starts the eventloop for mesh
 ///
@@ -158,14 +664,31 @@ fn main() -> ggez::GameResult {
     let cb = ggez::ContextBuilder::new("my-engine", "littleTitan");
     let (ctx, event_loop) = &mut cb.build()?;
 
-    // Create Camera
-    let camera: Camera = Camera::new(Vec3d::new(0.0, 0.0, -4.0), Vec3d::new(0.0, 0.0, 0.0));
+    let scene = &mut match std::env::args().nth(1) {
+        Some(filename) => {
+            let scene = Scene::from_file(&filename)?;
+            for message in scene.messages.iter() {
+                println!("{}", message);
+            }
+            scene
+        }
+        None => {
+            // Create Camera
+            let camera: Camera = Camera::new(Vec3d::new(0.0, 0.0, -4.0), Vec3d::new(0.0, 0.0, 0.0));
 
-    // Create Mesh
-    let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
-    mesh.from_file("models/xyz.stl");
+            // Create Mesh
+            let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+            mesh.from_file("models/xyz.stl")?;
+            mesh.calculate_vertex_normals(45.0);
 
-    let scene = &mut Scene::new(camera, vec![mesh])?;
+            // Create Lights
+            let mut sun = Light::directional(Vec3d::new(0.3, -1.0, 0.5), (1.0, 1.0, 1.0), 0.8);
+            sun.cast_shadows(0.05, 1);
+            let lights = vec![Light::ambient((1.0, 1.0, 1.0), 0.25), sun];
+
+            Scene::new(camera, vec![mesh], lights)?
+        }
+    };
 
     // Give Context and Mesh to GGez
     ggez::graphics::set_window_title(ctx, "My Engine");
@@ -0,0 +1,265 @@
+use crate::texture::{Texture, WrapMode};
+use std::{io::Error, path::Path, sync::Arc};
+
+/// A Material describes how the surface of a Mesh reflects light.
+/// The base color is multiplied with the vertex colors and the base
+/// color texture. Emissive light is added on top without any Lights.
+#[derive(Clone)]
+pub struct Material {
+    pub name: String,
+    pub base_color: (f32, f32, f32, f32),
+    pub emissive: (f32, f32, f32),
+    pub specular: (f32, f32, f32),
+    pub shininess: f32,
+    pub double_sided: bool,
+    pub base_color_texture: Option<Arc<Texture>>,
+    pub emissive_texture: Option<Arc<Texture>>,
+}
+
+impl Material {
+    /// Creates a new Material.
+    ///
+    /// # Arguments
+    /// * `specular` - The color of the specular highlights.
+    /// * `shininess` - How tight the specular highlights are.
+    ///
+    /// # Return
+    /// A new Material
+    ///
+    pub fn new(specular: (f32, f32, f32), shininess: f32) -> Material {
+        Material {
+            name: String::from("default"),
+            base_color: (0.8, 0.8, 0.8, 1.0),
+            emissive: (0.0, 0.0, 0.0),
+            specular: specular,
+            shininess: shininess,
+            double_sided: false,
+            base_color_texture: None,
+            emissive_texture: None,
+        }
+    }
+
+    /// Set how tight the specular highlights are from a roughness.
+    ///
+    /// # Arguments
+    /// * `self` - The Material the function was called for.
+    /// * `roughness` - 0 for a mirror up to 1 for a matte surface.
+    ///
+    pub fn set_roughness(&mut self, roughness: f32) {
+        // the Blinn-Phong exponent that matches a Beckmann roughness
+        let alpha = f32::max(roughness * roughness, 0.01);
+        self.shininess = 2.0 / (alpha * alpha) - 2.0;
+    }
+
+    /// The color of the surface at a texture coordinate without lighting.
+    ///
+    /// # Arguments
+    /// * `self` - The Material the function was called for.
+    /// * `color` - The vertex color.
+    /// * `uv` - The texture coordinate.
+    /// * `footprint` - How much the texture coordinate changes per pixel.
+    ///
+    /// # Return
+    /// The color
+    ///
+    pub fn albedo(
+        &self,
+        color: (f32, f32, f32),
+        uv: (f32, f32),
+        footprint: f32,
+    ) -> (f32, f32, f32) {
+        let tint = self.tint(color);
+        match &self.base_color_texture {
+            Some(texture) => {
+                let texel = texture.sample(uv, footprint);
+                (tint.0 * texel.0, tint.1 * texel.1, tint.2 * texel.2)
+            }
+            None => tint,
+        }
+    }
+
+    /// The base color multiplied with a vertex color.
+    ///
+    /// # Arguments
+    /// * `self` - The Material the function was called for.
+    /// * `color` - The vertex color.
+    ///
+    /// # Return
+    /// The color
+    ///
+    pub fn tint(&self, color: (f32, f32, f32)) -> (f32, f32, f32) {
+        (
+            self.base_color.0 * color.0,
+            self.base_color.1 * color.1,
+            self.base_color.2 * color.2,
+        )
+    }
+
+    /// The light given off by the surface at a texture coordinate.
+    ///
+    /// # Arguments
+    /// * `self` - The Material the function was called for.
+    /// * `uv` - The texture coordinate.
+    /// * `footprint` - How much the texture coordinate changes per pixel.
+    ///
+    /// # Return
+    /// The emitted color
+    ///
+    pub fn emission(&self, uv: (f32, f32), footprint: f32) -> (f32, f32, f32) {
+        match &self.emissive_texture {
+            Some(texture) => {
+                let texel = texture.sample(uv, footprint);
+                (
+                    self.emissive.0 * texel.0,
+                    self.emissive.1 * texel.1,
+                    self.emissive.2 * texel.2,
+                )
+            }
+            None => self.emissive,
+        }
+    }
+
+    /// Set a property from a line of an mtl file. Unknown keywords
+    /// are ignored.
+    ///
+    /// | keyword        | property                          |
+    /// |----------------|-----------------------------------|
+    /// | `Kd r g b`     | base color                        |
+    /// | `d a`          | alpha                             |
+    /// | `Tr t`         | 1 - alpha                         |
+    /// | `Ke r g b`     | emissive color                    |
+    /// | `Ks r g b`     | specular color                    |
+    /// | `Ns n`         | shininess                         |
+    /// | `Pr r`         | roughness                         |
+    /// | `map_Kd [-clamp on\|off] file` | base color texture |
+    /// | `map_Ke [-clamp on\|off] file` | emissive texture   |
+    /// | `double_sided` | draw the back of the faces as well |
+    ///
+    /// A texture with `-clamp on` stretches its edge texels instead of
+    /// being tiled.
+    ///
+    /// # Arguments
+    /// * `self` - The Material the function was called for.
+    /// * `keyword` - The first word of the line.
+    /// * `args` - The rest of the words of the line.
+    /// * `dir` - The directory texture filenames are relative to.
+    ///
+    /// # Return
+    /// Success status
+    ///
+    pub fn apply_mtl(&mut self, keyword: &str, args: &[&str], dir: &Path) -> Result<(), Error> {
+        let number = |i: usize| -> f32 {
+            args.get(i)
+                .and_then(|a| a.parse::<f32>().ok())
+                .unwrap_or(0.0)
+        };
+        let path = || {
+            dir.join(args.last().unwrap_or(&""))
+                .to_string_lossy()
+                .into_owned()
+        };
+        let texture = || -> Result<Texture, Error> {
+            let mut texture = Texture::from_file(&path())?;
+            let clamp = args.iter().position(|a| *a == "-clamp");
+            if clamp.and_then(|i| args.get(i + 1)) == Some(&"on") {
+                texture.wrap = WrapMode::Clamp;
+            }
+            Ok(texture)
+        };
+        match keyword {
+            "Kd" => {
+                self.base_color = (number(0), number(1), number(2), self.base_color.3);
+            }
+            "d" => self.base_color.3 = number(0),
+            "Tr" => self.base_color.3 = 1.0 - number(0),
+            "Ke" => self.emissive = (number(0), number(1), number(2)),
+            "Ks" => self.specular = (number(0), number(1), number(2)),
+            "Ns" => self.shininess = number(0),
+            "Pr" => self.set_roughness(number(0)),
+            "map_Kd" => self.base_color_texture = Some(Arc::new(texture()?)),
+            "map_Ke" => self.emissive_texture = Some(Arc::new(texture()?)),
+            "double_sided" => self.double_sided = true,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Read all the Materials in an mtl file.
+    ///
+    /// # Arguments
+    /// * `filename` - The filename of the mtl file.
+    ///
+    /// # Return
+    /// The Materials in the order they are in the file
+    ///
+    pub fn from_mtl(filename: &str) -> Result<Vec<Material>, Error> {
+        let contents = std::fs::read_to_string(filename)?;
+        let dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+
+        let mut materials: Vec<Material> = Vec::new();
+        for line in contents.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() == 0 || words[0].starts_with('#') {
+                continue;
+            }
+            if words[0] == "newmtl" {
+                let mut material = Material::new((0.5, 0.5, 0.5), 32.0);
+                material.name = words[1..].join(" ");
+                materials.push(material);
+            } else if let Some(material) = materials.last_mut() {
+                material.apply_mtl(words[0], &words[1..], dir)?;
+            }
+        }
+        Ok(materials)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::Material;
+    use std::fs;
+
+    #[test]
+    fn mtl_file_sets_every_material() {
+        let path = std::env::temp_dir().join("my-engine-materials.mtl");
+        fs::write(
+            &path,
+            "# two materials\n\
+             newmtl red glass\nKd 1 0 0\nd 0.25\nKs 1 1 1\nNs 64\n\
+             newmtl lamp\nKe 1 1 0.5\nTr 0.5\ndouble_sided\n",
+        )
+        .unwrap();
+        let materials = Material::from_mtl(&path.to_string_lossy()).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "red glass");
+        assert_eq!(materials[0].base_color, (1.0, 0.0, 0.0, 0.25));
+        assert_eq!(materials[0].specular, (1.0, 1.0, 1.0));
+        assert_eq!(materials[0].shininess, 64.0);
+        assert!(!materials[0].double_sided);
+        assert_eq!(materials[1].emissive, (1.0, 1.0, 0.5));
+        assert_eq!(materials[1].base_color.3, 0.5);
+        assert!(materials[1].double_sided);
+    }
+
+    #[test]
+    fn albedo_tints_the_vertex_color() {
+        let mut material = Material::new((0.5, 0.5, 0.5), 32.0);
+        material.base_color = (0.5, 1.0, 0.0, 1.0);
+        assert_eq!(
+            material.albedo((1.0, 0.5, 1.0), (0.0, 0.0), 0.0),
+            (0.5, 0.5, 0.0)
+        );
+        material.emissive = (0.2, 0.2, 0.2);
+        assert_eq!(material.emission((0.0, 0.0), 0.0), (0.2, 0.2, 0.2));
+    }
+
+    #[test]
+    fn rougher_surfaces_have_wider_highlights() {
+        let mut material = Material::new((0.5, 0.5, 0.5), 32.0);
+        material.set_roughness(0.2);
+        let smooth = material.shininess;
+        material.set_roughness(0.8);
+        assert!(material.shininess < smooth);
+        assert!(material.shininess > 0.0);
+    }
+}
@@ -51,6 +51,25 @@ impl Matrix3x3 {
         det
     }
 
+    /// Swap the rows and columns. For a rotation matrix this is the
+    /// rotation back.
+    ///
+    /// # Arguments
+    /// * `self` - The matrix the function is being called for
+    ///
+    /// # Return
+    /// The transposed Matrix
+    ///
+    pub fn transpose(&self) -> Matrix3x3 {
+        Matrix3x3 {
+            m: [
+                [self.m[0][0], self.m[1][0], self.m[2][0]],
+                [self.m[0][1], self.m[1][1], self.m[2][1]],
+                [self.m[0][2], self.m[1][2], self.m[2][2]],
+            ],
+        }
+    }
+
     /// Get the inverse of a 3x3 matrix.
     ///
     /// # Arguments
@@ -1,12 +1,56 @@
-use crate::{triangle::Triangle, vec3d::Vec3d};
-use byteorder::{LittleEndian, ReadBytesExt};
+use crate::{
+    bounds::{Aabb, BoundingSphere},
+    bvh::Bvh,
+    material::Material,
+    simplify::Simplifier,
+    texture::Texture,
+    transform::Transform,
+    triangle::Triangle,
+    validation::MeshReport,
+    vec3d::Vec3d,
+    weld::Weld,
+};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
 use std::{
+    collections::{HashMap, HashSet},
     fs::{self, File},
-    io::prelude::*,
+    io::{prelude::*, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
 };
 
+/// How the points of a Mesh in point mode are drawn.
+///  - Square  a square facing the screen
+///  - Disk    a circle facing the screen
+#[derive(Copy, Clone, PartialEq)]
+pub enum PointShape {
+    Square,
+    Disk,
+}
+
+/// A simpler version of a Mesh that is drawn while the Mesh is smaller
+/// on the screen than `max_size` pixels.
+#[derive(Clone)]
+pub struct Lod {
+    pub tris: Vec<Triangle>,
+    pub max_size: f32,
+}
+
 /// A Mesh is a 3D object made up of triangles.
 /// It also has a camera that is looking at it.
+/// Each Triangle picks its Material from the material table
+/// of the Mesh by index. The opacity fades the whole Mesh and
+/// x-ray makes it see through. Back faces are not drawn unless
+/// culling is turned off for open meshes. In point mode only the
+/// points of the Mesh are drawn, each with its own color.
+/// The bounding box and sphere contain every vertex and point
+/// and are kept up to date when the Mesh moves, like the optional
+/// Bvh over the Triangles. The Transform places the verticies in the
+/// world when the Mesh is drawn, the verticies and the bounds stay
+/// where they are until it is applied. Levels of detail are simpler versions of
+/// the Triangles drawn when the Mesh is small on the screen. They move
+/// with the Mesh and are dropped when its shape changes. The crease angle
+/// of the last smoothing is kept so edited faces can be smoothed again.
 ///
 /// NOTE: Structure is subject to change
 ///
@@ -16,9 +60,33 @@ pub struct Mesh {
     pub is_over: bool,
     pub is_held: bool,
     pub pos: Vec3d,
+    pub materials: Vec<Material>,
+    pub opacity: f32,
+    pub x_ray: bool,
+    pub cull_back_faces: bool,
+    pub points: Vec<Vec3d>,
+    pub point_colors: Vec<(f32, f32, f32)>,
+    pub point_mode: bool,
+    pub point_size: f32,
+    pub point_shape: PointShape,
+    pub aabb: Aabb,
+    pub bounding_sphere: BoundingSphere,
+    pub bvh: Option<Bvh>,
+    pub transform: Transform,
+    pub crease_angle: Option<f32>,
+    pub lods: Vec<Lod>,
+    pub lod: usize,
+    pub generation: u64,
 }
 
 impl Mesh {
+    /// How opaque a Mesh is in x-ray mode.
+    pub const X_RAY_OPACITY: f32 = 0.3;
+
+    /// How far past the limit of a level of detail the size on the
+    /// screen has to go before the level changes.
+    pub const LOD_HYSTERESIS: f32 = 0.15;
+
     /// Creates a new Mesh with a camera
     ///
     /// # Arguments
@@ -33,6 +101,26 @@ impl Mesh {
             is_over: false,
             is_held: false,
             pos: pos,
+            materials: vec![Material::new((0.5, 0.5, 0.5), 32.0)],
+            opacity: 1.0,
+            x_ray: false,
+            cull_back_faces: true,
+            points: Vec::new(),
+            point_colors: Vec::new(),
+            point_mode: false,
+            point_size: 3.0,
+            point_shape: PointShape::Square,
+            aabb: Aabb::empty(),
+            bounding_sphere: BoundingSphere {
+                center: pos,
+                radius: -1.0,
+            },
+            bvh: None,
+            transform: Transform::identity(),
+            crease_angle: None,
+            lods: Vec::new(),
+            lod: 0,
+            generation: 0,
         }
     }
 
@@ -41,7 +129,6 @@ impl Mesh {
     /// # Argumetns
     /// * `self` - The Mesh the function was called for.
     ///
-    #[allow(dead_code)]
     pub fn form_cube(&mut self) {
         self.tris = vec![
             // FRONT
@@ -111,6 +198,8 @@ impl Mesh {
                 Vec3d::new(0.5, -0.5, -0.5),
             ) + self.pos,
         ];
+        self.seed_colors(0);
+        self.update_bounds();
     }
 
     /// Rotates the Mesh arround the x-axis at the origin point.
@@ -126,6 +215,16 @@ impl Mesh {
         for i in 0..self.tris.len() {
             self.tris[i].x_axis_rotation(r, origin_y, origin_z);
         }
+        for lod in self.lods.iter_mut() {
+            for tri in lod.tris.iter_mut() {
+                tri.x_axis_rotation(r, origin_y, origin_z);
+            }
+        }
+        for point in self.points.iter_mut() {
+            point.x_axis_rotation(r, origin_y, origin_z);
+        }
+        self.pos.x_axis_rotation(r, origin_y, origin_z);
+        self.update_bounds();
     }
 
     /// Rotates the Mesh arround the y-axis at the origin point.
@@ -141,6 +240,16 @@ impl Mesh {
         for i in 0..self.tris.len() {
             self.tris[i].y_axis_rotation(r, origin_x, origin_z);
         }
+        for lod in self.lods.iter_mut() {
+            for tri in lod.tris.iter_mut() {
+                tri.y_axis_rotation(r, origin_x, origin_z);
+            }
+        }
+        for point in self.points.iter_mut() {
+            point.y_axis_rotation(r, origin_x, origin_z);
+        }
+        self.pos.y_axis_rotation(r, origin_x, origin_z);
+        self.update_bounds();
     }
 
     /// Rotates the Mesh arround the z-axis at the origin point.
@@ -156,6 +265,40 @@ impl Mesh {
         for i in 0..self.tris.len() {
             self.tris[i].z_axis_rotation(r, origin_x, origin_y);
         }
+        for lod in self.lods.iter_mut() {
+            for tri in lod.tris.iter_mut() {
+                tri.z_axis_rotation(r, origin_x, origin_y);
+            }
+        }
+        for point in self.points.iter_mut() {
+            point.z_axis_rotation(r, origin_x, origin_y);
+        }
+        self.pos.z_axis_rotation(r, origin_x, origin_y);
+        self.update_bounds();
+    }
+
+    /// Scales the Mesh along the axes away from the origin point.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `factor` - The positive scale along each axis.
+    /// * `origin` - The origin point.
+    ///
+    #[allow(dead_code)]
+    pub fn scale(&mut self, factor: Vec3d, origin: Vec3d) {
+        for i in 0..self.tris.len() {
+            self.tris[i].scale(factor, origin);
+        }
+        for lod in self.lods.iter_mut() {
+            for tri in lod.tris.iter_mut() {
+                tri.scale(factor, origin);
+            }
+        }
+        for point in self.points.iter_mut() {
+            *point = origin + (*point - origin) * factor;
+        }
+        self.pos = origin + (self.pos - origin) * factor;
+        self.update_bounds();
     }
 
     /// Increment the Mesh x position by a number.
@@ -164,12 +307,20 @@ impl Mesh {
     /// * `self` - The Mesh this function was called for.
     /// * `inc_x` - The number the x position will be incremented by.
     ///
-    #[allow(dead_code)]
     pub fn increment_x(&mut self, inc_x: f32) {
         self.pos.x += inc_x;
         for i in 0..self.tris.len() {
             self.tris[i].increment_x(inc_x);
         }
+        for lod in self.lods.iter_mut() {
+            for tri in lod.tris.iter_mut() {
+                tri.increment_x(inc_x);
+            }
+        }
+        for point in self.points.iter_mut() {
+            point.x += inc_x;
+        }
+        self.update_bounds();
     }
 
     /// Increment the Mesh y position by a number.
@@ -178,12 +329,20 @@ impl Mesh {
     /// * `self` - The Mesh this function was called for.
     /// * `inc_y` - The number the y position will be incremented by.
     ///
-    #[allow(dead_code)]
     pub fn increment_y(&mut self, inc_y: f32) {
         self.pos.y += inc_y;
         for i in 0..self.tris.len() {
             self.tris[i].increment_y(inc_y);
         }
+        for lod in self.lods.iter_mut() {
+            for tri in lod.tris.iter_mut() {
+                tri.increment_y(inc_y);
+            }
+        }
+        for point in self.points.iter_mut() {
+            point.y += inc_y;
+        }
+        self.update_bounds();
     }
 
     /// Increment the Mesh z position by a number.
@@ -192,165 +351,2128 @@ impl Mesh {
     /// * `self` - The Mesh this function was called for.
     /// * `inc_z` - The number the z position will be incremented by.
     ///
-    #[allow(dead_code)]
     pub fn increment_z(&mut self, inc_z: f32) {
         self.pos.z += inc_z;
         for i in 0..self.tris.len() {
             self.tris[i].increment_z(inc_z);
         }
+        for lod in self.lods.iter_mut() {
+            for tri in lod.tris.iter_mut() {
+                tri.increment_z(inc_z);
+            }
+        }
+        for point in self.points.iter_mut() {
+            point.z += inc_z;
+        }
+        self.update_bounds();
     }
 
-    /// Make a Mesh from a file.
+    /// Recalculates the bounding box and sphere from the verticies and
+    /// points and refits the Bvh. Has to be called after the geometry is
+    /// changed directly.
     ///
     /// # Arguments
     /// * `self` - The Mesh this function was called for.
-    /// * `filename` - The filename of the file containing the data.
     ///
-    #[allow(dead_code)]
-    pub fn from_file(&mut self, filename: &str) {
-        println!("In file {}", filename);
-        let file_type = filename.split('.').last().unwrap();
-        if file_type == "obj" {
-            self.from_obj(filename);
-        } else if file_type == "stl" {
-            match self.from_stl_ascii(filename) {
-                Ok(_) => (),
-                Err(_e) => self.from_stl_bin(filename),
+    pub fn update_bounds(&mut self) {
+        let mut verticies: Vec<Vec3d> = Vec::with_capacity(self.tris.len() * 3 + self.points.len());
+        for tri in self.tris.iter() {
+            verticies.push(tri.verticies.0);
+            verticies.push(tri.verticies.1);
+            verticies.push(tri.verticies.2);
+        }
+        verticies.extend(self.points.iter());
+        self.aabb = Aabb::from_points(verticies.iter());
+        self.bounding_sphere = BoundingSphere::from_points(verticies.iter(), &self.aabb);
+
+        let tris = &self.tris;
+        if let Some(bvh) = self.bvh.as_mut() {
+            if bvh.indices.len() == tris.len() {
+                bvh.refit(tris);
+            } else {
+                *bvh = Bvh::build(tris);
             }
         }
+        self.mark_changed();
     }
 
-    /// Make a Mesh from an obj file.
+    /// Counts a change to the verticies or the Transform so what is kept
+    /// from earlier frames, like the ShadowMaps, is made again.
     ///
     /// # Arguments
     /// * `self` - The Mesh this function was called for.
-    /// * `filename` - The filename of the obj file containing the data.
     ///
-    pub fn from_obj(&mut self, filename: &str) {
-        let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+    pub fn mark_changed(&mut self) {
+        self.generation += 1;
+    }
 
-        let mut points: Vec<Vec3d> = Vec::new();
-        for line in contents.lines() {
-            if line.chars().next().unwrap() == 'v' {
-                let mut e = line.split_whitespace();
-                e.next();
-                points.push(Vec3d {
-                    x: e.next().unwrap().parse::<f32>().unwrap(),
-                    y: e.next().unwrap().parse::<f32>().unwrap(),
-                    z: e.next().unwrap().parse::<f32>().unwrap(),
-                });
-            } else if line.chars().next().unwrap() == 'f' {
-                let mut e = line.split_whitespace();
-                e.next();
-                self.tris.push(Triangle::new(
-                    points[e.next().unwrap().parse::<usize>().unwrap() - 1_usize],
-                    points[e.next().unwrap().parse::<usize>().unwrap() - 1_usize],
-                    points[e.next().unwrap().parse::<usize>().unwrap() - 1_usize],
-                ));
-            }
+    /// The bounding box of the Mesh in the world.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    ///
+    /// # Return
+    /// The bounding box moved by the Transform
+    ///
+    pub fn world_aabb(&self) -> Aabb {
+        self.transform.apply_aabb(&self.aabb)
+    }
+
+    /// The bounding sphere of the Mesh in the world.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    ///
+    /// # Return
+    /// The bounding sphere moved by the Transform
+    ///
+    pub fn world_sphere(&self) -> BoundingSphere {
+        BoundingSphere {
+            center: self.transform.apply(self.bounding_sphere.center),
+            radius: self.bounding_sphere.radius * self.transform.max_scale(),
         }
     }
 
-    /// Make a Mesh from an stl binary file.
+    /// Moves the verticies, points and levels of detail to where the
+    /// Transform places them and resets the Transform.
     ///
     /// # Arguments
     /// * `self` - The Mesh this function was called for.
-    /// * `filename` - The filename of the stl binary file containing the data.
     ///
-    pub fn from_stl_bin(&mut self, filename: &str) {
-        let mut file = File::open(filename).expect("file not found");
+    pub fn apply_transform(&mut self) {
+        if self.transform.is_identity() {
+            return;
+        }
+        let transform = self.transform.clone();
+        for tri in self.tris.iter_mut() {
+            *tri = transform.apply_triangle(tri);
+        }
+        for lod in self.lods.iter_mut() {
+            for tri in lod.tris.iter_mut() {
+                *tri = transform.apply_triangle(tri);
+            }
+        }
+        for point in self.points.iter_mut() {
+            *point = transform.apply(*point);
+        }
+        self.pos = transform.apply(self.pos);
+        self.transform = Transform::identity();
+        self.update_bounds();
+    }
 
-        // header
-        let mut header_buf = [0; 80];
-        let header = file
-            .read(&mut header_buf[..])
-            .expect("Something went wrong reading the file");
-        let _contents = String::from_utf8_lossy(&header_buf[..header]);
+    /// Finds the first Triangle hit by a ray, using the Bvh if there is one.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `origin` - The start of the ray in the world.
+    /// * `direction` - The direction of the ray in the world.
+    ///
+    /// # Return
+    /// The index of the Triangle and how far along the ray it was hit
+    ///
+    pub fn intersect_ray(&self, origin: Vec3d, direction: Vec3d) -> Option<(usize, f32)> {
+        // the ray is moved onto the Mesh, which keeps how far along it a hit is
+        let origin = self.transform.invert(origin);
+        let direction = self.transform.invert_direction(direction);
+        if let Some(bvh) = &self.bvh {
+            return bvh.intersect_ray(&self.tris, origin, direction);
+        }
+        let mut best: Option<(usize, f32)> = None;
+        for i in 0..self.tris.len() {
+            if let Some(t) = self.tris[i].intersect_ray(origin, direction) {
+                if best.is_none_or(|(_, closest)| t < closest) {
+                    best = Some((i, t));
+                }
+            }
+        }
+        best
+    }
 
-        // number of triangles
-        let mut n_tris_buf = [0; 4];
-        let n_tris_raw = file
-            .read(&mut n_tris_buf[..])
-            .expect("Something went wrong reading the file");
-        let mut n_tris_dat = &n_tris_buf[..n_tris_raw];
-        let n_tris = *&n_tris_dat.read_u32::<LittleEndian>().unwrap() as i32;
-        println!("{:?}", n_tris);
+    /// Builds a Bvh over the Triangles to speed up culling and picking.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    ///
+    pub fn build_bvh(&mut self) {
+        self.bvh = Some(Bvh::build(&self.tris));
+    }
 
-        let mut tri_buf = [0; 50];
-        for _i in 0..n_tris {
-            let tri_raw = file
-                .read(&mut tri_buf[..])
-                .expect("Something went wrong reading the file");
-            let mut data = &tri_buf[..tri_raw];
-            self.tris.push(Triangle::new_with_normal(
-                Vec3d {
-                    // Normal
-                    x: *&data.read_f32::<LittleEndian>().unwrap(),
-                    y: *&data.read_f32::<LittleEndian>().unwrap(),
-                    z: *&data.read_f32::<LittleEndian>().unwrap(),
-                },
-                Vec3d {
-                    // Verticies
-                    x: *&data.read_f32::<LittleEndian>().unwrap(),
-                    y: *&data.read_f32::<LittleEndian>().unwrap(),
-                    z: *&data.read_f32::<LittleEndian>().unwrap(),
-                },
-                Vec3d {
-                    x: *&data.read_f32::<LittleEndian>().unwrap(),
-                    y: *&data.read_f32::<LittleEndian>().unwrap(),
-                    z: *&data.read_f32::<LittleEndian>().unwrap(),
-                },
-                Vec3d {
-                    x: *&data.read_f32::<LittleEndian>().unwrap(),
-                    y: *&data.read_f32::<LittleEndian>().unwrap(),
-                    z: *&data.read_f32::<LittleEndian>().unwrap(),
-                },
-            ));
+    /// Calculates the vertex normals of every Triangle by averaging the
+    /// normals of all the faces sharing that vertex weighted by their area.
+    /// Faces that meet at an angle larger than the crease angle are left
+    /// out so hard edges stay sharp.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `crease_angle` - The largest angle in degrees between two faces
+    ///                    that are smoothed together.
+    ///
+    pub fn calculate_vertex_normals(&mut self, crease_angle: f32) {
+        self.crease_angle = Some(crease_angle);
+        let faces = vec![true; self.tris.len()];
+        self.smooth_faces(crease_angle, &faces, false);
+    }
 
-            // Attribute byte count
-            &data.read_u16::<LittleEndian>().unwrap();
+    /// Calculates the vertex normals again after some faces changed
+    /// shape, for those faces and the faces sharing a vertex with them.
+    /// The crease angle of the last `calculate_vertex_normals` is used.
+    /// Without one only the faces that are not flat are smoothed and no
+    /// edge is a crease, flat faces already follow their new normal.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `faces` - If each Triangle changed.
+    ///
+    pub fn update_vertex_normals(&mut self, faces: &Vec<bool>) {
+        match self.crease_angle {
+            Some(crease_angle) => self.smooth_faces(crease_angle, faces, false),
+            None => self.smooth_faces(180.0, faces, true),
         }
     }
 
-    /// Make a Mesh from an stl ascii file.
+    /// Averages the face normals around the verticies of some faces and
+    /// of their neighbours into their vertex normals.
     ///
     /// # Arguments
     /// * `self` - The Mesh this function was called for.
-    /// * `filename` - The filename of the stl ascii file containing the data.
+    /// * `crease_angle` - The largest angle in degrees between two faces
+    ///                    that are smoothed together.
+    /// * `faces` - If each Triangle changed.
+    /// * `keep_flat` - If flat faces are left as they are.
+    ///
+    fn smooth_faces(&mut self, crease_angle: f32, faces: &Vec<bool>, keep_flat: bool) {
+        let min_cos = f32::cos(crease_angle.to_radians());
+
+        // area weighted face normals (the length of the cross product is twice the area)
+        let mut face_normals: Vec<Vec3d> = Vec::with_capacity(self.tris.len());
+        let mut shared: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+        for i in 0..self.tris.len() {
+            let (a, b, c) = self.tris[i].verticies;
+            face_normals.push((b - a).cross(c - a));
+            for vertex in [a, b, c].iter() {
+                shared.entry(Mesh::vertex_key(*vertex)).or_default().push(i);
+            }
+        }
+
+        // the changed faces and their neighbours
+        let mut update = vec![false; self.tris.len()];
+        for i in (0..self.tris.len()).filter(|i| faces[*i]) {
+            let (a, b, c) = self.tris[i].verticies;
+            for vertex in [a, b, c].iter() {
+                for j in shared[&Mesh::vertex_key(*vertex)].iter() {
+                    update[*j] = true;
+                }
+            }
+        }
+
+        for i in 0..self.tris.len() {
+            let mut face = face_normals[i];
+            if !update[i] || face.length() == 0.0 {
+                continue;
+            }
+            let tri = self.tris[i];
+            let flat = |n: Vec3d| (n - tri.normal).length() < 0.0001;
+            if keep_flat
+                && flat(tri.vertex_normals.0)
+                && flat(tri.vertex_normals.1)
+                && flat(tri.vertex_normals.2)
+            {
+                continue;
+            }
+            face.normalize();
+
+            let (a, b, c) = self.tris[i].verticies;
+            let corners = [a, b, c];
+            let mut normals = [face, face, face];
+            for n in 0..3 {
+                let mut sum = Vec3d::new(0.0, 0.0, 0.0);
+                for j in shared[&Mesh::vertex_key(corners[n])].iter() {
+                    let other = face_normals[*j];
+                    let len = other.length();
+                    if len != 0.0 && face.dot(other) / len >= min_cos {
+                        sum += other;
+                    }
+                }
+                if sum.length() != 0.0 {
+                    normals[n] = sum.normalize();
+                }
+            }
+            self.tris[i].vertex_normals = (normals[0], normals[1], normals[2]);
+        }
+    }
+
+    /// Rounds a vertex to a key so that verticies at the same position
+    /// in different Triangles can be matched.
+    ///
+    /// # Arguments
+    /// * `vertex` - The vertex.
     ///
     /// # Return
-    /// Success status
+    /// The key
     ///
-    pub fn from_stl_ascii(&mut self, filename: &str) -> Result<(), std::io::Error> {
-        let contents = fs::read_to_string(filename)?;
+    pub fn vertex_key(vertex: Vec3d) -> (i64, i64, i64) {
+        (
+            (vertex.x * 100000.0).round() as i64,
+            (vertex.y * 100000.0).round() as i64,
+            (vertex.z * 100000.0).round() as i64,
+        )
+    }
 
-        let mut points: Vec<Vec3d> = Vec::new();
-        let mut normal: Vec3d = Vec3d::new(0.0, 0.0, 0.0);
-        for line in contents.lines() {
-            let mut e = line.split_whitespace();
-            let s = e.next().unwrap();
-            if s == "facet" {
-                if e.next().unwrap() == "normal" {
-                    normal = Vec3d::new(
-                        e.next().unwrap().parse::<f32>().unwrap(),
-                        e.next().unwrap().parse::<f32>().unwrap(),
-                        e.next().unwrap().parse::<f32>().unwrap(),
+    /// Groups the verticies of the Triangles that are at the same
+    /// position.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    /// # Return
+    /// The position of every vertex and the Triangle index and corner of
+    /// every Triangle using it
+    ///
+    pub fn shared_verticies(&self) -> (Vec<Vec3d>, Vec<Vec<(usize, usize)>>) {
+        let mut index: HashMap<(i64, i64, i64), usize> = HashMap::new();
+        let mut verticies: Vec<Vec3d> = Vec::new();
+        let mut corners: Vec<Vec<(usize, usize)>> = Vec::new();
+        for i in 0..self.tris.len() {
+            let (a, b, c) = self.tris[i].verticies;
+            for (corner, vertex) in [a, b, c].iter().enumerate() {
+                let next = verticies.len();
+                let j = *index.entry(Mesh::vertex_key(*vertex)).or_insert(next);
+                if j == next {
+                    verticies.push(*vertex);
+                    corners.push(Vec::new());
+                }
+                corners[j].push((i, corner));
+            }
+        }
+        (verticies, corners)
+    }
+
+    /// Moves a vertex shared by Triangles. `update_vertex_normals`,
+    /// `update_bounds` and `refresh_points` have to be called after the
+    /// verticies are moved.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `corners` - The Triangle index and corner of every Triangle using the vertex.
+    /// * `position` - The new position of the vertex.
+    ///
+    pub fn set_vertex(&mut self, corners: &Vec<(usize, usize)>, position: Vec3d) {
+        for (tri, corner) in corners.iter() {
+            self.tris[*tri].set_vertex(*corner, position);
+        }
+        self.clear_lods();
+        self.mark_changed();
+    }
+
+    /// Splits every Triangle into four at the middle of its edges.
+    /// With faces given only those are split and their neighbours are
+    /// split in two or three so no edge ends in the middle of another.
+    /// The new Triangles keep the Material, face and colors of the one
+    /// they came from.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `iterations` - How many times the Triangles are split.
+    /// * `faces` - If each Triangle is split or None for all of them.
+    ///
+    pub fn subdivide_midpoint(&mut self, iterations: usize, faces: Option<&Vec<bool>>) {
+        self.subdivide(iterations, faces, false);
+    }
+
+    /// Splits every Triangle into four like `subdivide_midpoint` and
+    /// smooths the surface with Loop's rules. With faces given only the
+    /// verticies surrounded by those faces move so the rest of the Mesh
+    /// keeps its shape.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `iterations` - How many times the Triangles are split.
+    /// * `faces` - If each Triangle is split or None for all of them.
+    ///
+    pub fn subdivide_loop(&mut self, iterations: usize, faces: Option<&Vec<bool>>) {
+        self.subdivide(iterations, faces, true);
+    }
+
+    /// Splits the Triangles a number of times.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `iterations` - How many times the Triangles are split.
+    /// * `faces` - If each Triangle is split or None for all of them.
+    /// * `smooth` - If Loop's rules move the verticies.
+    ///
+    fn subdivide(&mut self, iterations: usize, faces: Option<&Vec<bool>>, smooth: bool) {
+        let mut selected = match faces {
+            Some(faces) => faces.clone(),
+            None => vec![true; self.tris.len()],
+        };
+        for _ in 0..iterations {
+            selected = self.subdivide_once(&selected, smooth);
+        }
+        self.clear_lods();
+        self.refresh_points();
+        self.update_bounds();
+    }
+
+    /// Splits the selected Triangles once.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `selected` - If each Triangle is split.
+    /// * `smooth` - If Loop's rules move the verticies.
+    ///
+    /// # Return
+    /// If each new Triangle came from a selected one
+    ///
+    fn subdivide_once(&mut self, selected: &Vec<bool>, smooth: bool) -> Vec<bool> {
+        type Key = (i64, i64, i64);
+        let edge_key = |a: Key, b: Key| if a < b { (a, b) } else { (b, a) };
+
+        // the Triangles and the opposite corner at every edge
+        let mut edges: HashMap<(Key, Key), Vec<(usize, Vec3d)>> = HashMap::new();
+        for i in 0..self.tris.len() {
+            let (a, b, c) = self.tris[i].verticies;
+            for (start, end, opposite) in [(a, b, c), (b, c, a), (c, a, b)].iter() {
+                edges
+                    .entry(edge_key(Mesh::vertex_key(*start), Mesh::vertex_key(*end)))
+                    .or_default()
+                    .push((i, *opposite));
+            }
+        }
+        let is_split = |key: &(Key, Key)| edges[key].iter().any(|(tri, _)| selected[*tri]);
+
+        // where the verticies move and where the new verticies on the edges go
+        let mut moved: HashMap<Key, Vec3d> = HashMap::new();
+        let mut edge_points: HashMap<(Key, Key), Vec3d> = HashMap::new();
+        if smooth {
+            let mut neighbours: HashMap<Key, (Vec3d, Vec<Vec3d>, Vec<Vec3d>, bool)> =
+                HashMap::new();
+            for ((k1, k2), users) in edges.iter() {
+                let tri = &self.tris[users[0].0];
+                let ends: Vec<Vec3d> = [tri.verticies.0, tri.verticies.1, tri.verticies.2]
+                    .iter()
+                    .copied()
+                    .filter(|v| Mesh::vertex_key(*v) == *k1 || Mesh::vertex_key(*v) == *k2)
+                    .collect();
+                let (a, b) = if Mesh::vertex_key(ends[0]) == *k1 {
+                    (ends[0], ends[1])
+                } else {
+                    (ends[1], ends[0])
+                };
+                let boundary = users.len() == 1;
+                let inside = users.iter().all(|(tri, _)| selected[*tri]);
+                for (key, vertex, other) in [(k1, a, b), (k2, b, a)].iter() {
+                    let entry =
+                        neighbours
+                            .entry(**key)
+                            .or_insert((*vertex, Vec::new(), Vec::new(), true));
+                    entry.1.push(*other);
+                    if boundary {
+                        entry.2.push(*other);
+                    }
+                    entry.3 = entry.3 && inside;
+                }
+                if users.len() == 2 && inside {
+                    edge_points.insert(
+                        (*k1, *k2),
+                        (a + b) * 0.375 + (users[0].1 + users[1].1) * 0.125,
                     );
                 }
-            } else if s == "vertex" {
-                points.push(Vec3d::new(
-                    e.next().unwrap().parse::<f32>().unwrap(),
-                    e.next().unwrap().parse::<f32>().unwrap(),
-                    e.next().unwrap().parse::<f32>().unwrap(),
-                ));
-            } else if s == "endfacet" {
-                self.tris.push(Triangle::new_with_normal(
-                    normal, points[0], points[1], points[2],
+            }
+            for (key, (vertex, around, boundary, inside)) in neighbours.iter() {
+                // only verticies surrounded by split Triangles move
+                if !*inside {
+                    continue;
+                }
+                let position = if boundary.len() == 2 {
+                    *vertex * 0.75 + (boundary[0] + boundary[1]) * 0.125
+                } else if boundary.len() == 0 {
+                    let n = around.len() as f32;
+                    let beta = if around.len() == 3 {
+                        3.0 / 16.0
+                    } else {
+                        3.0 / (8.0 * n)
+                    };
+                    let mut sum = Vec3d::new(0.0, 0.0, 0.0);
+                    for other in around.iter() {
+                        sum += *other;
+                    }
+                    *vertex * (1.0 - n * beta) + sum * beta
+                } else {
+                    // a corner where several boundaries meet stays
+                    *vertex
+                };
+                moved.insert(*key, position);
+            }
+        }
+
+        let mut tris: Vec<Triangle> = Vec::with_capacity(self.tris.len() * 4);
+        let mut children: Vec<bool> = Vec::with_capacity(self.tris.len() * 4);
+        for i in 0..self.tris.len() {
+            let tri = self.tris[i];
+            let verticies = [tri.verticies.0, tri.verticies.1, tri.verticies.2];
+            let keys = [
+                Mesh::vertex_key(verticies[0]),
+                Mesh::vertex_key(verticies[1]),
+                Mesh::vertex_key(verticies[2]),
+            ];
+            let normals = [
+                tri.vertex_normals.0,
+                tri.vertex_normals.1,
+                tri.vertex_normals.2,
+            ];
+            let colors = [
+                tri.vertex_colors.0,
+                tri.vertex_colors.1,
+                tri.vertex_colors.2,
+            ];
+            let uvs = [tri.uvs.0, tri.uvs.1, tri.uvs.2];
+
+            // the corners are 0 to 2 and the middles of the edges 3 to 5
+            let mut corners: Vec<(Vec3d, Vec3d, (f32, f32, f32), (f32, f32))> = Vec::new();
+            for j in 0..3 {
+                let position = moved.get(&keys[j]).copied().unwrap_or(verticies[j]);
+                corners.push((position, normals[j], colors[j], uvs[j]));
+            }
+            let mut split = [false; 3];
+            for j in 0..3 {
+                let k = (j + 1) % 3;
+                let key = edge_key(keys[j], keys[k]);
+                split[j] = is_split(&key);
+                let position = edge_points
+                    .get(&key)
+                    .copied()
+                    .unwrap_or((verticies[j] + verticies[k]) * 0.5);
+                corners.push((
+                    position,
+                    (normals[j] + normals[k]) * 0.5,
+                    (
+                        (colors[j].0 + colors[k].0) * 0.5,
+                        (colors[j].1 + colors[k].1) * 0.5,
+                        (colors[j].2 + colors[k].2) * 0.5,
+                    ),
+                    ((uvs[j].0 + uvs[k].0) * 0.5, (uvs[j].1 + uvs[k].1) * 0.5),
                 ));
-                normal = Vec3d::new(0.0, 0.0, 0.0);
-                points = Vec::new();
+            }
+
+            let pieces: Vec<[usize; 3]> = match split.iter().filter(|s| **s).count() {
+                3 => vec![[0, 3, 5], [3, 1, 4], [5, 4, 2], [3, 4, 5]],
+                0 => vec![[0, 1, 2]],
+                1 => {
+                    let j = split.iter().position(|s| *s).unwrap();
+                    vec![[j, j + 3, (j + 2) % 3], [j + 3, (j + 1) % 3, (j + 2) % 3]]
+                }
+                _ => {
+                    // the edge that is not split is the one after the two that are
+                    let j = (split.iter().position(|s| !*s).unwrap() + 1) % 3;
+                    let k = (j + 1) % 3;
+                    vec![
+                        [j + 3, k, k + 3],
+                        [j, j + 3, k + 3],
+                        [j, k + 3, (j + 2) % 3],
+                    ]
+                }
+            };
+            let flat = normals.iter().all(|n| (*n - tri.normal).length() < 0.0001);
+            let unit = |mut n: Vec3d| n.normalize();
+            for piece in pieces.iter() {
+                let (a, b, c) = (corners[piece[0]], corners[piece[1]], corners[piece[2]]);
+                let mut new = tri;
+                new.verticies = (a.0, b.0, c.0);
+                new.normal = Triangle::calculate_normal(new.verticies);
+                new.center = Triangle::calculate_center(new.verticies);
+                new.vertex_normals = if flat {
+                    (new.normal, new.normal, new.normal)
+                } else {
+                    (unit(a.1), unit(b.1), unit(c.1))
+                };
+                new.vertex_colors = (a.2, b.2, c.2);
+                new.uvs = (a.3, b.3, c.3);
+                tris.push(new);
+                children.push(selected[i]);
             }
         }
-        Ok(())
+        self.tris = tris;
+        children
+    }
+
+    /// Creates a copy of the Mesh with fewer Triangles by collapsing
+    /// edges where it changes the shape the least (see `Simplifier`).
+    /// It stops at the target number of Triangles or when every collapse
+    /// left would move a vertex further than the tolerance from the
+    /// planes of the faces it replaces, whichever comes first.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `target` - The number of Triangles to stop at, 0 to only use the tolerance.
+    /// * `tolerance` - The largest distance the surface may move, `f32::INFINITY`
+    ///                 to only use the target.
+    ///
+    /// # Return
+    /// The simplified Mesh
+    ///
+    pub fn simplify(&self, target: usize, tolerance: f32) -> Mesh {
+        let mut simplifier = Simplifier::new(self);
+        simplifier.run(target, tolerance);
+        simplifier.to_mesh(self)
+    }
+
+    /// Measures the Mesh where the Transform places it and checks if it
+    /// can be printed.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    /// # Return
+    /// The MeshReport
+    ///
+    pub fn validate(&self) -> MeshReport {
+        if self.transform.is_identity() {
+            return MeshReport::new(self);
+        }
+        let mut placed = self.clone();
+        placed.apply_transform();
+        MeshReport::new(&placed)
+    }
+
+    /// Recomputes the normals from the verticies and makes the winding
+    /// of the Triangles agree. The orientation of a Triangle is passed on
+    /// to its neighbours across every edge shared by two Triangles. A
+    /// closed part of the Mesh is turned to face outward and an open part
+    /// keeps the winding most of its Triangles had. Vertex normals that
+    /// are missing or point away from the face become the face normal.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    /// # Return
+    /// How many Triangles were turned around
+    ///
+    pub fn repair_normals(&mut self) -> usize {
+        type Key = (i64, i64, i64);
+        let keys: Vec<[Key; 3]> = self
+            .tris
+            .iter()
+            .map(|tri| {
+                let v = tri.verticies;
+                [
+                    Mesh::vertex_key(v.0),
+                    Mesh::vertex_key(v.1),
+                    Mesh::vertex_key(v.2),
+                ]
+            })
+            .collect();
+        let degenerate = |k: &[Key; 3]| k[0] == k[1] || k[1] == k[2] || k[2] == k[0];
+
+        // the Triangles using an edge and if they run along it from the
+        // smaller key to the larger one
+        let mut edges: HashMap<(Key, Key), Vec<(usize, bool)>> = HashMap::new();
+        for i in 0..self.tris.len() {
+            if degenerate(&keys[i]) {
+                continue;
+            }
+            for corner in 0..3 {
+                let (a, b) = (keys[i][corner], keys[i][(corner + 1) % 3]);
+                let key = if a < b { (a, b) } else { (b, a) };
+                edges.entry(key).or_default().push((i, a < b));
+            }
+        }
+
+        let mut flip = vec![false; self.tris.len()];
+        let mut visited = vec![false; self.tris.len()];
+        for seed in 0..self.tris.len() {
+            if visited[seed] || degenerate(&keys[seed]) {
+                continue;
+            }
+            visited[seed] = true;
+            let mut part = vec![seed];
+            let mut closed = true;
+            let mut next = 0;
+            while next < part.len() {
+                let face = part[next];
+                next += 1;
+                for corner in 0..3 {
+                    let (a, b) = (keys[face][corner], keys[face][(corner + 1) % 3]);
+                    let uses = &edges[&if a < b { (a, b) } else { (b, a) }];
+                    if uses.len() != 2 {
+                        closed = false;
+                        continue;
+                    }
+                    // the neighbour has to run along the edge the other way
+                    let runs_up = (a < b) != flip[face];
+                    for (other, other_up) in uses.iter() {
+                        if !visited[*other] {
+                            visited[*other] = true;
+                            flip[*other] = *other_up == runs_up;
+                            part.push(*other);
+                        }
+                    }
+                }
+            }
+
+            let turn = if closed {
+                // the signed volume of a closed part is negative when it faces inward
+                let mut volume = 0.0;
+                for face in part.iter() {
+                    let (a, b, c) = self.tris[*face].verticies;
+                    let v = a.dot(b.cross(c));
+                    volume += if flip[*face] { -v } else { v };
+                }
+                volume < 0.0
+            } else {
+                part.iter().filter(|face| flip[**face]).count() * 2 > part.len()
+            };
+            if turn {
+                for face in part.iter() {
+                    flip[*face] = !flip[*face];
+                }
+            }
+        }
+
+        let mut flipped = 0;
+        for i in 0..self.tris.len() {
+            let tri = &mut self.tris[i];
+            if flip[i] {
+                tri.flip();
+                flipped += 1;
+            }
+            let normal = Triangle::calculate_normal(tri.verticies);
+            if !(normal.length() > 0.5) {
+                continue;
+            }
+            tri.normal = normal;
+            let fix = |n: Vec3d| if n.dot(normal) > 0.0 { n } else { normal };
+            let (n0, n1, n2) = tri.vertex_normals;
+            tri.vertex_normals = (fix(n0), fix(n1), fix(n2));
+        }
+        self.clear_lods();
+        flipped
+    }
+
+    /// Merges the verticies closer than an epsilon and moves them to
+    /// the same position so the Triangles share them. The vertex normals
+    /// around moved verticies are smoothed again and the points are made
+    /// from the welded verticies.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `epsilon` - How close verticies have to be to be merged.
+    ///
+    /// # Return
+    /// The Weld with the connectivity and how many verticies were merged
+    ///
+    pub fn weld(&mut self, epsilon: f32) -> Weld {
+        let weld = Weld::new(self, epsilon);
+        let mut moved = vec![false; self.tris.len()];
+        for i in 0..self.tris.len() {
+            let v = self.tris[i].verticies;
+            let old = [v.0, v.1, v.2];
+            for corner in 0..3 {
+                let position = weld.positions[weld.faces[i][corner]];
+                if (position - old[corner]).length() > 0.0 {
+                    self.tris[i].set_vertex(corner, position);
+                    moved[i] = true;
+                }
+            }
+        }
+        self.update_vertex_normals(&moved);
+        self.clear_lods();
+        self.refresh_points();
+        self.update_bounds();
+        weld
+    }
+
+    /// Builds a chain of levels of detail by simplifying the Mesh again
+    /// and again. Every level has `ratio` times the Triangles of the one
+    /// before. The first level is drawn below `size` pixels on the screen
+    /// and every next one below `sqrt(ratio)` times the size of the one
+    /// before, so the Triangles cover about the same number of pixels.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `levels` - How many levels are built.
+    /// * `ratio` - How many of the Triangles each level keeps.
+    /// * `size` - The size on the screen in pixels below which the first level is drawn.
+    ///
+    pub fn build_lods(&mut self, levels: usize, ratio: f32, size: f32) {
+        self.clear_lods();
+        let mut current = self.clone();
+        let mut max_size = size;
+        for _ in 0..levels {
+            let target = (current.tris.len() as f32 * ratio) as usize;
+            let next = current.simplify(target, f32::INFINITY);
+            if next.tris.len() >= current.tris.len() || next.tris.len() == 0 {
+                break;
+            }
+            self.lods.push(Lod {
+                tris: next.tris.clone(),
+                max_size: max_size,
+            });
+            max_size *= ratio.sqrt();
+            current = next;
+        }
+    }
+
+    /// Drops the levels of detail.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    pub fn clear_lods(&mut self) {
+        self.lods.clear();
+        self.lod = 0;
+    }
+
+    /// Picks the level of detail for the size of the Mesh on the screen.
+    /// The size has to pass the limit of a level by a margin before the
+    /// level changes so it does not flip back and forth at the limit.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `size` - The size of the Mesh on the screen in pixels.
+    ///
+    pub fn select_lod(&mut self, size: f32) {
+        let mut lod = self.lod.min(self.lods.len());
+        while lod < self.lods.len() && size < self.lods[lod].max_size * (1.0 - Mesh::LOD_HYSTERESIS)
+        {
+            lod += 1;
+        }
+        while lod > 0 && size > self.lods[lod - 1].max_size * (1.0 + Mesh::LOD_HYSTERESIS) {
+            lod -= 1;
+        }
+        self.lod = lod;
+    }
+
+    /// The Triangles of the level of detail that is drawn.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    /// # Return
+    /// The Triangles of the Mesh or of a simpler level
+    ///
+    pub fn lod_tris(&self) -> &Vec<Triangle> {
+        match self.lod {
+            0 => &self.tris,
+            lod => &self.lods[lod - 1].tris,
+        }
+    }
+
+    /// Replaces the Triangles with the ones of the level of detail that
+    /// is drawn. This is meant for a copy of the Mesh that is drawn.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    pub fn use_lod(&mut self) {
+        if self.lod > 0 && self.lod <= self.lods.len() {
+            self.tris = self.lods[self.lod - 1].tris.clone();
+            // the Bvh is for the full Triangles
+            self.bvh = None;
+        }
+        self.clear_lods();
+    }
+
+    /// Switches point mode on or off. When there are no points yet
+    /// the verticies of the Triangles become the points, colored
+    /// with their vertex colors.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `on` - If only the points are drawn.
+    ///
+    pub fn set_point_mode(&mut self, on: bool) {
+        if on && self.points.len() == 0 {
+            let mut seen: HashSet<(i64, i64, i64)> = HashSet::new();
+            for tri in self.tris.iter() {
+                let verticies = [tri.verticies.0, tri.verticies.1, tri.verticies.2];
+                let colors = [
+                    tri.vertex_colors.0,
+                    tri.vertex_colors.1,
+                    tri.vertex_colors.2,
+                ];
+                for i in 0..3 {
+                    if seen.insert(Mesh::vertex_key(verticies[i])) {
+                        self.points.push(verticies[i]);
+                        self.point_colors.push(colors[i]);
+                    }
+                }
+            }
+        }
+        // a Mesh without Triangles can only be drawn as points
+        self.point_mode = on || self.tris.len() == 0;
+    }
+
+    /// Makes the points of a Mesh with Triangles again from its verticies
+    /// after they changed. A Mesh without Triangles keeps its points.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    pub fn refresh_points(&mut self) {
+        if self.tris.len() == 0 {
+            return;
+        }
+        self.points.clear();
+        self.point_colors.clear();
+        if self.point_mode {
+            self.set_point_mode(true);
+        }
+    }
+
+    /// Collects the edges of the Triangles of the level of detail that is
+    /// drawn. An edge shared by several Triangles is only in the list once.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    /// # Return
+    /// The two ends of every edge
+    ///
+    pub fn edges(&self) -> Vec<(Vec3d, Vec3d)> {
+        let mut seen: HashSet<((i64, i64, i64), (i64, i64, i64))> = HashSet::new();
+        let mut edges: Vec<(Vec3d, Vec3d)> = Vec::new();
+        for tri in self.lod_tris().iter() {
+            let (a, b, c) = tri.verticies;
+            for (start, end) in [(a, b), (b, c), (c, a)].iter() {
+                let (k1, k2) = (Mesh::vertex_key(*start), Mesh::vertex_key(*end));
+                let key = if k1 < k2 { (k1, k2) } else { (k2, k1) };
+                if seen.insert(key) {
+                    edges.push((*start, *end));
+                }
+            }
+        }
+        edges
+    }
+
+    /// Add a Material to the material table.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `material` - The Material.
+    ///
+    /// # Return
+    /// The index of the Material
+    ///
+    pub fn add_material(&mut self, material: Material) -> usize {
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    /// Find a Material in the material table by name. Later Materials
+    /// hide earlier ones with the same name.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `name` - The name of the Material.
+    ///
+    /// # Return
+    /// The index of the Material or None if there is none with the name
+    ///
+    pub fn material_index(&self, name: &str) -> Option<usize> {
+        self.materials
+            .iter()
+            .rposition(|material| material.name == name)
+    }
+
+    /// Calculates how opaque the faces using a Material are.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `material` - The index of the Material.
+    ///
+    /// # Return
+    /// 0 for invisible up to 1 for opaque
+    ///
+    pub fn alpha(&self, material: usize) -> f32 {
+        let mut alpha = self.opacity * self.materials[material].base_color.3;
+        if self.x_ray {
+            alpha *= Mesh::X_RAY_OPACITY;
+        }
+        alpha
+    }
+
+    /// Make every face of the Mesh use a Material. The vertex colors are
+    /// reset so the faces get the color of the Material.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `material` - The index of the Material.
+    ///
+    pub fn assign_material(&mut self, material: usize) {
+        for tri in self.tris.iter_mut() {
+            tri.material = material;
+            tri.set_color(Triangle::DEFAULT_COLOR);
+        }
+    }
+
+    /// Give every face a color picked from its index. The same seed
+    /// always gives the same colors so they never change between frames.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `seed` - Picks the set of colors.
+    ///
+    pub fn seed_colors(&mut self, seed: u64) {
+        self.seed_colors_from(0, seed);
+    }
+
+    /// Give the faces from an index onwards a color picked from their index.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `first` - The index of the first face to color.
+    /// * `seed` - Picks the set of colors.
+    ///
+    fn seed_colors_from(&mut self, first: usize, seed: u64) {
+        for i in first..self.tris.len() {
+            self.tris[i].set_color(Mesh::face_color(seed, i));
+        }
+    }
+
+    /// Picks a soft color for a face by hashing its index.
+    ///
+    /// # Arguments
+    /// * `seed` - Picks the set of colors.
+    /// * `index` - The index of the face.
+    ///
+    /// # Return
+    /// The color
+    ///
+    pub fn face_color(seed: u64, index: usize) -> (f32, f32, f32) {
+        // splitmix64
+        let mut h = seed
+            .wrapping_add(index as u64)
+            .wrapping_mul(0x9E3779B97F4A7C15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D049BB133111EB);
+        h ^= h >> 31;
+
+        // a hue with fixed saturation and brightness so no face is too dark or bright
+        let hue = (h % 360) as f32 / 60.0;
+        let x = 1.0 - ((hue % 2.0) - 1.0).abs();
+        let (r, g, b) = match hue as u32 {
+            0 => (1.0, x, 0.0),
+            1 => (x, 1.0, 0.0),
+            2 => (0.0, 1.0, x),
+            3 => (0.0, x, 1.0),
+            4 => (x, 0.0, 1.0),
+            _ => (1.0, 0.0, x),
+        };
+        (0.35 + r * 0.55, 0.35 + g * 0.55, 0.35 + b * 0.55)
+    }
+
+    /// Make a Mesh from a file. Files with only points switch the Mesh
+    /// to point mode.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `filename` - The filename of the file containing the data.
+    ///
+    /// # Return
+    /// Success status
+    ///
+    #[allow(dead_code)]
+    pub fn from_file(&mut self, filename: &str) -> Result<(), Error> {
+        println!("In file {}", filename);
+        let file_type = filename.split('.').next_back().unwrap();
+        if file_type == "obj" {
+            self.from_obj(filename)?;
+        } else if file_type == "stl" {
+            match self.from_stl_ascii(filename) {
+                Ok(_) => (),
+                Err(_e) => self.from_stl_bin(filename),
+            }
+        } else if file_type == "gltf" || file_type == "glb" {
+            self.from_gltf(filename)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+        } else if file_type == "ply" {
+            self.from_ply(filename)?;
+        } else if file_type == "xyz" || file_type == "pts" {
+            self.from_xyz(filename)?;
+        }
+
+        // point clouds have no Triangles
+        if self.tris.len() == 0 && self.points.len() > 0 {
+            self.set_point_mode(true);
+        }
+        self.update_bounds();
+        Ok(())
+    }
+
+    /// Make a Mesh from a file like `from_file` and recompute the normals
+    /// and fix the winding of the Triangles (see `repair_normals`).
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `filename` - The filename of the file containing the data.
+    ///
+    /// # Return
+    /// How many Triangles were turned around
+    ///
+    pub fn from_file_repaired(&mut self, filename: &str) -> Result<usize, Error> {
+        self.from_file(filename)?;
+        Ok(self.repair_normals())
+    }
+
+    /// Make a Mesh from an obj file. Faces with more than 3 verticies
+    /// are split into triangles and texture coordinates are kept.
+    /// Materials from mtl files are added to the material table and
+    /// using a Material that is not in it is an error.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `filename` - The filename of the obj file containing the data.
+    ///
+    /// # Return
+    /// Success status
+    ///
+    pub fn from_obj(&mut self, filename: &str) -> Result<(), Error> {
+        let contents = fs::read_to_string(filename)?;
+
+        let dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+        let number = |word: Option<&str>| {
+            word.and_then(|word| word.parse::<f32>().ok())
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "bad obj number"))
+        };
+
+        let first = self.tris.len();
+        let mut points: Vec<Vec3d> = Vec::new();
+        let mut colors: Vec<Option<(f32, f32, f32)>> = Vec::new();
+        let mut uvs: Vec<(f32, f32)> = Vec::new();
+        let mut material = 0;
+        let mut uses_materials = false;
+        for line in contents.lines() {
+            let mut e = line.split_whitespace();
+            match e.next() {
+                Some("mtllib") => {
+                    for library in e {
+                        let library = dir.join(library).to_string_lossy().into_owned();
+                        let materials = Material::from_mtl(&library)
+                            .map_err(|e| Error::new(e.kind(), format!("{}: {}", library, e)))?;
+                        self.materials.extend(materials);
+                    }
+                }
+                Some("usemtl") => {
+                    let name = e.collect::<Vec<&str>>().join(" ");
+                    material = self.material_index(&name).ok_or_else(|| {
+                        Error::new(ErrorKind::InvalidData, format!("unknown material {}", name))
+                    })?;
+                    uses_materials = true;
+                }
+                Some("v") => {
+                    points.push(Vec3d {
+                        x: number(e.next())?,
+                        y: number(e.next())?,
+                        z: number(e.next())?,
+                    });
+                    // some exporters write a color after the position,
+                    // a color that is not made of numbers is left out
+                    let rgb: Option<Vec<f32>> = e.map(|c| c.parse::<f32>().ok()).collect();
+                    colors.push(match rgb {
+                        Some(rgb) if rgb.len() >= 3 => Some((rgb[0], rgb[1], rgb[2])),
+                        _ => None,
+                    });
+                }
+                Some("vt") => {
+                    let u = number(e.next())?;
+                    let v = match e.next() {
+                        Some(v) => number(Some(v))?,
+                        None => 0.0,
+                    };
+                    uvs.push((u, v));
+                }
+                Some("f") => {
+                    // every corner is v, v/vt, v//vn or v/vt/vn
+                    let corners = e
+                        .map(|corner| {
+                            let mut indices = corner.split('/');
+                            let v = Mesh::obj_index(indices.next().unwrap_or(""), points.len())?;
+                            let vt = match indices.next() {
+                                Some(vt) if vt.len() > 0 => {
+                                    Some(uvs[Mesh::obj_index(vt, uvs.len())?])
+                                }
+                                _ => None,
+                            };
+                            Ok((points[v], vt, colors[v]))
+                        })
+                        .collect::<Result<Vec<(Vec3d, Option<(f32, f32)>, Option<(f32, f32, f32)>)>, Error>>()?;
+
+                    // faces with less than 3 corners make no Triangles
+                    for i in 1..corners.len().saturating_sub(1) {
+                        let (a, b, c) = (corners[0], corners[i], corners[i + 1]);
+                        let mut tri = Triangle::new(a.0, b.0, c.0);
+                        tri.material = material;
+                        if let (Some(uv0), Some(uv1), Some(uv2)) = (a.1, b.1, c.1) {
+                            tri.uvs = (uv0, uv1, uv2);
+                        }
+                        if let (Some(c0), Some(c1), Some(c2)) = (a.2, b.2, c.2) {
+                            tri.vertex_colors = (c0, c1, c2);
+                        }
+                        self.tris.push(tri);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if !uses_materials && !colors.iter().any(|color| color.is_some()) {
+            self.seed_colors_from(first, 0);
+        }
+
+        // an obj file without faces is a point cloud
+        if self.tris.len() == first {
+            for i in 0..points.len() {
+                self.points.push(points[i]);
+                self.point_colors
+                    .push(colors[i].unwrap_or(Triangle::DEFAULT_COLOR));
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn an obj index into a vec index. Obj indices start at 1 and
+    /// negative indices count back from the end.
+    ///
+    /// # Arguments
+    /// * `index` - The index in the obj file.
+    /// * `len` - How many elements have been read so far.
+    ///
+    /// # Return
+    /// The index in the vec or an error if it is not a number or
+    /// points outside of the elements read so far
+    ///
+    fn obj_index(index: &str, len: usize) -> Result<usize, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, format!("bad obj index {}", index));
+        let index = index.parse::<i64>().map_err(|_| invalid())?;
+        let resolved = if index < 0 {
+            len as i64 + index
+        } else {
+            index - 1
+        };
+        if index == 0 || resolved < 0 || resolved >= len as i64 {
+            return Err(invalid());
+        }
+        Ok(resolved as usize)
+    }
+
+    /// Make a Mesh from a gltf or glb file. Every primitive of the default
+    /// scene is added with the transforms of its nodes applied. The
+    /// materials of the file are added to the material table.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `filename` - The filename of the gltf or glb file containing the data.
+    ///
+    /// # Return
+    /// Success status
+    ///
+    pub fn from_gltf(&mut self, filename: &str) -> Result<(), gltf::Error> {
+        let (document, buffers, images) = gltf::import(filename)?;
+        let first = self.tris.len();
+
+        let offset = self.materials.len();
+        for material in document.materials() {
+            let pbr = material.pbr_metallic_roughness();
+            let mut converted = Material::new((0.5, 0.5, 0.5), 32.0);
+            converted.name = material.name().unwrap_or("").to_string();
+            let base = pbr.base_color_factor();
+            converted.base_color = (base[0], base[1], base[2], base[3]);
+            let emissive = material.emissive_factor();
+            converted.emissive = (emissive[0], emissive[1], emissive[2]);
+            converted.set_roughness(pbr.roughness_factor());
+            converted.double_sided = material.double_sided();
+            if let Some(info) = pbr.base_color_texture() {
+                converted.base_color_texture =
+                    Mesh::gltf_texture(&images[info.texture().source().index()]);
+            }
+            if let Some(info) = material.emissive_texture() {
+                converted.emissive_texture =
+                    Mesh::gltf_texture(&images[info.texture().source().index()]);
+            }
+            self.materials.push(converted);
+        }
+
+        let identity = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let scene = match document.default_scene() {
+            Some(scene) => scene,
+            None => match document.scenes().next() {
+                Some(scene) => scene,
+                None => return Ok(()),
+            },
+        };
+        let mut colored = false;
+        for node in scene.nodes() {
+            colored |= self.gltf_node(&node, identity, &buffers, offset);
+        }
+        if !colored && offset == self.materials.len() {
+            self.seed_colors_from(first, 0);
+        }
+        Ok(())
+    }
+
+    /// Add the primitives of a gltf node and its children to the Mesh.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `node` - The node.
+    /// * `parent` - The transform of the parent node in column major order.
+    /// * `buffers` - The buffers of the gltf file.
+    /// * `offset` - Where the materials of the gltf file start in the material table.
+    ///
+    /// # Return
+    /// If any of the primitives had vertex colors
+    ///
+    fn gltf_node(
+        &mut self,
+        node: &gltf::Node,
+        parent: [[f32; 4]; 4],
+        buffers: &Vec<gltf::buffer::Data>,
+        offset: usize,
+    ) -> bool {
+        let local = node.transform().matrix();
+        let mut transform = [[0.0; 4]; 4];
+        for column in 0..4 {
+            for row in 0..4 {
+                for k in 0..4 {
+                    transform[column][row] += parent[k][row] * local[column][k];
+                }
+            }
+        }
+        let apply = |v: [f32; 3], w: f32| {
+            Vec3d::new(
+                transform[0][0] * v[0]
+                    + transform[1][0] * v[1]
+                    + transform[2][0] * v[2]
+                    + transform[3][0] * w,
+                transform[0][1] * v[0]
+                    + transform[1][1] * v[1]
+                    + transform[2][1] * v[2]
+                    + transform[3][1] * w,
+                transform[0][2] * v[0]
+                    + transform[1][2] * v[1]
+                    + transform[2][2] * v[2]
+                    + transform[3][2] * w,
+            )
+        };
+
+        // normals are turned by the inverse transpose so scaling along
+        // an axis tilts them the other way, its columns are the cross
+        // products of the columns of the transform over the determinant
+        let axis = |column: usize| {
+            Vec3d::new(
+                transform[column][0],
+                transform[column][1],
+                transform[column][2],
+            )
+        };
+        let (x, y, z) = (axis(0), axis(1), axis(2));
+        let det = x.dot(y.cross(z));
+        let normal_matrix = (y.cross(z), z.cross(x), x.cross(y));
+        let apply_normal = |n: [f32; 3]| {
+            let mut normal =
+                (normal_matrix.0 * n[0] + normal_matrix.1 * n[1] + normal_matrix.2 * n[2])
+                    * (1.0 / det);
+            normal.normalize()
+        };
+
+        let mut colored = false;
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    continue;
+                }
+                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                let positions: Vec<Vec3d> = match reader.read_positions() {
+                    Some(positions) => positions.map(|p| apply(p, 1.0)).collect(),
+                    None => continue,
+                };
+                let normals: Option<Vec<Vec3d>> = reader
+                    .read_normals()
+                    .map(|normals| normals.map(apply_normal).collect());
+                // gltf textures start at the top left
+                let uvs: Option<Vec<(f32, f32)>> = reader
+                    .read_tex_coords(0)
+                    .map(|uvs| uvs.into_f32().map(|uv| (uv[0], 1.0 - uv[1])).collect());
+                let colors: Option<Vec<(f32, f32, f32)>> = reader
+                    .read_colors(0)
+                    .map(|colors| colors.into_rgb_f32().map(|c| (c[0], c[1], c[2])).collect());
+                colored |= colors.is_some();
+                let material = match primitive.material().index() {
+                    Some(index) => offset + index,
+                    None => 0,
+                };
+                let indices: Vec<u32> = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..positions.len() as u32).collect(),
+                };
+
+                for i in 0..indices.len() / 3 {
+                    let a = indices[i * 3] as usize;
+                    let b = indices[i * 3 + 1] as usize;
+                    let c = indices[i * 3 + 2] as usize;
+                    let mut tri = Triangle::new(positions[a], positions[b], positions[c]);
+                    tri.material = material;
+                    if let Some(normals) = &normals {
+                        tri.vertex_normals = (normals[a], normals[b], normals[c]);
+                    }
+                    if let Some(uvs) = &uvs {
+                        tri.uvs = (uvs[a], uvs[b], uvs[c]);
+                    }
+                    if let Some(colors) = &colors {
+                        tri.vertex_colors = (colors[a], colors[b], colors[c]);
+                    }
+                    self.tris.push(tri);
+                }
+            }
+        }
+
+        for child in node.children() {
+            colored |= self.gltf_node(&child, transform, buffers, offset);
+        }
+        colored
+    }
+
+    /// Convert a decoded gltf image to a Texture.
+    ///
+    /// # Arguments
+    /// * `image` - The decoded image.
+    ///
+    /// # Return
+    /// The Texture or None if the pixel format is not supported
+    ///
+    fn gltf_texture(image: &gltf::image::Data) -> Option<Arc<Texture>> {
+        use gltf::image::Format;
+        let (channels, bgr) = match image.format {
+            Format::R8 => (1, false),
+            Format::R8G8 => (2, false),
+            Format::R8G8B8 => (3, false),
+            Format::R8G8B8A8 => (4, false),
+            Format::B8G8R8 => (3, true),
+            Format::B8G8R8A8 => (4, true),
+            _ => return None,
+        };
+        let mut pixels = image.pixels.clone();
+        if bgr {
+            for pixel in pixels.chunks_mut(channels) {
+                pixel.swap(0, 2);
+            }
+        }
+        Some(Arc::new(Texture::from_bytes(
+            image.width as usize,
+            image.height as usize,
+            channels,
+            &pixels,
+        )))
+    }
+
+    /// Make a point cloud from an xyz or pts file. Every line holds the
+    /// position of a point and optionally its color, either from 0 to 1
+    /// or from 0 to 255.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `filename` - The filename of the xyz or pts file containing the data.
+    ///
+    /// # Return
+    /// Success status
+    ///
+    pub fn from_xyz(&mut self, filename: &str) -> Result<(), std::io::Error> {
+        let contents = fs::read_to_string(filename)?;
+        for line in contents.lines() {
+            let values: Vec<f32> = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter_map(|value| value.parse::<f32>().ok())
+                .collect();
+            // pts files start with the number of points
+            if values.len() < 3 {
+                continue;
+            }
+            self.points
+                .push(Vec3d::new(values[0], values[1], values[2]));
+
+            // pts files put an intensity between the position and the color
+            let color = if values.len() >= 6 {
+                let rgb = &values[values.len() - 3..];
+                let scale = if rgb.iter().any(|c| *c > 1.0) {
+                    1.0 / 255.0
+                } else {
+                    1.0
+                };
+                (rgb[0] * scale, rgb[1] * scale, rgb[2] * scale)
+            } else {
+                Triangle::DEFAULT_COLOR
+            };
+            self.point_colors.push(color);
+        }
+        Ok(())
+    }
+
+    /// Make a Mesh from a ply file in ascii or binary. Verticies can have
+    /// colors. A file without faces is a point cloud.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `filename` - The filename of the ply file containing the data.
+    ///
+    /// # Return
+    /// Success status
+    ///
+    pub fn from_ply(&mut self, filename: &str) -> Result<(), std::io::Error> {
+        let data = fs::read(filename)?;
+        let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+
+        // header
+        let marker = b"end_header";
+        let end = data
+            .windows(marker.len())
+            .position(|window| window == marker)
+            .ok_or_else(|| invalid("ply file has no end_header"))?;
+        let mut body_start = end + marker.len();
+        while body_start < data.len() && data[body_start - 1] != b'\n' {
+            body_start += 1;
+        }
+        let header = String::from_utf8_lossy(&data[..end]);
+
+        // every element has a name, a count and properties with a name,
+        // a type and the type of the length if it is a list
+        let mut format = String::new();
+        let mut elements: Vec<(String, usize, Vec<(String, String, Option<String>)>)> = Vec::new();
+        for line in header.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.first() {
+                Some(&"format") if words.len() > 1 => format = words[1].to_string(),
+                Some(&"element") if words.len() > 2 => elements.push((
+                    words[1].to_string(),
+                    words[2]
+                        .parse::<usize>()
+                        .map_err(|_| invalid("bad element count"))?,
+                    Vec::new(),
+                )),
+                Some(&"property") if words.len() > 2 => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| invalid("property before element"))?;
+                    if words[1] == "list" && words.len() > 4 {
+                        element.2.push((
+                            words[4].to_string(),
+                            words[3].to_string(),
+                            Some(words[2].to_string()),
+                        ));
+                    } else {
+                        element
+                            .2
+                            .push((words[2].to_string(), words[1].to_string(), None));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        // body
+        let text = String::from_utf8_lossy(&data[body_start..]);
+        let mut ascii = text.split_whitespace();
+        let mut binary = &data[body_start..];
+        let mut positions: Vec<Vec3d> = Vec::new();
+        let mut colors: Vec<Option<(f32, f32, f32)>> = Vec::new();
+        let mut faces: Vec<Vec<usize>> = Vec::new();
+        for (name, count, properties) in elements.iter() {
+            for _ in 0..*count {
+                let mut position = Vec3d::new(0.0, 0.0, 0.0);
+                let mut color: (f32, f32, f32) = (0.0, 0.0, 0.0);
+                let mut has_color = false;
+                for (property, kind, list) in properties.iter() {
+                    if let Some(length_kind) = list {
+                        let length = Mesh::ply_value(&format, length_kind, &mut binary, &mut ascii)?
+                            as usize;
+                        // every value takes at least a byte so a bad length
+                        // can not reserve more than the size of the file
+                        let mut indices: Vec<usize> = Vec::with_capacity(length.min(data.len()));
+                        for _ in 0..length {
+                            indices
+                                .push(Mesh::ply_value(&format, kind, &mut binary, &mut ascii)?
+                                    as usize);
+                        }
+                        if name == "face"
+                            && (property == "vertex_indices" || property == "vertex_index")
+                        {
+                            faces.push(indices);
+                        }
+                        continue;
+                    }
+
+                    let value = Mesh::ply_value(&format, kind, &mut binary, &mut ascii)? as f32;
+                    // integer colors go up to 255
+                    let channel = if kind.starts_with("float") || kind == "double" {
+                        value
+                    } else {
+                        value / 255.0
+                    };
+                    match property.as_str() {
+                        "x" => position.x = value,
+                        "y" => position.y = value,
+                        "z" => position.z = value,
+                        "red" | "r" => {
+                            color.0 = channel;
+                            has_color = true;
+                        }
+                        "green" | "g" => color.1 = channel,
+                        "blue" | "b" => color.2 = channel,
+                        _ => (),
+                    }
+                }
+                if name == "vertex" {
+                    positions.push(position);
+                    colors.push(if has_color { Some(color) } else { None });
+                }
+            }
+        }
+
+        let first = self.tris.len();
+        for face in faces.iter() {
+            if face.iter().any(|index| *index >= positions.len()) {
+                return Err(invalid("face uses a vertex that does not exist"));
+            }
+            for i in 1..face.len().saturating_sub(1) {
+                let (a, b, c) = (face[0], face[i], face[i + 1]);
+                let mut tri = Triangle::new(positions[a], positions[b], positions[c]);
+                if let (Some(c0), Some(c1), Some(c2)) = (colors[a], colors[b], colors[c]) {
+                    tri.vertex_colors = (c0, c1, c2);
+                }
+                self.tris.push(tri);
+            }
+        }
+
+        if faces.len() == 0 {
+            for i in 0..positions.len() {
+                self.points.push(positions[i]);
+                self.point_colors
+                    .push(colors[i].unwrap_or(Triangle::DEFAULT_COLOR));
+            }
+        } else if !colors.iter().any(|color| color.is_some()) {
+            self.seed_colors_from(first, 0);
+        }
+        Ok(())
+    }
+
+    /// Read one value from the body of a ply file.
+    ///
+    /// # Arguments
+    /// * `format` - ascii, binary_little_endian or binary_big_endian.
+    /// * `kind` - The type of the value.
+    /// * `binary` - The rest of the body of a binary file.
+    /// * `ascii` - The rest of the words of an ascii file.
+    ///
+    /// # Return
+    /// The value
+    ///
+    fn ply_value(
+        format: &str,
+        kind: &str,
+        binary: &mut &[u8],
+        ascii: &mut std::str::SplitWhitespace,
+    ) -> Result<f64, std::io::Error> {
+        if format == "ascii" {
+            return ascii
+                .next()
+                .and_then(|word| word.parse::<f64>().ok())
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "bad ply value"));
+        }
+        let big = format == "binary_big_endian";
+        Ok(match kind {
+            "char" | "int8" => binary.read_i8()? as f64,
+            "uchar" | "uint8" => binary.read_u8()? as f64,
+            "short" | "int16" if big => binary.read_i16::<BigEndian>()? as f64,
+            "short" | "int16" => binary.read_i16::<LittleEndian>()? as f64,
+            "ushort" | "uint16" if big => binary.read_u16::<BigEndian>()? as f64,
+            "ushort" | "uint16" => binary.read_u16::<LittleEndian>()? as f64,
+            "int" | "int32" if big => binary.read_i32::<BigEndian>()? as f64,
+            "int" | "int32" => binary.read_i32::<LittleEndian>()? as f64,
+            "uint" | "uint32" if big => binary.read_u32::<BigEndian>()? as f64,
+            "uint" | "uint32" => binary.read_u32::<LittleEndian>()? as f64,
+            "float" | "float32" if big => binary.read_f32::<BigEndian>()? as f64,
+            "float" | "float32" => binary.read_f32::<LittleEndian>()? as f64,
+            "double" | "float64" if big => binary.read_f64::<BigEndian>()?,
+            "double" | "float64" => binary.read_f64::<LittleEndian>()?,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "unknown ply type")),
+        })
+    }
+
+    /// Make a Mesh from an stl binary file.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `filename` - The filename of the stl binary file containing the data.
+    ///
+    pub fn from_stl_bin(&mut self, filename: &str) {
+        let mut file = File::open(filename).expect("file not found");
+
+        // header
+        let mut header_buf = [0; 80];
+        let header = file
+            .read(&mut header_buf[..])
+            .expect("Something went wrong reading the file");
+        let _contents = String::from_utf8_lossy(&header_buf[..header]);
+
+        // number of triangles
+        let mut n_tris_buf = [0; 4];
+        let n_tris_raw = file
+            .read(&mut n_tris_buf[..])
+            .expect("Something went wrong reading the file");
+        let mut n_tris_dat = &n_tris_buf[..n_tris_raw];
+        let n_tris = n_tris_dat.read_u32::<LittleEndian>().unwrap() as i32;
+        println!("{:?}", n_tris);
+
+        let first = self.tris.len();
+        let mut colored = false;
+        let mut tri_buf = [0; 50];
+        for _i in 0..n_tris {
+            let tri_raw = file
+                .read(&mut tri_buf[..])
+                .expect("Something went wrong reading the file");
+            let mut data = &tri_buf[..tri_raw];
+            self.tris.push(Triangle::new_with_normal(
+                Vec3d {
+                    // Normal
+                    x: data.read_f32::<LittleEndian>().unwrap(),
+                    y: data.read_f32::<LittleEndian>().unwrap(),
+                    z: data.read_f32::<LittleEndian>().unwrap(),
+                },
+                Vec3d {
+                    // Verticies
+                    x: data.read_f32::<LittleEndian>().unwrap(),
+                    y: data.read_f32::<LittleEndian>().unwrap(),
+                    z: data.read_f32::<LittleEndian>().unwrap(),
+                },
+                Vec3d {
+                    x: data.read_f32::<LittleEndian>().unwrap(),
+                    y: data.read_f32::<LittleEndian>().unwrap(),
+                    z: data.read_f32::<LittleEndian>().unwrap(),
+                },
+                Vec3d {
+                    x: data.read_f32::<LittleEndian>().unwrap(),
+                    y: data.read_f32::<LittleEndian>().unwrap(),
+                    z: data.read_f32::<LittleEndian>().unwrap(),
+                },
+            ));
+
+            // Attribute byte count. VisCAM and SolidView store a 15 bit
+            // color in it and set the top bit when the color is used.
+            let attribute = data.read_u16::<LittleEndian>().unwrap();
+            if attribute & 0x8000 != 0 {
+                let channel = |shift: u16| ((attribute >> shift) & 0x1F) as f32 / 31.0;
+                let last = self.tris.len() - 1;
+                self.tris[last].set_color((channel(10), channel(5), channel(0)));
+                colored = true;
+            }
+        }
+
+        if !colored {
+            self.seed_colors_from(first, 0);
+        }
+    }
+
+    /// Make a Mesh from an stl ascii file.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh this function was called for.
+    /// * `filename` - The filename of the stl ascii file containing the data.
+    ///
+    /// # Return
+    /// Success status
+    ///
+    pub fn from_stl_ascii(&mut self, filename: &str) -> Result<(), std::io::Error> {
+        let contents = fs::read_to_string(filename)?;
+
+        let first = self.tris.len();
+        let mut points: Vec<Vec3d> = Vec::new();
+        let mut normal: Vec3d = Vec3d::new(0.0, 0.0, 0.0);
+        for line in contents.lines() {
+            let mut e = line.split_whitespace();
+            let s = e.next().unwrap();
+            if s == "facet" {
+                if e.next().unwrap() == "normal" {
+                    normal = Vec3d::new(
+                        e.next().unwrap().parse::<f32>().unwrap(),
+                        e.next().unwrap().parse::<f32>().unwrap(),
+                        e.next().unwrap().parse::<f32>().unwrap(),
+                    );
+                }
+            } else if s == "vertex" {
+                points.push(Vec3d::new(
+                    e.next().unwrap().parse::<f32>().unwrap(),
+                    e.next().unwrap().parse::<f32>().unwrap(),
+                    e.next().unwrap().parse::<f32>().unwrap(),
+                ));
+            } else if s == "endfacet" {
+                self.tris.push(Triangle::new_with_normal(
+                    normal, points[0], points[1], points[2],
+                ));
+                normal = Vec3d::new(0.0, 0.0, 0.0);
+                points = Vec::new();
+            }
+        }
+        // ascii stl files have no colors
+        self.seed_colors_from(first, 0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mesh::{Lod, Mesh},
+        vec3d::Vec3d,
+    };
+    use std::{fs, io::ErrorKind};
+
+    fn cube() -> Mesh {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        mesh
+    }
+
+    /// Writes a file to the temporary directory and returns its path.
+    fn write<C: AsRef<[u8]>>(name: &str, contents: C) -> String {
+        let path = std::env::temp_dir().join(format!("my-engine-{}", name));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn midpoint_subdivision_keeps_the_shape() {
+        let mut mesh = cube();
+        mesh.subdivide_midpoint(2, None);
+        assert_eq!(mesh.tris.len(), 12 * 4 * 4);
+        let report = mesh.validate();
+        assert!(report.is_printable());
+        assert!((report.volume - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn loop_subdivision_smooths_the_shape() {
+        let mut mesh = cube();
+        mesh.subdivide_loop(1, None);
+        assert_eq!(mesh.tris.len(), 12 * 4);
+        let report = mesh.validate();
+        assert!(report.is_printable());
+        // the corners are pulled in
+        assert!(report.volume < 1.0);
+        assert!(report.volume > 0.3);
+    }
+
+    #[test]
+    fn partial_subdivision_leaves_no_cracks() {
+        let mut mesh = cube();
+        let mut faces = vec![false; mesh.tris.len()];
+        faces[0] = true;
+        mesh.subdivide_midpoint(1, Some(&faces));
+        // the face is split in four and its three neighbours in two
+        assert_eq!(mesh.tris.len(), 12 + 3 + 3);
+        assert!(mesh.validate().is_printable());
+    }
+
+    #[test]
+    fn edited_vertex_is_smoothed_again() {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_grid(1.0, 1.0, 2, 2, false);
+        mesh.calculate_vertex_normals(180.0);
+        let (verticies, corners) = mesh.shared_verticies();
+        let middle = (0..verticies.len())
+            .find(|i| verticies[*i].length() < 1e-6)
+            .unwrap();
+        mesh.set_vertex(&corners[middle], Vec3d::new(0.0, 0.5, 0.0));
+        let faces: Vec<bool> = (0..mesh.tris.len()).map(|_| true).collect();
+        mesh.update_vertex_normals(&faces);
+        // the normal at the peak points straight up
+        for (tri, corner) in corners[middle].iter() {
+            let n = mesh.tris[*tri].vertex_normals;
+            let normal = [n.0, n.1, n.2][*corner];
+            assert!((normal - Vec3d::new(0.0, 1.0, 0.0)).length() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn obj_keeps_texture_coordinates() {
+        let file = write(
+            "uv.obj",
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+             f 1/1 2/2 3/3 4/4\n",
+        );
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.from_obj(&file).unwrap();
+        // the quad is split into two Triangles around the first corner
+        assert_eq!(mesh.tris.len(), 2);
+        assert_eq!(mesh.tris[0].uvs, ((0.0, 0.0), (1.0, 0.0), (1.0, 1.0)));
+        assert_eq!(mesh.tris[1].uvs, ((0.0, 0.0), (1.0, 1.0), (0.0, 1.0)));
+    }
+
+    #[test]
+    fn obj_counts_negative_indices_from_the_end() {
+        let file = write("negative.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\n");
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.from_obj(&file).unwrap();
+        assert_eq!(mesh.tris.len(), 1);
+        assert_eq!(mesh.tris[0].verticies.1.x, 1.0);
+    }
+
+    #[test]
+    fn obj_rejects_bad_indices_and_numbers() {
+        let bad = [
+            ("zero.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n"),
+            ("past.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n"),
+            ("before.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 -4\n"),
+            ("word.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 x\n"),
+            (
+                "uv.obj",
+                "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nf 1/1 2/2 3/1\n",
+            ),
+            ("number.obj", "v 0 zero 0\n"),
+            ("short.obj", "v 0 0\n"),
+            ("vt.obj", "vt 0.5 half\n"),
+        ];
+        for (name, contents) in bad.iter() {
+            let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+            let error = mesh.from_obj(&write(name, contents)).unwrap_err();
+            assert!(error.kind() == ErrorKind::InvalidData, "{}", name);
+        }
+    }
+
+    #[test]
+    fn gltf_normals_use_the_inverse_transpose() {
+        // a Triangle in the plane x + y = 0 stretched along x by its node
+        let file = write(
+            "stretched.gltf",
+            r#"{
+                "asset": {"version": "2.0"},
+                "scene": 0,
+                "scenes": [{"nodes": [0]}],
+                "nodes": [{"mesh": 0, "scale": [2.0, 1.0, 1.0]}],
+                "meshes": [{"primitives": [{"attributes": {"POSITION": 0, "NORMAL": 1}}]}],
+                "buffers": [{
+                    "byteLength": 72,
+                    "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAgL8AAAAAAAAAAAAAAAAAAIA/8wQ1P/MENT8AAAAA8wQ1P/MENT8AAAAA8wQ1P/MENT8AAAAA"
+                }],
+                "bufferViews": [
+                    {"buffer": 0, "byteOffset": 0, "byteLength": 36},
+                    {"buffer": 0, "byteOffset": 36, "byteLength": 36}
+                ],
+                "accessors": [
+                    {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                     "min": [0.0, -1.0, 0.0], "max": [1.0, 0.0, 1.0]},
+                    {"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3"}
+                ]
+            }"#,
+        );
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.from_gltf(&file).unwrap();
+        assert_eq!(mesh.tris.len(), 1);
+        // the stretched plane is x / 2 + y = 0
+        let mut expected = Vec3d::new(0.5, 1.0, 0.0);
+        let expected = expected.normalize();
+        let normal = mesh.tris[0].vertex_normals.0;
+        assert!((normal - expected).length() < 1e-5);
+        assert!(
+            normal
+                .dot(mesh.tris[0].verticies.1 - mesh.tris[0].verticies.0)
+                .abs()
+                < 1e-5
+        );
+    }
+
+    #[test]
+    fn obj_faces_use_their_material() {
+        write(
+            "used.mtl",
+            "newmtl blue\nKd 0 0 1\nnewmtl green\nKd 0 1 0\n",
+        );
+        let file = write(
+            "used.obj",
+            "mtllib my-engine-used.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             usemtl green\nf 1 2 3\nusemtl blue\nf 1 3 2\n",
+        );
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.from_obj(&file).unwrap();
+        assert_eq!(mesh.materials.len(), 3);
+        assert_eq!(mesh.materials[mesh.tris[0].material].name, "green");
+        assert_eq!(mesh.materials[mesh.tris[1].material].name, "blue");
+    }
+
+    #[test]
+    fn obj_with_an_unknown_material_is_rejected() {
+        let file = write(
+            "unknown.obj",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl gold\nf 1 2 3\n",
+        );
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        let error = mesh.from_obj(&file).unwrap_err();
+        assert!(error.kind() == ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn xyz_reads_points_and_colors() {
+        let file = write(
+            "cloud.pts",
+            "3\n0 0 0\n1 2 3 0.5 0.25 1\n4,5,6,10,255,0,51\n",
+        );
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.from_xyz(&file).unwrap();
+        assert_eq!(mesh.tris.len(), 0);
+        assert_eq!(mesh.points.len(), 3);
+        assert_eq!(mesh.points[2].z, 6.0);
+        // colors from 0 to 1 are kept and from 0 to 255 are scaled
+        assert_eq!(mesh.point_colors[1], (0.5, 0.25, 1.0));
+        let scaled = mesh.point_colors[2];
+        assert_eq!((scaled.0, scaled.1), (1.0, 0.0));
+        assert!((scaled.2 - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ascii_ply_without_faces_is_a_point_cloud() {
+        let file = write(
+            "cloud.ply",
+            "ply\nformat ascii 1.0\nelement vertex 2\n\
+             property float x\nproperty float y\nproperty float z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             end_header\n0 0 0 255 0 0\n1 1 1 0 0 255\n",
+        );
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.from_ply(&file).unwrap();
+        assert_eq!(mesh.tris.len(), 0);
+        assert_eq!(mesh.points.len(), 2);
+        assert_eq!(mesh.point_colors, vec![(1.0, 0.0, 0.0), (0.0, 0.0, 1.0)]);
+    }
+
+    #[test]
+    fn binary_ply_faces_become_triangles() {
+        let mut data = b"ply\nformat binary_little_endian 1.0\nelement vertex 4\n\
+            property float x\nproperty float y\nproperty float z\n\
+            element face 1\nproperty list uchar int vertex_indices\nend_header\n"
+            .to_vec();
+        for (x, y) in [(0.0f32, 0.0f32), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].iter() {
+            for value in [*x, *y, 0.0].iter() {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        data.push(4);
+        for index in 0..4i32 {
+            data.extend_from_slice(&index.to_le_bytes());
+        }
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.from_ply(&write("quad.ply", &data)).unwrap();
+        assert_eq!(mesh.tris.len(), 2);
+        assert_eq!(mesh.points.len(), 0);
+        let corner = mesh.tris[1].verticies.2;
+        assert_eq!((corner.x, corner.y, corner.z), (0.0, 1.0, 0.0));
+
+        // a face that uses a missing vertex is an error
+        let last = data.len() - 4;
+        data[last] = 9;
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        let error = mesh.from_ply(&write("broken.ply", &data)).unwrap_err();
+        assert!(error.kind() == ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn lod_does_not_flicker_around_a_limit() {
+        let mut mesh = cube();
+        for max_size in [100.0, 50.0] {
+            mesh.lods.push(Lod {
+                tris: mesh.tris[..6].to_vec(),
+                max_size: max_size,
+            });
+        }
+        mesh.select_lod(200.0);
+        assert_eq!(mesh.lod, 0);
+        // wobbling just around the limit keeps the level
+        for size in [99.0, 101.0, 98.0, 102.0, 90.0] {
+            mesh.select_lod(size);
+            assert_eq!(mesh.lod, 0);
+        }
+        // passing it by the margin switches once
+        mesh.select_lod(100.0 * (1.0 - Mesh::LOD_HYSTERESIS) - 1.0);
+        assert_eq!(mesh.lod, 1);
+        for size in [99.0, 101.0, 98.0, 110.0, 84.0] {
+            mesh.select_lod(size);
+            assert_eq!(mesh.lod, 1);
+        }
+        // and back only when it is clearly larger again
+        mesh.select_lod(100.0 * (1.0 + Mesh::LOD_HYSTERESIS) + 1.0);
+        assert_eq!(mesh.lod, 0);
+        // a tiny size skips straight to the simplest level
+        mesh.select_lod(1.0);
+        assert_eq!(mesh.lod, 2);
+        assert_eq!(mesh.lod_tris().len(), 6);
+    }
+
+    /// A cube as an obj file. The faces listed in `flipped` wind the
+    /// other way around.
+    fn cube_obj(flipped: &[usize]) -> String {
+        let mut obj = String::new();
+        for v in 0..8 {
+            obj += &format!("v {} {} {}\n", v & 1, (v >> 1) & 1, (v >> 2) & 1);
+        }
+        let faces = [
+            [1, 3, 4],
+            [1, 4, 2],
+            [5, 6, 8],
+            [5, 8, 7],
+            [1, 2, 6],
+            [1, 6, 5],
+            [3, 7, 8],
+            [3, 8, 4],
+            [1, 5, 7],
+            [1, 7, 3],
+            [2, 4, 8],
+            [2, 8, 6],
+        ];
+        for (i, [a, b, c]) in faces.iter().enumerate() {
+            if flipped.contains(&i) {
+                obj += &format!("f {} {} {}\n", a, c, b);
+            } else {
+                obj += &format!("f {} {} {}\n", a, b, c);
+            }
+        }
+        obj
+    }
+
+    /// Checks that every face normal points away from the middle of the cube.
+    fn faces_outward(mesh: &Mesh) -> bool {
+        mesh.tris.iter().all(|tri| {
+            let (a, b, c) = tri.verticies;
+            let middle = (a + b + c) * (1.0 / 3.0) - Vec3d::new(0.5, 0.5, 0.5);
+            let normal = (b - a).cross(c - a);
+            normal.dot(middle) > 0.0 && tri.normal.dot(middle) > 0.0
+        })
+    }
+
+    #[test]
+    fn repair_turns_flipped_faces_around() {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        let file = write("flipped.obj", cube_obj(&[2, 7]));
+        assert_eq!(mesh.from_file_repaired(&file).unwrap(), 2);
+        assert!(faces_outward(&mesh));
+        let report = mesh.validate();
+        assert_eq!(report.inconsistent_edges, 0);
+        assert!((report.volume - 1.0).abs() < 1e-4);
+        // a repaired Mesh has nothing left to turn
+        assert_eq!(mesh.repair_normals(), 0);
+    }
+
+    #[test]
+    fn repair_turns_an_inside_out_mesh_outward() {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        let file = write("inside-out.obj", cube_obj(&(0..12).collect::<Vec<_>>()));
+        mesh.from_file(&file).unwrap();
+        assert_eq!(mesh.validate().inconsistent_edges, 0);
+        assert_eq!(mesh.repair_normals(), 12);
+        assert!(faces_outward(&mesh));
+        assert!((mesh.validate().volume - 1.0).abs() < 1e-4);
     }
 }
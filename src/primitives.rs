@@ -0,0 +1,550 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use crate::{mesh::Mesh, triangle::Triangle, vec3d::Vec3d};
+
+/// A point on the outline of a shape that is turned around the y-axis:
+/// the distance from the axis, the height, the normal as the part away
+/// from the axis and the part along it, and the v texture coordinate.
+/// Two points at the same place with different normals make a hard edge.
+type ProfilePoint = (f32, f32, f32, f32, f32);
+
+/// Shapes built from Triangles like the cube. Every shape but the arrow
+/// is centered on the position of the Mesh, the arrow starts there. All
+/// of them have the y-axis up, have smooth normals
+/// pointing outward except along hard edges and can have texture
+/// coordinates. The Triangles replace the ones the Mesh had.
+impl Mesh {
+    /// Forms a sphere out of rings of Triangles around the y-axis.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `radius` - The radius of the sphere.
+    /// * `segments` - How many times the sphere is split around the y-axis.
+    /// * `rings` - How many times the sphere is split from the bottom to the top.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    pub fn form_uv_sphere(&mut self, radius: f32, segments: usize, rings: usize, uvs: bool) {
+        let rings = rings.max(2);
+        let profile: Vec<ProfilePoint> = (0..rings + 1)
+            .map(|k| {
+                let angle = PI * k as f32 / rings as f32;
+                let (out, up) = (Mesh::pole_sin(angle), -angle.cos());
+                (out * radius, up * radius, out, up, k as f32 / rings as f32)
+            })
+            .collect();
+        self.tris = Vec::new();
+        self.lathe(&profile, segments, uvs);
+        self.finish_shape();
+    }
+
+    /// Forms a sphere by splitting the faces of an icosahedron into four
+    /// again and again and pushing the new verticies onto the sphere.
+    /// The Triangles are all about the same size.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `radius` - The radius of the sphere.
+    /// * `subdivisions` - How many times the faces are split.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    pub fn form_icosphere(&mut self, radius: f32, subdivisions: usize, uvs: bool) {
+        let t = (1.0 + f32::sqrt(5.0)) / 2.0;
+        let mut points: Vec<Vec3d> = [
+            (-1.0, t, 0.0),
+            (1.0, t, 0.0),
+            (-1.0, -t, 0.0),
+            (1.0, -t, 0.0),
+            (0.0, -1.0, t),
+            (0.0, 1.0, t),
+            (0.0, -1.0, -t),
+            (0.0, 1.0, -t),
+            (t, 0.0, -1.0),
+            (t, 0.0, 1.0),
+            (-t, 0.0, -1.0),
+            (-t, 0.0, 1.0),
+        ]
+        .iter()
+        .map(|(x, y, z)| Vec3d::new(*x, *y, *z).normalize())
+        .collect();
+        let mut faces: Vec<[usize; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            // the point in the middle of every edge is only made once
+            let mut middles: HashMap<(usize, usize), usize> = HashMap::new();
+            let mut middle = |a: usize, b: usize, points: &mut Vec<Vec3d>| {
+                let key = if a < b { (a, b) } else { (b, a) };
+                *middles.entry(key).or_insert_with(|| {
+                    points.push(((points[a] + points[b]) * 0.5).normalize());
+                    points.len() - 1
+                })
+            };
+            let mut split = Vec::new();
+            for [a, b, c] in faces.iter() {
+                let ab = middle(*a, *b, &mut points);
+                let bc = middle(*b, *c, &mut points);
+                let ca = middle(*c, *a, &mut points);
+                split.push([*a, ab, ca]);
+                split.push([*b, bc, ab]);
+                split.push([*c, ca, bc]);
+                split.push([ab, bc, ca]);
+            }
+            faces = split;
+        }
+
+        self.tris = Vec::new();
+        for [a, b, c] in faces.iter() {
+            let normals = [points[*a], points[*b], points[*c]];
+            let mut coordinates = normals.iter().map(|n| {
+                let u = 0.5 + n.z.atan2(n.x) / (2.0 * PI);
+                (u, n.y.acos() / PI)
+            });
+            let mut uv = [
+                coordinates.next().unwrap(),
+                coordinates.next().unwrap(),
+                coordinates.next().unwrap(),
+            ];
+            // a pole has no u of its own
+            let pole = |k: usize| normals[k].x.abs() < 1e-6 && normals[k].z.abs() < 1e-6;
+            // faces across the seam wrap around instead of spanning the texture
+            let max_u = (0..3)
+                .filter(|k| !pole(*k))
+                .fold(0.0f32, |max, k| max.max(uv[k].0));
+            for k in (0..3).filter(|k| !pole(*k)) {
+                if max_u - uv[k].0 > 0.5 {
+                    uv[k].0 += 1.0;
+                }
+            }
+            // the pole takes the u between the other two corners
+            for k in (0..3).filter(|k| pole(*k)) {
+                uv[k].0 = (uv[(k + 1) % 3].0 + uv[(k + 2) % 3].0) * 0.5;
+            }
+            let corner = |k: usize| (normals[k] * radius, normals[k], uv[k]);
+            self.add_face([corner(0), corner(1), corner(2)], uvs);
+        }
+        self.finish_shape();
+    }
+
+    /// Forms a closed cylinder around the y-axis.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `radius` - The radius of the cylinder.
+    /// * `height` - The height of the cylinder.
+    /// * `segments` - How many times the cylinder is split around the y-axis.
+    /// * `stacks` - How many times the side is split from the bottom to the top.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    pub fn form_cylinder(
+        &mut self,
+        radius: f32,
+        height: f32,
+        segments: usize,
+        stacks: usize,
+        uvs: bool,
+    ) {
+        let (bottom, top) = (-height * 0.5, height * 0.5);
+        let stacks = stacks.max(1);
+        let mut profile: Vec<ProfilePoint> = vec![(0.0, bottom, 0.0, -1.0, 0.0)];
+        profile.push((radius, bottom, 0.0, -1.0, 0.0));
+        for k in 0..stacks + 1 {
+            let f = k as f32 / stacks as f32;
+            profile.push((radius, bottom + height * f, 1.0, 0.0, f));
+        }
+        profile.push((radius, top, 0.0, 1.0, 1.0));
+        profile.push((0.0, top, 0.0, 1.0, 1.0));
+        self.tris = Vec::new();
+        self.lathe(&profile, segments, uvs);
+        self.finish_shape();
+    }
+
+    /// Forms a closed cone around the y-axis with its tip at the top.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `radius` - The radius of the bottom of the cone.
+    /// * `height` - The height of the cone.
+    /// * `segments` - How many times the cone is split around the y-axis.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    pub fn form_cone(&mut self, radius: f32, height: f32, segments: usize, uvs: bool) {
+        let (bottom, top) = (-height * 0.5, height * 0.5);
+        let slope = f32::sqrt(height * height + radius * radius);
+        let (out, up) = (height / slope, radius / slope);
+        let profile: Vec<ProfilePoint> = vec![
+            (0.0, bottom, 0.0, -1.0, 0.0),
+            (radius, bottom, 0.0, -1.0, 0.0),
+            (radius, bottom, out, up, 0.0),
+            (0.0, top, out, up, 1.0),
+        ];
+        self.tris = Vec::new();
+        self.lathe(&profile, segments, uvs);
+        self.finish_shape();
+    }
+
+    /// Forms a torus lying on the xz-plane around the y-axis.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `major_radius` - The distance from the center to the middle of the tube.
+    /// * `minor_radius` - The radius of the tube.
+    /// * `segments` - How many times the torus is split around the y-axis.
+    /// * `sides` - How many times the tube is split around itself.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    pub fn form_torus(
+        &mut self,
+        major_radius: f32,
+        minor_radius: f32,
+        segments: usize,
+        sides: usize,
+        uvs: bool,
+    ) {
+        let sides = sides.max(3);
+        let profile: Vec<ProfilePoint> = (0..sides + 1)
+            .map(|k| {
+                let angle = 2.0 * PI * k as f32 / sides as f32;
+                let (out, up) = (-angle.cos(), -angle.sin());
+                (
+                    major_radius + out * minor_radius,
+                    up * minor_radius,
+                    out,
+                    up,
+                    k as f32 / sides as f32,
+                )
+            })
+            .collect();
+        self.tris = Vec::new();
+        self.lathe(&profile, segments, uvs);
+        self.finish_shape();
+    }
+
+    /// Forms a flat grid on the xz-plane facing up.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `width` - The size along the x-axis.
+    /// * `depth` - The size along the z-axis.
+    /// * `columns` - How many times the grid is split along the x-axis.
+    /// * `rows` - How many times the grid is split along the z-axis.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    pub fn form_grid(&mut self, width: f32, depth: f32, columns: usize, rows: usize, uvs: bool) {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let up = Vec3d::new(0.0, 1.0, 0.0);
+        let corner = |i: usize, j: usize| {
+            let (u, v) = (i as f32 / columns as f32, j as f32 / rows as f32);
+            let position = Vec3d::new((u - 0.5) * width, 0.0, (v - 0.5) * depth);
+            (position, up, (u, v))
+        };
+        self.tris = Vec::new();
+        for i in 0..columns {
+            for j in 0..rows {
+                let (a, b) = (corner(i, j), corner(i + 1, j));
+                let (c, d) = (corner(i + 1, j + 1), corner(i, j + 1));
+                self.add_face([a, b, c], uvs);
+                self.add_face([a, c, d], uvs);
+            }
+        }
+        self.finish_shape();
+    }
+
+    /// Forms a capsule around the y-axis: a cylinder with a half sphere
+    /// on each end.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `radius` - The radius of the cylinder and the half spheres.
+    /// * `height` - The height of the cylinder between the half spheres.
+    /// * `segments` - How many times the capsule is split around the y-axis.
+    /// * `rings` - How many times each half sphere is split from its pole to the cylinder.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    pub fn form_capsule(
+        &mut self,
+        radius: f32,
+        height: f32,
+        segments: usize,
+        rings: usize,
+        uvs: bool,
+    ) {
+        let rings = rings.max(1);
+        let total = height + 2.0 * radius;
+        let mut profile: Vec<ProfilePoint> = Vec::new();
+        for (center, first, last) in
+            [(-height * 0.5, 0, rings), (height * 0.5, rings, 2 * rings)].iter()
+        {
+            for k in *first..*last + 1 {
+                let angle = 0.5 * PI * k as f32 / rings as f32;
+                let (out, up) = (Mesh::pole_sin(angle), -angle.cos());
+                let y = center + up * radius;
+                profile.push((out * radius, y, out, up, (y + total * 0.5) / total));
+            }
+        }
+        self.tris = Vec::new();
+        self.lathe(&profile, segments, uvs);
+        self.finish_shape();
+    }
+
+    /// Forms an arrow along the y-axis from the origin up to its tip:
+    /// a thin cylinder with a cone on top.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `length` - The length from the bottom to the tip.
+    /// * `shaft_radius` - The radius of the cylinder.
+    /// * `head_radius` - The radius of the bottom of the cone.
+    /// * `head_length` - The height of the cone.
+    /// * `segments` - How many times the arrow is split around the y-axis.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    pub fn form_arrow(
+        &mut self,
+        length: f32,
+        shaft_radius: f32,
+        head_radius: f32,
+        head_length: f32,
+        segments: usize,
+        uvs: bool,
+    ) {
+        let head_length = head_length.min(length);
+        let neck = length - head_length;
+        let slope = f32::sqrt(head_length * head_length + head_radius * head_radius);
+        let (out, up) = (head_length / slope, head_radius / slope);
+        let v = |y: f32| y / length;
+        let profile: Vec<ProfilePoint> = vec![
+            (0.0, 0.0, 0.0, -1.0, 0.0),
+            (shaft_radius, 0.0, 0.0, -1.0, 0.0),
+            (shaft_radius, 0.0, 1.0, 0.0, 0.0),
+            (shaft_radius, neck, 1.0, 0.0, v(neck)),
+            (shaft_radius, neck, 0.0, -1.0, v(neck)),
+            (head_radius, neck, 0.0, -1.0, v(neck)),
+            (head_radius, neck, out, up, v(neck)),
+            (0.0, length, out, up, 1.0),
+        ];
+        self.tris = Vec::new();
+        self.lathe(&profile, segments, uvs);
+        self.finish_shape();
+    }
+
+    /// Turns an outline around the y-axis and adds the Triangles between
+    /// each pair of points next to each other. Triangles without area
+    /// at the axis and along hard edges are left out.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `profile` - The outline from the bottom to the top.
+    /// * `segments` - How many times the outline is turned around the y-axis.
+    /// * `uvs` - If texture coordinates are added.
+    ///
+    fn lathe(&mut self, profile: &Vec<ProfilePoint>, segments: usize, uvs: bool) {
+        let segments = segments.max(3);
+        let corner = |point: &ProfilePoint, j: usize| {
+            let (radius, y, out, up, v) = *point;
+            let u = j as f32 / segments as f32;
+            let (cos, sin) = ((2.0 * PI * u).cos(), (2.0 * PI * u).sin());
+            (
+                Vec3d::new(radius * cos, y, radius * sin),
+                Vec3d::new(out * cos, up, out * sin),
+                (u, v),
+            )
+        };
+        for k in 0..profile.len().saturating_sub(1) {
+            for j in 0..segments {
+                let (a, b) = (corner(&profile[k], j), corner(&profile[k], j + 1));
+                let (c, d) = (corner(&profile[k + 1], j + 1), corner(&profile[k + 1], j));
+                self.add_face([a, b, c], uvs);
+                self.add_face([a, c, d], uvs);
+            }
+        }
+    }
+
+    /// Adds a Triangle at the position of the Mesh. The Triangle is
+    /// turned around if it faces away from its vertex normals so it
+    /// always faces outward. Triangles without area are left out.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    /// * `corners` - The position, normal and texture coordinate of each corner.
+    /// * `uvs` - If the texture coordinates are kept.
+    ///
+    fn add_face(&mut self, corners: [(Vec3d, Vec3d, (f32, f32)); 3], uvs: bool) {
+        let [(a, na, ta), (b, nb, tb), (c, nc, tc)] = corners;
+        if (b - a).cross(c - a).length() < 1e-10 {
+            return;
+        }
+        let mut tri = Triangle::new(a, b, c) + self.pos;
+        tri.vertex_normals = (na, nb, nc);
+        if uvs {
+            tri.uvs = (ta, tb, tc);
+        }
+        if tri.normal.dot(na + nb + nc) < 0.0 {
+            tri.flip();
+            // the vertex normals were already right
+            tri.vertex_normals = (na, nc, nb);
+        }
+        self.tris.push(tri);
+    }
+
+    /// The sine of an angle that is exactly 0 at half turns so the
+    /// poles of round shapes are on the axis.
+    ///
+    /// # Arguments
+    /// * `angle` - The angle in radians.
+    ///
+    /// # Return
+    /// The sine
+    ///
+    fn pole_sin(angle: f32) -> f32 {
+        let sin = angle.sin();
+        if sin.abs() < 1e-6 {
+            0.0
+        } else {
+            sin
+        }
+    }
+
+    /// Colors a new shape and fits the bounds around it.
+    ///
+    /// # Arguments
+    /// * `self` - The Mesh the function was called for.
+    ///
+    fn finish_shape(&mut self) {
+        self.clear_lods();
+        self.bvh = None;
+        self.seed_colors(0);
+        self.update_bounds();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mesh::Mesh, vec3d::Vec3d};
+
+    fn shape(form: impl Fn(&mut Mesh)) -> Mesh {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        form(&mut mesh);
+        mesh
+    }
+
+    /// Every face of a shape that is round around its center faces away from it.
+    fn faces_out_from_center(mesh: &Mesh) {
+        for tri in mesh.tris.iter() {
+            assert!(tri.normal.dot(tri.center) > 0.0);
+        }
+    }
+
+    /// A closed shape is watertight, wound one way and faces outward.
+    fn closed(mesh: &Mesh) {
+        let report = mesh.validate();
+        assert!(report.is_watertight());
+        assert!(report.is_printable());
+    }
+
+    #[test]
+    fn uv_sphere() {
+        let mesh = shape(|mesh| mesh.form_uv_sphere(0.5, 16, 8, true));
+        // the faces at the poles are triangles instead of quads
+        assert_eq!(mesh.tris.len(), 16 * (2 * 8 - 2));
+        faces_out_from_center(&mesh);
+        closed(&mesh);
+    }
+
+    #[test]
+    fn icosphere() {
+        let mesh = shape(|mesh| mesh.form_icosphere(0.5, 2, true));
+        assert_eq!(mesh.tris.len(), 20 * 4 * 4);
+        faces_out_from_center(&mesh);
+        closed(&mesh);
+    }
+
+    #[test]
+    fn icosphere_poles() {
+        let mesh = shape(|mesh| mesh.form_icosphere(0.5, 1, true));
+        for tri in mesh.tris.iter() {
+            let (a, b, c) = tri.uvs;
+            // no face spans more than a small part of the texture around the y-axis
+            let (min, max) = (a.0.min(b.0).min(c.0), a.0.max(b.0).max(c.0));
+            assert!(max - min < 0.3);
+        }
+    }
+
+    #[test]
+    fn cylinder() {
+        let mesh = shape(|mesh| mesh.form_cylinder(0.5, 1.0, 16, 2, true));
+        assert_eq!(mesh.tris.len(), 2 * 16 + 2 * 2 * 16);
+        faces_out_from_center(&mesh);
+        closed(&mesh);
+    }
+
+    #[test]
+    fn cone() {
+        let mesh = shape(|mesh| mesh.form_cone(0.5, 1.0, 16, true));
+        assert_eq!(mesh.tris.len(), 2 * 16);
+        faces_out_from_center(&mesh);
+        closed(&mesh);
+    }
+
+    #[test]
+    fn capsule() {
+        let mesh = shape(|mesh| mesh.form_capsule(0.25, 0.5, 16, 4, true));
+        assert_eq!(mesh.tris.len(), 16 * 4 * 4);
+        faces_out_from_center(&mesh);
+        closed(&mesh);
+    }
+
+    #[test]
+    fn torus() {
+        let mesh = shape(|mesh| mesh.form_torus(0.35, 0.15, 24, 12, true));
+        assert_eq!(mesh.tris.len(), 2 * 24 * 12);
+        // away from the middle of the tube
+        for tri in mesh.tris.iter() {
+            let mut ring = Vec3d::new(tri.center.x, 0.0, tri.center.z);
+            let ring = ring.normalize() * 0.35;
+            assert!(tri.normal.dot(tri.center - ring) > 0.0);
+        }
+        closed(&mesh);
+    }
+
+    #[test]
+    fn grid() {
+        let mesh = shape(|mesh| mesh.form_grid(1.0, 1.0, 4, 3, true));
+        assert_eq!(mesh.tris.len(), 2 * 4 * 3);
+        for tri in mesh.tris.iter() {
+            assert!(tri.normal.y > 0.99);
+        }
+        let report = mesh.validate();
+        assert_eq!(report.open_edges, 2 * (4 + 3));
+        assert!((report.area - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn arrow() {
+        let mesh = shape(|mesh| mesh.form_arrow(1.0, 0.03, 0.08, 0.25, 16, true));
+        // a disk, the shaft, the ring under the head and the head
+        assert_eq!(mesh.tris.len(), 16 + 2 * 16 + 2 * 16 + 16);
+        assert!(mesh.aabb.min.y.abs() < 1e-6);
+        assert!((mesh.aabb.max.y - 1.0).abs() < 1e-6);
+        closed(&mesh);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::{mesh::PointShape, triangle::Triangle, vec3d::Vec3d};
+
+/// A Fragment is a point on a projected Triangle that covers a pixel.
+/// It knows the index of the Material of its Triangle.
+/// The position, normal and texture coordinate are interpolated
+/// perspective-correctly across the Triangle. The footprint is how
+/// much the texture coordinate changes from one pixel to the next.
+#[derive(Copy, Clone)]
+pub struct Fragment {
+    pub position: Vec3d,
+    pub normal: Vec3d,
+    pub color: (f32, f32, f32),
+    pub uv: (f32, f32),
+    pub footprint: f32,
+    pub material: usize,
+}
+
+/// A Rasterizer fills projected Triangles pixel by pixel on the CPU.
+/// It keeps a color buffer and a depth buffer so it can be used
+/// without a window.
+pub struct Rasterizer {
+    pub width: usize,
+    pub height: usize,
+    pub color_buffer: Vec<(f32, f32, f32)>,
+    pub depth_buffer: Vec<f32>,
+}
+
+impl Rasterizer {
+    /// Creates a new Rasterizer cleared to a background color.
+    ///
+    /// # Arguments
+    /// * `width` - The width of the image in pixels.
+    /// * `height` - The height of the image in pixels.
+    /// * `background` - The background color.
+    ///
+    /// # Return
+    /// A new Rasterizer
+    ///
+    pub fn new(width: usize, height: usize, background: (f32, f32, f32)) -> Rasterizer {
+        Rasterizer {
+            width: width,
+            height: height,
+            color_buffer: vec![background; width * height],
+            // The depth is 1 / distance so 0 is infinitely far away.
+            depth_buffer: vec![0.0; width * height],
+        }
+    }
+
+    /// Fills a projected Triangle. Every pixel whose center is inside the
+    /// Triangle and closer than what is already drawn gets colored by the
+    /// shader.
+    ///
+    /// # Arguments
+    /// * `self` - The Rasterizer the function was called for.
+    /// * `tri` - The projected Triangle.
+    /// * `shader` - Calculates the color of a Fragment.
+    ///
+    pub fn draw_triangle<F>(&mut self, tri: &Triangle, shader: &F)
+    where
+        F: Fn(&Fragment) -> (f32, f32, f32),
+    {
+        self.fill_triangle(tri, shader, 1.0);
+    }
+
+    /// Blends a projected Triangle over what is already drawn. The
+    /// Triangle is hidden behind closer surfaces but does not hide
+    /// anything itself, so transparent Triangles have to be drawn
+    /// after the opaque ones from back to front.
+    ///
+    /// # Arguments
+    /// * `self` - The Rasterizer the function was called for.
+    /// * `tri` - The projected Triangle.
+    /// * `shader` - Calculates the color of a Fragment.
+    /// * `alpha` - How opaque the Triangle is.
+    ///
+    pub fn blend_triangle<F>(&mut self, tri: &Triangle, shader: &F, alpha: f32)
+    where
+        F: Fn(&Fragment) -> (f32, f32, f32),
+    {
+        self.fill_triangle(tri, shader, alpha);
+    }
+
+    /// Fills a projected Triangle, blending it if it is not opaque.
+    ///
+    /// # Arguments
+    /// * `self` - The Rasterizer the function was called for.
+    /// * `tri` - The projected Triangle.
+    /// * `shader` - Calculates the color of a Fragment.
+    /// * `alpha` - How opaque the Triangle is.
+    ///
+    fn fill_triangle<F>(&mut self, tri: &Triangle, shader: &F, alpha: f32)
+    where
+        F: Fn(&Fragment) -> (f32, f32, f32),
+    {
+        let (a, b, c) = tri.verticies;
+        let min_x = f32::max(0.0, a.x.min(b.x).min(c.x).floor()) as usize;
+        let min_y = f32::max(0.0, a.y.min(b.y).min(c.y).floor()) as usize;
+        let max_x = f32::min(self.width as f32 - 1.0, a.x.max(b.x).max(c.x).ceil());
+        let max_y = f32::min(self.height as f32 - 1.0, a.y.max(b.y).max(c.y).ceil());
+        if max_x < 0.0 || max_y < 0.0 {
+            return;
+        }
+
+        for y in min_y..=(max_y as usize) {
+            for x in min_x..=(max_x as usize) {
+                let pixel = Vec3d::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+                let w = tri.barycentric(pixel);
+                if w.0 < -0.0001 || w.1 < -0.0001 || w.2 < -0.0001 {
+                    continue;
+                }
+
+                let depth = w.0 * a.z + w.1 * b.z + w.2 * c.z;
+                let index = y * self.width + x;
+                if depth <= self.depth_buffer[index] {
+                    continue;
+                }
+
+                let p = tri.perspective_weights(w);
+                let mut normal = Triangle::interpolate_vec3d(tri.vertex_normals, p);
+                let uv = tri.interpolate_uv(p);
+
+                // texture coordinates of the neighbouring pixels
+                let right = tri.interpolate_uv(
+                    tri.perspective_weights(tri.barycentric(pixel + Vec3d::new(1.0, 0.0, 0.0))),
+                );
+                let down = tri.interpolate_uv(
+                    tri.perspective_weights(tri.barycentric(pixel + Vec3d::new(0.0, 1.0, 0.0))),
+                );
+                let footprint = f32::max(
+                    f32::sqrt((right.0 - uv.0).powi(2) + (right.1 - uv.1).powi(2)),
+                    f32::sqrt((down.0 - uv.0).powi(2) + (down.1 - uv.1).powi(2)),
+                );
+
+                let fragment = Fragment {
+                    position: Triangle::interpolate_vec3d(tri.world_verticies, p),
+                    normal: normal.normalize(),
+                    color: tri.interpolate_color(p),
+                    uv: uv,
+                    footprint: footprint,
+                    material: tri.material,
+                };
+                let color = shader(&fragment);
+                if alpha >= 1.0 {
+                    self.depth_buffer[index] = depth;
+                    self.color_buffer[index] = color;
+                } else {
+                    let under = self.color_buffer[index];
+                    self.color_buffer[index] = (
+                        color.0 * alpha + under.0 * (1.0 - alpha),
+                        color.1 * alpha + under.1 * (1.0 - alpha),
+                        color.2 * alpha + under.2 * (1.0 - alpha),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Draws a projected line with a square pen. The depth along the
+    /// line is interpolated like on a Triangle. With the depth test on
+    /// the line is hidden where something drawn before is in front of it.
+    ///
+    /// # Arguments
+    /// * `self` - The Rasterizer the function was called for.
+    /// * `start` - The projected start of the line.
+    /// * `end` - The projected end of the line.
+    /// * `color` - The color of the line.
+    /// * `width` - The width of the line in pixels.
+    /// * `depth_test` - If the line can be hidden.
+    ///
+    pub fn draw_line(
+        &mut self,
+        start: Vec3d,
+        end: Vec3d,
+        color: (f32, f32, f32),
+        width: f32,
+        depth_test: bool,
+    ) {
+        // cut the line to the image (Liang-Barsky)
+        let delta = end - start;
+        let mut t0: f32 = 0.0;
+        let mut t1: f32 = 1.0;
+        let edges = [
+            (-delta.x, start.x),
+            (delta.x, self.width as f32 - start.x),
+            (-delta.y, start.y),
+            (delta.y, self.height as f32 - start.y),
+        ];
+        for (p, q) in edges.iter() {
+            if *p == 0.0 {
+                if *q < 0.0 {
+                    return;
+                }
+            } else {
+                let t = q / p;
+                if *p < 0.0 {
+                    t0 = t0.max(t);
+                } else {
+                    t1 = t1.min(t);
+                }
+            }
+        }
+        if !(t0 <= t1) {
+            return;
+        }
+
+        let steps = (f32::max(delta.x.abs(), delta.y.abs()) * (t1 - t0)).ceil() as usize + 1;
+        let reach = ((width - 1.0) * 0.5).round().max(0.0) as i64;
+        for i in 0..=steps {
+            let t = t0 + (t1 - t0) * (i as f32 / steps as f32);
+            let point = start + delta * t;
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let x = point.x.floor() as i64 + dx;
+                    let y = point.y.floor() as i64 + dy;
+                    if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+                        continue;
+                    }
+                    let index = y as usize * self.width + x as usize;
+                    // lines on a surface are at the same depth so they get a little room
+                    let stored = self.depth_buffer[index];
+                    if depth_test && stored > 0.0 && 1.0 / point.z > 1.0 / stored * 1.01 + 0.001 {
+                        continue;
+                    }
+                    self.color_buffer[index] = color;
+                }
+            }
+        }
+    }
+
+    /// Draws a projected point as a square or a disk facing the screen.
+    /// Every pixel of the point has the depth of its center.
+    ///
+    /// # Arguments
+    /// * `self` - The Rasterizer the function was called for.
+    /// * `point` - The projected point.
+    /// * `size` - The width of the point in pixels.
+    /// * `shape` - The shape of the point.
+    /// * `color` - The color of the point.
+    ///
+    pub fn draw_point(
+        &mut self,
+        point: Vec3d,
+        size: f32,
+        shape: PointShape,
+        color: (f32, f32, f32),
+    ) {
+        let radius = f32::max(size * 0.5, 0.5);
+        let min_x = f32::max(0.0, (point.x - radius).floor()) as i64;
+        let min_y = f32::max(0.0, (point.y - radius).floor()) as i64;
+        let max_x = i64::min(self.width as i64 - 1, (point.x + radius).ceil() as i64);
+        let max_y = i64::min(self.height as i64 - 1, (point.y + radius).ceil() as i64);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let dx = x as f32 + 0.5 - point.x;
+                let dy = y as f32 + 0.5 - point.y;
+                let inside = match shape {
+                    PointShape::Square => dx.abs() <= radius && dy.abs() <= radius,
+                    PointShape::Disk => dx * dx + dy * dy <= radius * radius,
+                };
+                let index = y as usize * self.width + x as usize;
+                if !inside || point.z <= self.depth_buffer[index] {
+                    continue;
+                }
+                self.depth_buffer[index] = point.z;
+                self.color_buffer[index] = color;
+            }
+        }
+    }
+
+    /// Converts the color buffer to rgba bytes.
+    ///
+    /// # Arguments
+    /// * `self` - The Rasterizer the function was called for.
+    ///
+    /// # Return
+    /// A vec of 4 bytes per pixel
+    ///
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::with_capacity(self.color_buffer.len() * 4);
+        for color in self.color_buffer.iter() {
+            out.push((color.0.clamp(0.0, 1.0) * 255.0) as u8);
+            out.push((color.1.clamp(0.0, 1.0) * 255.0) as u8);
+            out.push((color.2.clamp(0.0, 1.0) * 255.0) as u8);
+            out.push(255);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mesh::PointShape, rasterizer::Rasterizer, vec3d::Vec3d};
+
+    fn covered(rasterizer: &Rasterizer) -> usize {
+        rasterizer
+            .color_buffer
+            .iter()
+            .filter(|pixel| **pixel == (1.0, 1.0, 1.0))
+            .count()
+    }
+
+    #[test]
+    fn disks_are_rounder_than_squares() {
+        let center = Vec3d::new(10.0, 10.0, 0.5);
+        let mut square = Rasterizer::new(20, 20, (0.0, 0.0, 0.0));
+        square.draw_point(center, 8.0, PointShape::Square, (1.0, 1.0, 1.0));
+        let mut disk = Rasterizer::new(20, 20, (0.0, 0.0, 0.0));
+        disk.draw_point(center, 8.0, PointShape::Disk, (1.0, 1.0, 1.0));
+        assert_eq!(covered(&square), 64);
+        assert!(covered(&disk) < 64);
+        assert!(covered(&disk) > 40);
+    }
+
+    #[test]
+    fn closer_points_hide_farther_ones() {
+        let mut rasterizer = Rasterizer::new(10, 10, (0.0, 0.0, 0.0));
+        // z is 1 / distance so larger is closer
+        rasterizer.draw_point(
+            Vec3d::new(5.0, 5.0, 0.5),
+            4.0,
+            PointShape::Square,
+            (1.0, 1.0, 1.0),
+        );
+        rasterizer.draw_point(
+            Vec3d::new(5.0, 5.0, 0.25),
+            4.0,
+            PointShape::Square,
+            (1.0, 0.0, 0.0),
+        );
+        assert_eq!(covered(&rasterizer), 16);
+    }
+}
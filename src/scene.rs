@@ -1,27 +1,1047 @@
-use ggez::GameResult;
+use ggez::{graphics::Image, GameResult};
+use std::{
+    cmp::Ordering,
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
 
-use crate::{Camera, Mesh};
+use crate::{
+    color_mode::ColorMode,
+    gizmo::{Gizmo, GizmoMode},
+    light::{Attenuation, Light, Shading},
+    material::Material,
+    mesh::PointShape,
+    rasterizer::{Fragment, Rasterizer},
+    shadow::ShadowMap,
+    texture::Texture,
+    triangle::Triangle,
+    validation::MeshReport,
+    vertex_editor::VertexEditor,
+    weld::Weld,
+    Camera, Mesh, Vec3d,
+};
+
+/// What the Triangles are drawn as.
+///  - Filled      filled Triangles
+///  - Wireframe   every edge
+///  - HiddenLine  the edges that are not behind a Triangle
+#[derive(Copy, Clone, PartialEq)]
+pub enum RenderMode {
+    Filled,
+    Wireframe,
+    HiddenLine,
+}
+
+/// What is under a point on the screen: the index of the Mesh, the
+/// index of the Triangle in the Mesh, where the Triangle was hit and
+/// how far that is from the Camera.
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub struct Hit {
+    pub mesh: usize,
+    pub triangle: usize,
+    pub point: Vec3d,
+    pub distance: f32,
+}
 
 /// The Scene to be rendered.
+/// The Mesh under the mouse is hovered and the last one clicked is
+/// selected. Both are highlighted. The selected Mesh can be moved,
+/// turned and scaled with the Gizmo. In vertex mode the Gizmo changes
+/// the selected verticies of the Mesh instead. The problems found by
+/// validating a Mesh are drawn on top of it. The Images made from the
+/// Textures of the Materials are kept between frames. What loading a
+/// scene file did to the meshes is kept in the messages for the caller
+/// to report.
 pub struct Scene {
     pub camera: Camera,
     pub mesh_vec: Vec<Mesh>,
+    pub lights: Vec<Light>,
+    pub shading: Shading,
+    pub color_mode: ColorMode,
+    pub render_mode: RenderMode,
+    pub line_color: (f32, f32, f32),
+    pub line_width: f32,
+    pub background: (f32, f32, f32),
+    pub shadow_resolution: usize,
+    pub shadows: Vec<Option<ShadowMap>>,
+    pub shadow_key: u64,
+    pub culled_meshes: usize,
+    pub hovered: Option<Hit>,
+    pub selected: Option<usize>,
+    pub gizmo: Gizmo,
+    pub vertex_editor: VertexEditor,
+    pub report: Option<(usize, MeshReport)>,
+    pub texture_images: Vec<(Arc<Texture>, Image)>,
+    pub messages: Vec<String>,
 }
 
 impl Scene {
+    /// The color mixed into the selected Mesh.
+    pub const SELECT_COLOR: (f32, f32, f32) = (1.0, 0.55, 0.1);
+
+    /// The color mixed into the hovered Mesh.
+    pub const HOVER_COLOR: (f32, f32, f32) = (1.0, 1.0, 1.0);
+
     /// Creates a new Mesh with a camera
     ///
     /// # Arguments
     /// * `camera` - The Camera.
     /// * `mesh_vec` - A vec of all the meshes to render.
+    /// * `lights` - The Lights shining on the meshes.
     ///
     /// # Return
     /// A GameRusult<Scene> object
     ///
-    pub fn new(camera: Camera, mesh_vec: Vec<Mesh>) -> GameResult<Scene> {
+    pub fn new(camera: Camera, mesh_vec: Vec<Mesh>, lights: Vec<Light>) -> GameResult<Scene> {
         Ok(Scene {
             camera: camera,
             mesh_vec: mesh_vec,
+            lights: lights,
+            shading: Shading::Gouraud,
+            color_mode: ColorMode::Shaded,
+            render_mode: RenderMode::Filled,
+            line_color: (1.0, 1.0, 1.0),
+            line_width: 1.0,
+            background: (0.1, 0.2, 0.3),
+            shadow_resolution: 512,
+            shadows: Vec::new(),
+            shadow_key: 0,
+            culled_meshes: 0,
+            hovered: None,
+            selected: None,
+            gizmo: Gizmo::new(),
+            vertex_editor: VertexEditor::new(),
+            report: None,
+            texture_images: Vec::new(),
+            messages: Vec::new(),
         })
     }
+
+    /// Loads a Scene from a scene file. Every line starts with a keyword
+    /// followed by numbers or a filename. Lines starting with `#` are
+    /// comments. Paths are relative to the scene file. A line that can not
+    /// be used, like a mesh without a filename or an unknown Material,
+    /// stops the loading with an error.
+    ///
+    /// | keyword                                     | effect                               |
+    /// |---------------------------------------------|--------------------------------------|
+    /// | `camera x y z [rx ry]`                      | position and rotation of the Camera  |
+    /// | `background r g b`                          | background color                     |
+    /// | `lines r g b [width]`                       | color and width of wireframe lines   |
+    /// | `ambient r g b intensity`                   | adds an ambient Light                |
+    /// | `directional dx dy dz r g b intensity`      | adds a directional Light             |
+    /// | `point x y z r g b intensity range [falloff]` | adds a point Light                 |
+    /// | `spot x y z dx dy dz r g b intensity range inner outer [falloff]` | adds a spot Light |
+    /// | `shadows [bias pcf_radius]`                 | the last Light casts shadows         |
+    /// | `mesh file [x y z] [repair]`                | adds a Mesh loaded from a file, repairing its normals |
+    /// | `cube [x y z]`                              | adds a cube                          |
+    /// | `sphere\|icosphere\|cylinder\|cone [x y z]`   | adds a shape one unit across         |
+    /// | `torus\|grid\|capsule\|arrow [x y z]`         | adds a shape one unit across         |
+    /// | `smooth crease_angle`                       | smooths the normals of the last Mesh |
+    /// | `opacity a`                                 | how opaque the last Mesh is          |
+    /// | `xray`                                      | the last Mesh is see through         |
+    /// | `cull on\|off`                              | back-face culling of the last Mesh   |
+    /// | `points [size] [square\|disk]`              | draws only the points of the last Mesh |
+    /// | `bvh`                                       | builds a Bvh over the last Mesh      |
+    /// | `subdivide [midpoint\|loop] [iterations]`   | subdivides the last Mesh             |
+    /// | `simplify ratio\|triangles [tolerance]`      | simplifies the last Mesh             |
+    /// | `lod levels [ratio size]`                   | builds levels of detail for the last Mesh |
+    /// | `weld [epsilon]`                            | merges close verticies of the last Mesh |
+    /// | `mtllib file`                               | adds the Materials of an mtl file to the last Mesh |
+    /// | `newmtl name`                               | adds a Material to the last Mesh     |
+    /// | `usemtl name`                               | every face of the last Mesh uses the Material |
+    ///
+    /// The falloff of a point or spot Light is `constant`, `linear` or
+    /// `inverse_square` (the default).
+    ///
+    /// Any other keyword sets a property of the last `newmtl` Material
+    /// like in an mtl file (see `Material::apply_mtl`).
+    ///
+    /// # Arguments
+    /// * `filename` - The filename of the scene file.
+    ///
+    /// # Return
+    /// A GameRusult<Scene> object
+    ///
+    pub fn from_file(filename: &str) -> GameResult<Scene> {
+        let contents = fs::read_to_string(filename)?;
+        let dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+        let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+
+        let mut scene = Scene::new(
+            Camera::new(Vec3d::new(0.0, 0.0, -4.0), Vec3d::new(0.0, 0.0, 0.0)),
+            Vec::new(),
+            Vec::new(),
+        )?;
+        let mut material: Option<usize> = None;
+        for line in contents.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() == 0 || words[0].starts_with('#') {
+                continue;
+            }
+            let n = |i: usize| -> f32 {
+                words
+                    .get(i)
+                    .and_then(|word| word.parse::<f32>().ok())
+                    .unwrap_or(0.0)
+            };
+            let v = |i: usize| Vec3d::new(n(i), n(i + 1), n(i + 2));
+            let c = |i: usize| (n(i), n(i + 1), n(i + 2));
+            let falloff = |i: usize| match words.get(i) {
+                None | Some(&"inverse_square") => Ok(Attenuation::InverseSquare),
+                Some(&"linear") => Ok(Attenuation::Linear),
+                Some(&"constant") => Ok(Attenuation::Constant),
+                Some(_) => Err(invalid(
+                    "falloff must be constant, linear or inverse_square",
+                )),
+            };
+
+            match words[0] {
+                "camera" => scene.camera = Camera::new(v(1), Vec3d::new(n(4), n(5), 0.0)),
+                "background" => scene.background = c(1),
+                "lines" => {
+                    scene.line_color = c(1);
+                    if words.len() > 4 {
+                        scene.line_width = n(4);
+                    }
+                }
+                "ambient" => scene.lights.push(Light::ambient(c(1), n(4))),
+                "directional" => scene.lights.push(Light::directional(v(1), c(4), n(7))),
+                "point" => scene
+                    .lights
+                    .push(Light::point(v(1), c(4), n(7), n(8), falloff(9)?)),
+                "spot" => scene.lights.push(Light::spot(
+                    v(1),
+                    v(4),
+                    c(7),
+                    n(10),
+                    n(11),
+                    falloff(14)?,
+                    n(12),
+                    n(13),
+                )),
+                "shadows" => {
+                    if let Some(light) = scene.lights.last_mut() {
+                        if words.len() > 2 {
+                            light.cast_shadows(n(1), n(2) as i32);
+                        } else {
+                            light.cast_shadows(0.05, 1);
+                        }
+                    }
+                }
+                "mesh" | "cube" => {
+                    let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+                    if words[0] == "cube" {
+                        mesh.form_cube();
+                        mesh.increment_x(n(1));
+                        mesh.increment_y(n(2));
+                        mesh.increment_z(n(3));
+                    } else {
+                        let file = words
+                            .get(1)
+                            .ok_or_else(|| invalid("mesh needs a filename"))?;
+                        if words.last() == Some(&"repair") {
+                            let flipped = mesh.from_file_repaired(&path(file))?;
+                            scene.messages.push(format!(
+                                "{}: turned {} of {} triangles around",
+                                file,
+                                flipped,
+                                mesh.tris.len()
+                            ));
+                        } else {
+                            mesh.from_file(&path(file))?;
+                        }
+                        mesh.increment_x(n(2));
+                        mesh.increment_y(n(3));
+                        mesh.increment_z(n(4));
+                    }
+                    scene.mesh_vec.push(mesh);
+                    material = None;
+                }
+                "sphere" | "icosphere" | "cylinder" | "cone" | "torus" | "grid" | "capsule"
+                | "arrow" => {
+                    let mut mesh = Mesh::new(v(1));
+                    match words[0] {
+                        "sphere" => mesh.form_uv_sphere(0.5, 32, 16, true),
+                        "icosphere" => mesh.form_icosphere(0.5, 3, true),
+                        "cylinder" => mesh.form_cylinder(0.5, 1.0, 32, 1, true),
+                        "cone" => mesh.form_cone(0.5, 1.0, 32, true),
+                        "torus" => mesh.form_torus(0.35, 0.15, 48, 24, true),
+                        "grid" => mesh.form_grid(1.0, 1.0, 10, 10, true),
+                        "capsule" => mesh.form_capsule(0.25, 0.5, 32, 8, true),
+                        _ => mesh.form_arrow(1.0, 0.03, 0.08, 0.25, 24, true),
+                    }
+                    scene.mesh_vec.push(mesh);
+                    material = None;
+                }
+                keyword => {
+                    let mesh = match scene.mesh_vec.last_mut() {
+                        Some(mesh) => mesh,
+                        None => continue,
+                    };
+                    let name = words[1..].join(" ");
+                    match keyword {
+                        "smooth" => {
+                            mesh.calculate_vertex_normals(n(1));
+                            mesh.clear_lods();
+                        }
+                        "opacity" => mesh.opacity = n(1),
+                        "xray" => mesh.x_ray = true,
+                        "cull" => mesh.cull_back_faces = words.get(1) != Some(&"off"),
+                        "bvh" => mesh.build_bvh(),
+                        "simplify" => {
+                            // a ratio of the Triangles or a number of them
+                            let target = if n(1) > 1.0 {
+                                n(1) as usize
+                            } else {
+                                (mesh.tris.len() as f32 * n(1)) as usize
+                            };
+                            let tolerance = if words.len() > 2 { n(2) } else { f32::INFINITY };
+                            *mesh = mesh.simplify(target, tolerance);
+                        }
+                        "weld" => {
+                            let epsilon = if words.len() > 1 {
+                                n(1)
+                            } else {
+                                Weld::DEFAULT_EPSILON
+                            };
+                            let merged = mesh.weld(epsilon).merged;
+                            scene.messages.push(format!(
+                                "mesh {}: merged {} verticies",
+                                scene.mesh_vec.len() - 1,
+                                merged
+                            ));
+                        }
+                        "lod" => {
+                            let (ratio, size) = if words.len() > 3 {
+                                (n(2), n(3))
+                            } else {
+                                (0.5, 200.0)
+                            };
+                            mesh.build_lods(n(1) as usize, ratio, size);
+                        }
+                        "subdivide" => {
+                            let iterations = words
+                                .iter()
+                                .find_map(|word| word.parse::<usize>().ok())
+                                .unwrap_or(1);
+                            if words.get(1) == Some(&"loop") {
+                                mesh.subdivide_loop(iterations, None);
+                            } else {
+                                mesh.subdivide_midpoint(iterations, None);
+                            }
+                        }
+                        "points" => {
+                            mesh.set_point_mode(true);
+                            if n(1) > 0.0 {
+                                mesh.point_size = n(1);
+                            }
+                            if words.contains(&"disk") {
+                                mesh.point_shape = PointShape::Disk;
+                            }
+                        }
+                        "mtllib" => mesh.materials.extend(Material::from_mtl(&path(&name))?),
+                        "newmtl" => {
+                            let mut new = Material::new((0.5, 0.5, 0.5), 32.0);
+                            new.name = name;
+                            material = Some(mesh.add_material(new));
+                        }
+                        "usemtl" => match mesh.material_index(&name) {
+                            Some(index) => {
+                                mesh.assign_material(index);
+                                mesh.clear_lods();
+                            }
+                            None => {
+                                return Err(invalid(&format!("no material named {}", name)).into())
+                            }
+                        },
+                        _ => {
+                            if let Some(index) = material {
+                                mesh.materials[index].apply_mtl(keyword, &words[1..], dir)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(scene)
+    }
+
+    /// Renders the ShadowMaps of the Lights that cast shadows. They are
+    /// kept for the next frames until a Light or a Mesh changes.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    ///
+    pub fn update_shadows(&mut self) {
+        let key = ShadowMap::key(&self.lights, &self.mesh_vec, self.shadow_resolution);
+        if key != self.shadow_key || self.shadows.len() != self.lights.len() {
+            let meshes = &self.mesh_vec;
+            let resolution = self.shadow_resolution;
+            self.shadows = self
+                .lights
+                .iter()
+                .map(|light| ShadowMap::render(light, meshes, resolution))
+                .collect();
+            self.shadow_key = key;
+        }
+    }
+
+    /// Renders the Scene with the Rasterizer, lighting every pixel
+    /// with the Blinn-Phong model. Lights that cast shadows first render
+    /// a ShadowMap of the Scene, which is kept for the next frames until
+    /// a Light or a Mesh changes. Transparent Triangles are blended
+    /// over the opaque ones from back to front. Other color modes are
+    /// drawn without lighting and the line render modes only draw edges.
+    /// Meshes the Camera can not see are skipped. Does not need a window.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `size` - The dimensions of the image.
+    ///
+    /// # Return
+    /// The Rasterizer containing the rendered image
+    ///
+    pub fn rasterize(&mut self, size: (f32, f32)) -> Rasterizer {
+        if self.render_mode != RenderMode::Filled {
+            return self.rasterize_lines(size);
+        }
+
+        let visible = self.visible_meshes(size);
+        let mut rasterizer = Rasterizer::new(size.0 as usize, size.1 as usize, self.background);
+        self.update_shadows();
+        let lights = &self.lights;
+        let eye = self.camera.position;
+        let shadows = &self.shadows;
+
+        let shaded = self.color_mode.is_shaded();
+        let shade = |materials: &Vec<Material>, fragment: &Fragment| {
+            if !shaded {
+                return fragment.color;
+            }
+            let material = &materials[fragment.material];
+            let lit = Light::blinn_phong(
+                lights,
+                fragment.position,
+                fragment.normal,
+                eye,
+                material.albedo(fragment.color, fragment.uv, fragment.footprint),
+                material,
+                shadows,
+            );
+            Light::add_emission(lit, material.emission(fragment.uv, fragment.footprint))
+        };
+
+        // Draw the opaque triangles and keep the transparent ones for later.
+        let mut transparent: Vec<(usize, Triangle)> = Vec::new();
+        for i in 0..self.mesh_vec.len() {
+            if !visible[i] {
+                continue;
+            }
+            if self.mesh_vec[i].point_mode {
+                self.draw_points(&mut rasterizer, i, size);
+                continue;
+            }
+            let materials = &self.mesh_vec[i].materials;
+            let mut tris = self.camera.get_projected_triangles(&self.mesh_vec[i], size);
+            self.color_mode.apply(&mut tris, &self.mesh_vec[i], eye);
+
+            let shader = |fragment: &Fragment| self.highlight(i, shade(materials, fragment));
+            for j in 0..tris.len() {
+                if tris[j].alpha >= 1.0 {
+                    rasterizer.draw_triangle(&tris[j], &shader);
+                } else {
+                    transparent.push((i, tris[j]));
+                }
+            }
+        }
+
+        // Blend the transparent triangles of all the meshes from back to front.
+        transparent.sort_by(|a, b| b.1.dist.partial_cmp(&a.1.dist).unwrap_or(Ordering::Equal));
+        for (i, tri) in transparent.iter() {
+            let materials = &self.mesh_vec[*i].materials;
+            let shader = |fragment: &Fragment| self.highlight(*i, shade(materials, fragment));
+            rasterizer.blend_triangle(tri, &shader, tri.alpha);
+        }
+        self.rasterize_overlay(&mut rasterizer, size);
+        rasterizer
+    }
+
+    /// Finds the closest Triangle under a pixel. Meshes in point mode
+    /// can not be picked.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `pixel` - The position on the screen in pixels.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// What was hit or None
+    ///
+    pub fn pick(&self, pixel: (f32, f32), size: (f32, f32)) -> Option<Hit> {
+        let (origin, direction) = self.camera.ray_through(pixel, size);
+        let mut closest: Option<Hit> = None;
+        for i in 0..self.mesh_vec.len() {
+            let mesh = &self.mesh_vec[i];
+            if mesh.point_mode || !self.camera.sees(mesh, size) {
+                continue;
+            }
+            if let Some((triangle, distance)) = mesh.intersect_ray(origin, direction) {
+                if closest.is_none_or(|hit| distance < hit.distance) {
+                    closest = Some(Hit {
+                        mesh: i,
+                        triangle: triangle,
+                        point: origin + direction * distance,
+                        distance: distance,
+                    });
+                }
+            }
+        }
+        closest
+    }
+
+    /// Updates which Mesh is under the mouse.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `pixel` - The position of the mouse in pixels.
+    /// * `size` - The dimensions of the screen.
+    ///
+    pub fn hover(&mut self, pixel: (f32, f32), size: (f32, f32)) {
+        self.hovered = self.pick(pixel, size);
+        let hovered = self.hovered.map(|hit| hit.mesh);
+        for i in 0..self.mesh_vec.len() {
+            self.mesh_vec[i].is_over = hovered == Some(i);
+        }
+    }
+
+    /// Starts dragging the Gizmo if it is under the mouse when the mouse
+    /// is pressed. Otherwise the verticies are selected in vertex mode
+    /// and the hovered Mesh is selected and held if not. Pressing where
+    /// there is no Mesh clears the selection.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `pixel` - The position of the mouse in pixels.
+    /// * `size` - The dimensions of the screen.
+    /// * `add` - If the selected verticies are added to.
+    ///
+    pub fn press(&mut self, pixel: (f32, f32), size: (f32, f32), add: bool) {
+        if let (Some(selected), Some((center, axes))) = (self.selected, self.gizmo_frame()) {
+            if self.gizmo.start(center, axes, &self.camera, pixel, size) {
+                self.mesh_vec[selected].is_held = true;
+                return;
+            }
+        }
+        if self.vertex_editor.mesh.is_some() {
+            self.vertex_editor.press(&self.camera, pixel, size, add);
+            return;
+        }
+        self.selected = self.hovered.map(|hit| hit.mesh);
+        for i in 0..self.mesh_vec.len() {
+            self.mesh_vec[i].is_held = self.selected == Some(i);
+        }
+    }
+
+    /// Changes the selected Mesh or verticies while the Gizmo or the
+    /// verticies are dragged.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `pixel` - The position of the mouse in pixels.
+    /// * `size` - The dimensions of the screen.
+    ///
+    pub fn drag(&mut self, pixel: (f32, f32), size: (f32, f32)) {
+        let selected = match self.selected {
+            Some(selected) => selected,
+            None => return,
+        };
+        let mesh = &mut self.mesh_vec[selected];
+        if self.gizmo.drag.is_some() {
+            if let Some(change) = self.gizmo.drag_to(&self.camera, pixel, size) {
+                if self.vertex_editor.mesh.is_some() {
+                    self.vertex_editor.transform(mesh, change);
+                } else {
+                    change.apply(mesh);
+                }
+            }
+        } else if self.vertex_editor.mesh.is_some() {
+            self.vertex_editor.drag_to(mesh, &self.camera, pixel, size);
+        }
+    }
+
+    /// Lets go of the held Mesh, the Gizmo and the verticies when the
+    /// mouse is released. The Mesh stays selected.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `size` - The dimensions of the screen.
+    ///
+    pub fn release(&mut self, size: (f32, f32)) {
+        self.gizmo.end();
+        self.vertex_editor.release(&self.camera, size);
+        for mesh in self.mesh_vec.iter_mut() {
+            mesh.is_held = false;
+        }
+    }
+
+    /// Switches vertex mode on or off for the selected Mesh. Meshes in
+    /// point mode have no verticies to edit.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    ///
+    pub fn toggle_vertex_mode(&mut self) {
+        if self.vertex_editor.mesh.is_some() {
+            self.vertex_editor.end();
+            return;
+        }
+        if let Some(selected) = self.selected {
+            let mesh = &self.mesh_vec[selected];
+            if !mesh.point_mode && mesh.tris.len() > 0 {
+                self.vertex_editor.begin(selected, mesh);
+            }
+        }
+    }
+
+    /// Subdivides the selected Mesh once. In vertex mode only the faces
+    /// whose verticies are all selected are split if there are any.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `smooth` - If Loop subdivision smooths the Mesh.
+    ///
+    pub fn subdivide_selected(&mut self, smooth: bool) {
+        let selected = match self.selected {
+            Some(selected) if !self.mesh_vec[selected].point_mode => selected,
+            _ => return,
+        };
+        let mesh = &mut self.mesh_vec[selected];
+        let mut faces = None;
+        if self.vertex_editor.mesh.is_some() {
+            let selected_faces = self.vertex_editor.selected_faces(mesh);
+            if selected_faces.iter().any(|face| *face) {
+                faces = Some(selected_faces);
+            }
+        }
+        if smooth {
+            mesh.subdivide_loop(1, faces.as_ref());
+        } else {
+            mesh.subdivide_midpoint(1, faces.as_ref());
+        }
+        if self.vertex_editor.mesh.is_some() {
+            self.vertex_editor.begin(selected, mesh);
+        }
+        self.report = None;
+    }
+
+    /// Replaces the selected Mesh with a simplified copy. Vertex mode
+    /// ends because the verticies change.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `ratio` - How many of the Triangles are kept.
+    ///
+    pub fn simplify_selected(&mut self, ratio: f32) {
+        let selected = match self.selected {
+            Some(selected) if !self.mesh_vec[selected].point_mode => selected,
+            _ => return,
+        };
+        let mesh = &self.mesh_vec[selected];
+        let target = (mesh.tris.len() as f32 * ratio) as usize;
+        self.mesh_vec[selected] = mesh.simplify(target, f32::INFINITY);
+        self.vertex_editor.end();
+        self.report = None;
+    }
+
+    /// Validates the selected Mesh and shows its problems on top of the
+    /// Mesh with the summary of the MeshReport. Validating again hides them.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    ///
+    pub fn validate_selected(&mut self) {
+        if self.report.is_some() {
+            self.report = None;
+            return;
+        }
+        if let Some(selected) = self.selected {
+            self.report = Some((selected, self.mesh_vec[selected].validate()));
+        }
+    }
+
+    /// Moves the verticies of the selected Mesh to where its Transform
+    /// places them, so the Gizmo changes are kept in the Mesh itself.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    ///
+    pub fn apply_transform_selected(&mut self) {
+        if let Some(selected) = self.selected {
+            self.mesh_vec[selected].apply_transform();
+        }
+    }
+
+    /// Where the Gizmo is: the selected verticies in vertex mode or
+    /// else the selected Mesh. Scaling a Mesh stretches it along its own
+    /// axes so the scale handles point along them, everything else uses
+    /// the axes of the world.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    ///
+    /// # Return
+    /// The center of what the Gizmo changes and the directions of its
+    /// axes or None if there is no Gizmo
+    ///
+    pub fn gizmo_frame(&self) -> Option<(Vec3d, [Vec3d; 3])> {
+        let selected = self.selected?;
+        if self.vertex_editor.mesh.is_some() {
+            return Some((self.vertex_editor.selection_center()?, Gizmo::world_axes()));
+        }
+        let mesh = &self.mesh_vec[selected];
+        if mesh.point_mode {
+            return None;
+        }
+        let axes = if self.gizmo.mode == GizmoMode::Scale {
+            // the columns of the rotation are the axes of the Mesh
+            let rotation = mesh.transform.rotation.transpose();
+            let axis = |i: usize| {
+                let mut axis = Vec3d::new(rotation.m[i][0], rotation.m[i][1], rotation.m[i][2]);
+                axis.normalize()
+            };
+            [axis(0), axis(1), axis(2)]
+        } else {
+            Gizmo::world_axes()
+        };
+        Some((mesh.world_aabb().center(), axes))
+    }
+
+    /// Projects what is drawn on top of the Scene: the problems of the
+    /// validated Mesh, the handles of the verticies in vertex mode, the
+    /// Gizmo and the selection box.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// The lines with their width and color and the handles with their
+    /// size and color in the order they are drawn
+    ///
+    pub fn project_overlay(
+        &self,
+        size: (f32, f32),
+    ) -> (
+        Vec<(Vec3d, Vec3d, f32, (f32, f32, f32))>,
+        Vec<(Vec3d, f32, (f32, f32, f32))>,
+    ) {
+        let r = self.camera.rotation_matrix();
+        let mut lines = Vec::new();
+        if let Some((index, report)) = &self.report {
+            for (start, end, color) in report.lines(&self.mesh_vec[*index]) {
+                if let Some((start, end)) = self.camera.project_line(start, end, size, r.clone()) {
+                    lines.push((start, end, 2.0, color));
+                }
+            }
+        }
+        let mut handles: Vec<(Vec3d, f32, (f32, f32, f32))> = self
+            .vertex_editor
+            .handles(&self.camera, size)
+            .into_iter()
+            .map(|(center, color)| (center, VertexEditor::HANDLE_SIZE, color))
+            .collect();
+        if let Some((center, axes)) = self.gizmo_frame() {
+            for (start, end, _, color) in self.gizmo.lines(center, axes, &self.camera, size) {
+                lines.push((start, end, 2.0, color));
+            }
+            for (center, color) in self.gizmo.handles(center, axes, &self.camera, size) {
+                handles.push((center, 9.0, color));
+            }
+        }
+        for (start, end) in self.vertex_editor.box_lines() {
+            lines.push((start, end, 1.0, VertexEditor::HANDLE_COLOR));
+        }
+        (lines, handles)
+    }
+
+    /// Mixes the highlight of a Mesh into a color. Held and selected
+    /// meshes get more of the highlight than hovered ones.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `index` - The index of the Mesh.
+    /// * `color` - The color of a part of the Mesh.
+    ///
+    /// # Return
+    /// The highlighted color
+    ///
+    pub fn highlight(&self, index: usize, color: (f32, f32, f32)) -> (f32, f32, f32) {
+        let mesh = &self.mesh_vec[index];
+        let (tint, amount) = if mesh.is_held || self.selected == Some(index) {
+            (Scene::SELECT_COLOR, 0.4)
+        } else if mesh.is_over {
+            (Scene::HOVER_COLOR, 0.2)
+        } else {
+            return color;
+        };
+        (
+            color.0 + (tint.0 - color.0) * amount,
+            color.1 + (tint.1 - color.1) * amount,
+            color.2 + (tint.2 - color.2) * amount,
+        )
+    }
+
+    /// Checks which meshes can be seen by the Camera and counts the
+    /// ones that are culled. The meshes that can be seen pick their
+    /// level of detail from their size on the screen.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// If each Mesh can be seen
+    ///
+    pub fn visible_meshes(&mut self, size: (f32, f32)) -> Vec<bool> {
+        let mut visible = vec![false; self.mesh_vec.len()];
+        for i in 0..self.mesh_vec.len() {
+            if let Some(screen_size) = self.camera.projected_size(&self.mesh_vec[i], size) {
+                self.mesh_vec[i].select_lod(screen_size);
+                visible[i] = true;
+            }
+        }
+        self.culled_meshes = visible.iter().filter(|seen| !**seen).count();
+        visible
+    }
+
+    /// Projects the edges of every visible Mesh onto the screen. Edges
+    /// shared by Triangles are only projected once.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `size` - The dimensions of the screen.
+    /// * `visible` - If each Mesh can be seen.
+    ///
+    /// # Return
+    /// The projected ends of every edge in front of the Camera
+    ///
+    pub fn project_edges(&self, size: (f32, f32), visible: &Vec<bool>) -> Vec<(Vec3d, Vec3d)> {
+        let r = self.camera.rotation_matrix();
+        let mut lines: Vec<(Vec3d, Vec3d)> = Vec::new();
+        for i in 0..self.mesh_vec.len() {
+            let mesh = &self.mesh_vec[i];
+            if !visible[i] || mesh.point_mode {
+                continue;
+            }
+            for (start, end) in mesh.edges() {
+                let (start, end) = (mesh.transform.apply(start), mesh.transform.apply(end));
+                if let Some(line) = self.camera.project_line(start, end, size, r.clone()) {
+                    lines.push(line);
+                }
+            }
+        }
+        lines
+    }
+
+    /// Renders the edges of the Scene with the Rasterizer. For hidden
+    /// lines the Triangles are first filled with the background color so
+    /// that only the depth of the surfaces is kept.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `size` - The dimensions of the image.
+    ///
+    /// # Return
+    /// The Rasterizer containing the rendered image
+    ///
+    fn rasterize_lines(&mut self, size: (f32, f32)) -> Rasterizer {
+        let visible = self.visible_meshes(size);
+        let mut rasterizer = Rasterizer::new(size.0 as usize, size.1 as usize, self.background);
+        let hidden = self.render_mode == RenderMode::HiddenLine;
+        if hidden {
+            let background = self.background;
+            for i in 0..self.mesh_vec.len() {
+                if !visible[i] || self.mesh_vec[i].point_mode {
+                    continue;
+                }
+                let tris = self.camera.get_projected_triangles(&self.mesh_vec[i], size);
+                for j in 0..tris.len() {
+                    rasterizer.draw_triangle(&tris[j], &|_fragment: &Fragment| background);
+                }
+            }
+        }
+        for (start, end) in self.project_edges(size, &visible) {
+            rasterizer.draw_line(start, end, self.line_color, self.line_width, hidden);
+        }
+        for i in 0..self.mesh_vec.len() {
+            if visible[i] && self.mesh_vec[i].point_mode {
+                self.draw_points(&mut rasterizer, i, size);
+            }
+        }
+        self.rasterize_overlay(&mut rasterizer, size);
+        rasterizer
+    }
+
+    /// Draws the points of a Mesh in point mode with their own colors
+    /// and the highlight of the Mesh.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `rasterizer` - The Rasterizer to draw on.
+    /// * `index` - The index of the Mesh.
+    /// * `size` - The dimensions of the image.
+    ///
+    fn draw_points(&self, rasterizer: &mut Rasterizer, index: usize, size: (f32, f32)) {
+        let mesh = &self.mesh_vec[index];
+        let r = self.camera.rotation_matrix();
+        for i in 0..mesh.points.len() {
+            let point = mesh.transform.apply(mesh.points[i]);
+            let projected = self.camera.project_point(point, size, r.clone());
+            if !(projected.z > 0.0) {
+                continue;
+            }
+            let color = mesh
+                .point_colors
+                .get(i)
+                .copied()
+                .unwrap_or(Triangle::DEFAULT_COLOR);
+            let color = self.highlight(index, color);
+            rasterizer.draw_point(projected, mesh.point_size, mesh.point_shape, color);
+        }
+    }
+
+    /// Draws the handles, the Gizmo and the selection box on top of
+    /// everything else.
+    ///
+    /// # Arguments
+    /// * `self` - The Scene the function was called for.
+    /// * `rasterizer` - The Rasterizer to draw on.
+    /// * `size` - The dimensions of the image.
+    ///
+    fn rasterize_overlay(&self, rasterizer: &mut Rasterizer, size: (f32, f32)) {
+        let (lines, handles) = self.project_overlay(size);
+        // handles are drawn in front of everything so lines are drawn last
+        for (center, width, color) in handles {
+            let center = Vec3d::new(center.x, center.y, f32::INFINITY);
+            rasterizer.draw_point(center, width, PointShape::Square, color);
+        }
+        for (start, end, width, color) in lines {
+            rasterizer.draw_line(start, end, color, width, false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        camera::Camera,
+        gizmo::GizmoMode,
+        mesh::Mesh,
+        scene::{RenderMode, Scene},
+        vec3d::Vec3d,
+    };
+
+    const SIZE: (f32, f32) = (160.0, 120.0);
+
+    /// A Scene looking at a cube turned so three of its sides show.
+    fn cube_scene() -> Scene {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        mesh.y_axis_rotation(30.0, 0.0, 0.0);
+        mesh.x_axis_rotation(20.0, 0.0, 0.0);
+        let camera = Camera::new(Vec3d::new(0.0, 0.0, -4.0), Vec3d::new(0.0, 0.0, 0.0));
+        Scene::new(camera, vec![mesh], Vec::new()).unwrap()
+    }
+
+    fn count(scene: &mut Scene, color: (f32, f32, f32)) -> usize {
+        let rasterizer = scene.rasterize(SIZE);
+        rasterizer
+            .color_buffer
+            .iter()
+            .filter(|pixel| **pixel == color)
+            .count()
+    }
+
+    #[test]
+    fn hidden_lines_are_left_out() {
+        let mut scene = cube_scene();
+        let magenta = (1.0, 0.0, 1.0);
+        scene.line_color = magenta;
+        scene.render_mode = RenderMode::Wireframe;
+        let wireframe = count(&mut scene, magenta);
+        scene.render_mode = RenderMode::HiddenLine;
+        let hidden_line = count(&mut scene, magenta);
+        assert!(hidden_line > 0);
+        assert!(hidden_line < wireframe);
+        // the filled cube covers the lines
+        scene.render_mode = RenderMode::Filled;
+        assert_eq!(count(&mut scene, magenta), 0);
+    }
+
+    #[test]
+    fn pick_finds_the_closest_triangle_under_a_pixel() {
+        let mut front = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        front.form_cube();
+        let mut back = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        back.form_cube();
+        back.increment_z(3.0);
+        let camera = Camera::new(Vec3d::new(0.0, 0.0, -4.0), Vec3d::new(0.0, 0.0, 0.0));
+        let scene = Scene::new(camera, vec![back, front], Vec::new()).unwrap();
+
+        // a little below and right of the middle is the second front Triangle
+        let hit = scene
+            .pick((SIZE.0 * 0.5 + 5.0, SIZE.1 * 0.5 + 5.0), SIZE)
+            .unwrap();
+        assert_eq!(hit.mesh, 1);
+        assert_eq!(hit.triangle, 1);
+        assert!((hit.distance - 3.5).abs() < 0.05);
+        assert!((hit.point.z + 0.5).abs() < 1e-4);
+        // above and left of the middle is the first one
+        let hit = scene
+            .pick((SIZE.0 * 0.5 - 5.0, SIZE.1 * 0.5 - 5.0), SIZE)
+            .unwrap();
+        assert_eq!((hit.mesh, hit.triangle), (1, 0));
+        // the corner of the screen shows nothing
+        assert!(scene.pick((1.0, 1.0), SIZE).is_none());
+    }
+
+    #[test]
+    fn scale_handles_follow_the_axes_of_the_mesh() {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        // the x axis of the Mesh now points along the y axis of the world
+        let center = mesh.world_aabb().center();
+        mesh.transform.rotate(2, 90.0, center);
+        let camera = Camera::new(Vec3d::new(0.0, 0.0, -4.0), Vec3d::new(0.0, 0.0, 0.0));
+        let mut scene = Scene::new(camera, vec![mesh], Vec::new()).unwrap();
+        scene.selected = Some(0);
+        scene.gizmo.mode = GizmoMode::Scale;
+
+        let (_, axes) = scene.gizmo_frame().unwrap();
+        assert!(axes[0].x.abs() < 1e-4 && (axes[0].y.abs() - 1.0).abs() < 1e-4);
+        assert!(axes[0].z.abs() < 1e-4);
+        let (handle, _) = scene.gizmo.handles(center, axes, &scene.camera, SIZE)[0];
+        let middle = scene
+            .camera
+            .project_point(center, SIZE, scene.camera.rotation_matrix());
+        assert!((handle.x - middle.x).abs() < 0.5);
+        assert!((handle.y - middle.y).abs() > 5.0);
+
+        // dragging the handle away from the middle stretches the Mesh along y
+        let before = scene.mesh_vec[0].world_aabb();
+        scene.press((handle.x, handle.y), SIZE, false);
+        let to = (2.0 * handle.x - middle.x, 2.0 * handle.y - middle.y);
+        scene.drag(to, SIZE);
+        scene.release(SIZE);
+        let after = scene.mesh_vec[0].world_aabb();
+        assert!(scene.mesh_vec[0].transform.scale.x > 1.5);
+        assert_eq!(scene.mesh_vec[0].transform.scale.y, 1.0);
+        assert!((after.max.x - after.min.x - (before.max.x - before.min.x)).abs() < 1e-3);
+        assert!(after.max.y - after.min.y > 1.5 * (before.max.y - before.min.y));
+    }
 }
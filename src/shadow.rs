@@ -0,0 +1,340 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    bounds::Aabb,
+    camera::Camera,
+    light::{Light, LightKind},
+    matrix3x3::Matrix3x3,
+    mesh::Mesh,
+    rasterizer::{Fragment, Rasterizer},
+    triangle::Triangle,
+    vec3d::Vec3d,
+};
+
+/// How a ShadowMap sees the Scene.
+///  - Perspective    from the position of a spot Light through its cone
+///  - Orthographic   along the direction of a directional Light. The bounds
+///                   of the Scene in the space of the Light fill the map.
+#[derive(Copy, Clone)]
+pub enum ShadowProjection {
+    Perspective,
+    Orthographic(Aabb),
+}
+
+/// A ShadowMap is the depth of the Scene seen from a Light. A point
+/// is in shadow if something closer to the Light covers it on the map.
+pub struct ShadowMap {
+    pub camera: Camera,
+    pub rotation: Matrix3x3,
+    pub projection: ShadowProjection,
+    pub size: (f32, f32),
+    pub depth: Rasterizer,
+    pub bias: f32,
+    pub pcf_radius: i32,
+}
+
+impl ShadowMap {
+    /// Renders the depth of the meshes seen from a Light. Spot lights look
+    /// from their position, directional lights look along their direction
+    /// with an orthographic projection fitted around all of the meshes.
+    ///
+    /// # Arguments
+    /// * `light` - The Light casting the shadows.
+    /// * `meshes` - The meshes casting the shadows.
+    /// * `resolution` - The width and height of the map in pixels.
+    ///
+    /// # Return
+    /// The ShadowMap or None if the Light can not cast shadows
+    ///
+    pub fn render(light: &Light, meshes: &Vec<Mesh>, resolution: usize) -> Option<ShadowMap> {
+        if !light.casts_shadows {
+            return None;
+        }
+
+        let mut camera = Camera::new(light.position, Vec3d::new(0.0, 0.0, 0.0));
+        camera.look_along(light.direction);
+        let rotation = camera.rotation_matrix();
+        let projection = match light.kind {
+            LightKind::Spot => ShadowProjection::Perspective,
+            LightKind::Directional => {
+                ShadowProjection::Orthographic(ShadowMap::light_bounds(meshes, rotation.clone())?)
+            }
+            _ => return None,
+        };
+
+        let mut map = ShadowMap {
+            camera: camera,
+            rotation: rotation,
+            projection: projection,
+            size: (resolution as f32, resolution as f32),
+            depth: Rasterizer::new(resolution, resolution, (0.0, 0.0, 0.0)),
+            bias: light.shadow_bias,
+            pcf_radius: light.shadow_pcf_radius,
+        };
+
+        let no_color = |_fragment: &Fragment| (0.0, 0.0, 0.0);
+        for i in 0..meshes.len() {
+            let tris = match map.projection {
+                ShadowProjection::Perspective => {
+                    if !camera.sees(&meshes[i], map.size) {
+                        continue;
+                    }
+                    camera.get_projected_triangles(&meshes[i], map.size)
+                }
+                ShadowProjection::Orthographic(_) => map.project_orthographic(&meshes[i]),
+            };
+            for j in 0..tris.len() {
+                map.depth.draw_triangle(&tris[j], &no_color);
+            }
+        }
+        Some(map)
+    }
+
+    /// Sums up everything that changes the ShadowMaps of a Scene so
+    /// they only have to be rendered again when it is different. The
+    /// meshes are told apart by their generation, which counts the changes
+    /// to their verticies and Transform, so the verticies are not looked at.
+    ///
+    /// # Arguments
+    /// * `lights` - The Lights of the Scene.
+    /// * `meshes` - The meshes casting the shadows.
+    /// * `resolution` - The width and height of the maps in pixels.
+    ///
+    /// # Return
+    /// The key of the ShadowMaps
+    ///
+    pub fn key(lights: &Vec<Light>, meshes: &Vec<Mesh>, resolution: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let vec3d = |v: Vec3d, hasher: &mut DefaultHasher| {
+            v.x.to_bits().hash(hasher);
+            v.y.to_bits().hash(hasher);
+            v.z.to_bits().hash(hasher);
+        };
+        resolution.hash(&mut hasher);
+        for light in lights.iter() {
+            light.casts_shadows.hash(&mut hasher);
+            if !light.casts_shadows {
+                continue;
+            }
+            (light.kind as u8).hash(&mut hasher);
+            vec3d(light.position, &mut hasher);
+            vec3d(light.direction, &mut hasher);
+            light.shadow_bias.to_bits().hash(&mut hasher);
+            light.shadow_pcf_radius.hash(&mut hasher);
+        }
+        meshes.len().hash(&mut hasher);
+        for mesh in meshes.iter() {
+            mesh.generation.hash(&mut hasher);
+            mesh.tris.len().hash(&mut hasher);
+            mesh.cull_back_faces.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Calculates how much of a point is lit. Percentage closer filtering
+    /// compares the point against the pixels around it on the map and
+    /// averages the results to soften the edges of the shadow.
+    ///
+    /// # Arguments
+    /// * `self` - The ShadowMap the function was called for.
+    /// * `position` - The position of the point in the world.
+    ///
+    /// # Return
+    /// 0 if the point is in shadow up to 1 if it is lit
+    ///
+    pub fn visibility(&self, position: Vec3d) -> f32 {
+        let projected = self.project(position);
+        if !(projected.z > 0.0) {
+            return 1.0;
+        }
+        let dist = self.distance(projected.z);
+
+        let center_x = projected.x.floor() as i32;
+        let center_y = projected.y.floor() as i32;
+        let mut samples = 0;
+        let mut lit = 0;
+        for dy in -self.pcf_radius..=self.pcf_radius {
+            for dx in -self.pcf_radius..=self.pcf_radius {
+                let x = center_x + dx;
+                let y = center_y + dy;
+                samples += 1;
+                if x < 0 || y < 0 || x >= self.depth.width as i32 || y >= self.depth.height as i32 {
+                    lit += 1;
+                    continue;
+                }
+                let occluder = self.depth.depth_buffer[y as usize * self.depth.width + x as usize];
+                if occluder <= 0.0 || self.distance(occluder) >= dist - self.bias {
+                    lit += 1;
+                }
+            }
+        }
+        lit as f32 / samples as f32
+    }
+
+    /// Projects a point onto the map in pixels. Like on the screen the z
+    /// is bigger the closer the point is to the Light and more than 0
+    /// for everything the map can see.
+    ///
+    /// # Arguments
+    /// * `self` - The ShadowMap the function was called for.
+    /// * `position` - The position of the point in the world.
+    ///
+    /// # Return
+    /// The projected point
+    ///
+    pub fn project(&self, position: Vec3d) -> Vec3d {
+        match self.projection {
+            ShadowProjection::Perspective => {
+                self.camera
+                    .project_point(position, self.size, self.rotation.clone())
+            }
+            ShadowProjection::Orthographic(bounds) => {
+                // the depth changes linearly across an orthographic map
+                // so it can be interpolated like 1 / distance on the screen
+                let view = self.rotation.clone() * position;
+                let extent = bounds.max - bounds.min;
+                Vec3d::new(
+                    (view.x - bounds.min.x) / extent.x * self.size.0,
+                    (bounds.max.y - view.y) / extent.y * self.size.1,
+                    bounds.max.z - view.z,
+                )
+            }
+        }
+    }
+
+    /// How far from the Light a depth on the map is.
+    ///
+    /// # Arguments
+    /// * `self` - The ShadowMap the function was called for.
+    /// * `z` - The depth of a projected point.
+    ///
+    /// # Return
+    /// The distance along the direction of the Light
+    ///
+    fn distance(&self, z: f32) -> f32 {
+        match self.projection {
+            ShadowProjection::Perspective => 1.0 / z,
+            ShadowProjection::Orthographic(bounds) => bounds.max.z - z,
+        }
+    }
+
+    /// Projects the Triangles of a Mesh onto an orthographic map.
+    ///
+    /// # Arguments
+    /// * `self` - The ShadowMap the function was called for.
+    /// * `mesh` - The Mesh.
+    ///
+    /// # Return
+    /// The projected Triangles
+    ///
+    fn project_orthographic(&self, mesh: &Mesh) -> Vec<Triangle> {
+        let transform = &mesh.transform;
+        let mut tris = Vec::new();
+        for tri in mesh.tris.iter() {
+            let projected = Triangle::new(
+                self.project(transform.apply(tri.verticies.0)),
+                self.project(transform.apply(tri.verticies.1)),
+                self.project(transform.apply(tri.verticies.2)),
+            );
+            tris.append(&mut projected.clip(self.size.0, self.size.1));
+        }
+        tris
+    }
+
+    /// Fits a square box around all the meshes in the space of the Light.
+    /// It is a little bigger than the meshes so the edge of the map stays
+    /// empty and nothing is right in front of the Light.
+    ///
+    /// # Arguments
+    /// * `meshes` - The meshes.
+    /// * `rotation` - The rotation of the Light.
+    ///
+    /// # Return
+    /// The box or None if there are no Triangles
+    ///
+    fn light_bounds(meshes: &Vec<Mesh>, rotation: Matrix3x3) -> Option<Aabb> {
+        let mut scene = Aabb::empty();
+        for mesh in meshes.iter() {
+            if mesh.tris.len() > 0 {
+                scene = scene.merge(&mesh.world_aabb());
+            }
+        }
+        if scene.is_empty() {
+            return None;
+        }
+
+        let mut bounds = Aabb::empty();
+        for corner in scene.corners().iter() {
+            bounds.grow(rotation.clone() * *corner);
+        }
+        let center = bounds.center();
+        let extent = bounds.max - bounds.min;
+        let half = f32::max(extent.x, extent.y) * 0.5 * 1.05 + 0.001;
+        bounds.min = Vec3d::new(center.x - half, center.y - half, bounds.min.z - 1.0);
+        bounds.max = Vec3d::new(center.x + half, center.y + half, bounds.max.z + 1.0);
+        Some(bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{gizmo::GizmoChange, light::Light, mesh::Mesh, shadow::ShadowMap, vec3d::Vec3d};
+
+    /// A floor with a cube floating above its middle.
+    fn meshes() -> Vec<Mesh> {
+        let mut floor = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        floor.form_grid(6.0, 6.0, 6, 6, false);
+        let mut cube = Mesh::new(Vec3d::new(0.0, 1.5, 0.0));
+        cube.form_cube();
+        vec![floor, cube]
+    }
+
+    #[test]
+    fn directional_shadow_falls_under_the_cube() {
+        let mut sun = Light::directional(Vec3d::new(0.05, -1.0, 0.02), (1.0, 1.0, 1.0), 1.0);
+        sun.cast_shadows(0.01, 0);
+        let map = ShadowMap::render(&sun, &meshes(), 256).unwrap();
+        assert!(map.visibility(Vec3d::new(0.0, 0.0, 0.0)) < 0.5);
+        assert!(map.visibility(Vec3d::new(2.5, 0.0, 2.5)) > 0.5);
+        // the top of the cube is lit
+        assert!(map.visibility(Vec3d::new(0.0, 2.0, 0.0)) > 0.5);
+    }
+
+    #[test]
+    fn key_changes_when_a_mesh_moves() {
+        let sun = Light::directional(Vec3d::new(0.05, -1.0, 0.02), (1.0, 1.0, 1.0), 1.0);
+        let lights = vec![sun];
+        let mut meshes = meshes();
+        let key = ShadowMap::key(&lights, &meshes, 256);
+        assert_eq!(key, ShadowMap::key(&lights, &meshes, 256));
+        GizmoChange::Move(Vec3d::new(0.5, 0.0, 0.0)).apply(&mut meshes[1]);
+        let moved = ShadowMap::key(&lights, &meshes, 256);
+        assert_ne!(key, moved);
+        // moving a vertex changes it too
+        meshes[0].set_vertex(&vec![(0, 0)], Vec3d::new(0.0, 1.0, 0.0));
+        assert_ne!(moved, ShadowMap::key(&lights, &meshes, 256));
+    }
+
+    #[test]
+    fn flat_shading_darkens_the_shadow() {
+        let mut sun = Light::directional(Vec3d::new(0.05, -1.0, 0.02), (1.0, 1.0, 1.0), 1.0);
+        sun.cast_shadows(0.01, 0);
+        let mut meshes = meshes();
+        let shadows = vec![ShadowMap::render(&sun, &meshes, 256)];
+        Light::shade_mesh(&[sun], &mut meshes[0], &shadows);
+        let mut shadowed = 0;
+        for tri in meshes[0].tris.iter() {
+            let brightness = tri.interpolate_color((1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0)).0;
+            if tri.center.x.abs() < 0.45 && tri.center.z.abs() < 0.45 {
+                assert_eq!(brightness, 0.0);
+                shadowed += 1;
+            } else if tri.center.x.abs() > 1.5 || tri.center.z.abs() > 1.5 {
+                assert!(brightness > 0.0);
+            }
+        }
+        assert!(shadowed > 0);
+    }
+}
@@ -0,0 +1,769 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::{mesh::Mesh, triangle::Triangle, vec3d::Vec3d};
+
+/// A Quadric measures the squared distance of a point to a set of planes.
+/// It is kept as the upper half of a symmetric 4x4 matrix in f64 so that
+/// summing many planes stays precise.
+#[derive(Copy, Clone)]
+pub struct Quadric {
+    pub m: [f64; 10],
+}
+
+impl Quadric {
+    /// Creates a Quadric measuring nothing.
+    ///
+    /// # Return
+    /// A new Quadric
+    ///
+    pub fn zero() -> Quadric {
+        Quadric { m: [0.0; 10] }
+    }
+
+    /// Creates a Quadric measuring the squared distance to a plane.
+    ///
+    /// # Arguments
+    /// * `normal` - The unit normal of the plane.
+    /// * `point` - A point on the plane.
+    /// * `weight` - How much the plane counts.
+    ///
+    /// # Return
+    /// A new Quadric
+    ///
+    pub fn plane(normal: Vec3d, point: Vec3d, weight: f64) -> Quadric {
+        let (a, b, c) = (normal.x as f64, normal.y as f64, normal.z as f64);
+        let d = -(a * point.x as f64 + b * point.y as f64 + c * point.z as f64);
+        Quadric {
+            m: [
+                a * a * weight,
+                a * b * weight,
+                a * c * weight,
+                a * d * weight,
+                b * b * weight,
+                b * c * weight,
+                b * d * weight,
+                c * c * weight,
+                c * d * weight,
+                d * d * weight,
+            ],
+        }
+    }
+
+    /// Adds the planes of another Quadric.
+    ///
+    /// # Arguments
+    /// * `self` - The Quadric the function was called for.
+    /// * `other` - The other Quadric.
+    ///
+    /// # Return
+    /// The sum
+    ///
+    pub fn add(&self, other: &Quadric) -> Quadric {
+        let mut sum = *self;
+        for i in 0..10 {
+            sum.m[i] += other.m[i];
+        }
+        sum
+    }
+
+    /// The squared distance of a point to the planes.
+    ///
+    /// # Arguments
+    /// * `self` - The Quadric the function was called for.
+    /// * `p` - The point.
+    ///
+    /// # Return
+    /// The error
+    ///
+    pub fn error(&self, p: Vec3d) -> f64 {
+        let (x, y, z) = (p.x as f64, p.y as f64, p.z as f64);
+        let m = &self.m;
+        m[0] * x * x
+            + 2.0 * m[1] * x * y
+            + 2.0 * m[2] * x * z
+            + 2.0 * m[3] * x
+            + m[4] * y * y
+            + 2.0 * m[5] * y * z
+            + 2.0 * m[6] * y
+            + m[7] * z * z
+            + 2.0 * m[8] * z
+            + m[9]
+    }
+
+    /// The point closest to all of the planes.
+    ///
+    /// # Arguments
+    /// * `self` - The Quadric the function was called for.
+    ///
+    /// # Return
+    /// The point or None if the planes do not meet in one point
+    ///
+    pub fn minimum(&self) -> Option<Vec3d> {
+        let m = &self.m;
+        // solve A p = -b with Cramer's rule
+        let (a, b, c, e, f, i) = (m[0], m[1], m[2], m[4], m[5], m[7]);
+        let det = a * (e * i - f * f) - b * (b * i - f * c) + c * (b * f - e * c);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let (r0, r1, r2) = (-m[3], -m[6], -m[8]);
+        let x = (r0 * (e * i - f * f) - b * (r1 * i - f * r2) + c * (r1 * f - e * r2)) / det;
+        let y = (a * (r1 * i - f * r2) - r0 * (b * i - f * c) + c * (b * r2 - r1 * c)) / det;
+        let z = (a * (e * r2 - r1 * f) - b * (b * r2 - r1 * c) + r0 * (b * f - e * c)) / det;
+        Some(Vec3d::new(x as f32, y as f32, z as f32))
+    }
+}
+
+/// An edge that may be collapsed. The stamps of its ends tell if the
+/// ends changed since the cost was calculated. The distance is the
+/// squared distance summed over the planes without their weights.
+struct Collapse {
+    cost: f64,
+    distance: f64,
+    ends: (usize, usize),
+    stamps: (u32, u32),
+    position: Vec3d,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Collapse) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Collapse) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // the cheapest collapse comes first out of the max heap
+    fn cmp(&self, other: &Collapse) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// A face of the Mesh being simplified. Every corner has an index into
+/// the verticies and its own normal, color and uv.
+#[derive(Copy, Clone)]
+struct Face {
+    verticies: [usize; 3],
+    normals: [Vec3d; 3],
+    colors: [(f32, f32, f32); 3],
+    uvs: [(f32, f32); 3],
+    source: usize,
+    flat: bool,
+    alive: bool,
+}
+
+/// A Simplifier reduces the Triangles of a Mesh by collapsing edges
+/// into single verticies (Garland and Heckbert). Every vertex keeps a
+/// Quadric of the planes of the faces around it and the edge whose
+/// collapse moves the surface the least goes first.
+///
+/// Boundary edges and creases get planes across them that make moving
+/// away from them expensive, so outlines, hard edges and the borders
+/// between Materials, colors or uvs stay where they are. The same planes
+/// are also kept without weights to measure how far the surface moves.
+pub struct Simplifier {
+    positions: Vec<Vec3d>,
+    quadrics: Vec<Quadric>,
+    distances: Vec<Quadric>,
+    stamps: Vec<u32>,
+    faces: Vec<Face>,
+    vertex_faces: Vec<Vec<usize>>,
+    alive_faces: usize,
+}
+
+impl Simplifier {
+    /// The smallest angle in degrees between two faces that is kept as a crease.
+    pub const CREASE_ANGLE: f32 = 45.0;
+
+    /// How much more the planes across boundaries and creases count.
+    pub const EDGE_WEIGHT: f64 = 1000.0;
+
+    /// How far the normal of a face may turn in one collapse (the cosine).
+    pub const MIN_NORMAL_DOT: f32 = 0.2;
+
+    /// Prepares the Triangles of a Mesh. Triangles sharing verticies at
+    /// the same position are joined and Triangles without area are left out.
+    ///
+    /// # Arguments
+    /// * `mesh` - The Mesh.
+    ///
+    /// # Return
+    /// A new Simplifier
+    ///
+    pub fn new(mesh: &Mesh) -> Simplifier {
+        let (positions, corners) = mesh.shared_verticies();
+        let mut faces: Vec<Face> = mesh
+            .tris
+            .iter()
+            .enumerate()
+            .map(|(i, tri)| Face {
+                verticies: [0; 3],
+                normals: [
+                    tri.vertex_normals.0,
+                    tri.vertex_normals.1,
+                    tri.vertex_normals.2,
+                ],
+                colors: [
+                    tri.vertex_colors.0,
+                    tri.vertex_colors.1,
+                    tri.vertex_colors.2,
+                ],
+                uvs: [tri.uvs.0, tri.uvs.1, tri.uvs.2],
+                source: i,
+                flat: [
+                    tri.vertex_normals.0,
+                    tri.vertex_normals.1,
+                    tri.vertex_normals.2,
+                ]
+                .iter()
+                .all(|n| (*n - tri.normal).length() < 0.0001),
+                alive: true,
+            })
+            .collect();
+        let mut vertex_faces: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+        for v in 0..corners.len() {
+            for (tri, corner) in corners[v].iter() {
+                faces[*tri].verticies[*corner] = v;
+                vertex_faces[v].push(*tri);
+            }
+        }
+        // faces with two corners at the same place have no edges to collapse
+        let mut alive_faces = faces.len();
+        for face in faces.iter_mut() {
+            let v = face.verticies;
+            if v[0] == v[1] || v[1] == v[2] || v[2] == v[0] {
+                face.alive = false;
+                alive_faces -= 1;
+            }
+        }
+
+        let mut simplifier = Simplifier {
+            quadrics: vec![Quadric::zero(); positions.len()],
+            distances: vec![Quadric::zero(); positions.len()],
+            stamps: vec![0; positions.len()],
+            positions: positions,
+            alive_faces: alive_faces,
+            faces: faces,
+            vertex_faces: vertex_faces,
+        };
+        simplifier.build_quadrics(mesh);
+        simplifier
+    }
+
+    /// Collapses edges until there are no more Triangles than the
+    /// target or no collapse is left that keeps the surface within the
+    /// tolerance. A new vertex may be no further than the tolerance from
+    /// any plane of the faces it replaces (the squared distances summed
+    /// over the planes stay below the squared tolerance).
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `target` - The number of Triangles to stop at.
+    /// * `tolerance` - The largest distance the surface may move.
+    ///
+    pub fn run(&mut self, target: usize, tolerance: f32) {
+        let max_distance = tolerance as f64 * tolerance as f64;
+        let mut heap: BinaryHeap<Collapse> = BinaryHeap::new();
+        for f in 0..self.faces.len() {
+            let v = self.faces[f].verticies;
+            for i in 0..3 {
+                let (a, b) = (v[i], v[(i + 1) % 3]);
+                // every edge once (both halves of a shared edge have the same ends)
+                if a < b || self.edge_faces(a, b).len() == 1 {
+                    heap.push(self.collapse(a, b));
+                }
+            }
+        }
+
+        while self.alive_faces > target {
+            let next = match heap.pop() {
+                Some(next) => next,
+                None => break,
+            };
+            let (a, b) = next.ends;
+            if (self.stamps[a], self.stamps[b]) != next.stamps {
+                continue;
+            }
+            // a cheaper collapse may still move the surface too far
+            if next.distance > max_distance {
+                continue;
+            }
+            if !self.can_collapse(a, b, next.position) {
+                continue;
+            }
+            self.apply(a, b, next.position);
+            let mut around: Vec<usize> = self.neighbours(b);
+            around.sort();
+            around.dedup();
+            for other in around {
+                heap.push(self.collapse(b, other));
+            }
+        }
+    }
+
+    /// Builds a Mesh from the faces that are left. It keeps the
+    /// Materials and settings of the original Mesh. The points of
+    /// point mode are made from the verticies that are left.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `mesh` - The original Mesh.
+    ///
+    /// # Return
+    /// The simplified Mesh
+    ///
+    pub fn to_mesh(&self, mesh: &Mesh) -> Mesh {
+        let mut tris: Vec<Triangle> = Vec::with_capacity(self.alive_faces);
+        for face in self.faces.iter().filter(|face| face.alive) {
+            let mut tri = mesh.tris[face.source];
+            let p = |i: usize| self.positions[face.verticies[i]];
+            tri.verticies = (p(0), p(1), p(2));
+            tri.normal = Triangle::calculate_normal(tri.verticies);
+            tri.center = Triangle::calculate_center(tri.verticies);
+            tri.vertex_normals = if face.flat {
+                (tri.normal, tri.normal, tri.normal)
+            } else {
+                (face.normals[0], face.normals[1], face.normals[2])
+            };
+            tri.vertex_colors = (face.colors[0], face.colors[1], face.colors[2]);
+            tri.uvs = (face.uvs[0], face.uvs[1], face.uvs[2]);
+            tris.push(tri);
+        }
+
+        let mut simplified = Mesh::new(mesh.pos);
+        simplified.tris = tris;
+        simplified.materials = mesh.materials.clone();
+        simplified.opacity = mesh.opacity;
+        simplified.x_ray = mesh.x_ray;
+        simplified.cull_back_faces = mesh.cull_back_faces;
+        simplified.transform = mesh.transform.clone();
+        simplified.generation = mesh.generation;
+        simplified.crease_angle = mesh.crease_angle;
+        simplified.point_size = mesh.point_size;
+        simplified.point_shape = mesh.point_shape;
+        simplified.set_point_mode(mesh.point_mode);
+        if mesh.bvh.is_some() {
+            simplified.build_bvh();
+        }
+        simplified.update_bounds();
+        simplified
+    }
+
+    /// Adds the plane of every face to its verticies and the planes
+    /// across boundaries and creases to their ends.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `mesh` - The Mesh.
+    ///
+    fn build_quadrics(&mut self, mesh: &Mesh) {
+        let min_cos = f32::cos(Simplifier::CREASE_ANGLE.to_radians());
+        for f in 0..self.faces.len() {
+            let face = self.faces[f];
+            let normal = self.face_normal(&face.verticies);
+            if normal.length() == 0.0 {
+                continue;
+            }
+            let plane = Quadric::plane(normal, self.positions[face.verticies[0]], 1.0);
+            for v in face.verticies.iter() {
+                self.quadrics[*v] = self.quadrics[*v].add(&plane);
+                self.distances[*v] = self.distances[*v].add(&plane);
+            }
+
+            for i in 0..3 {
+                let (a, b) = (face.verticies[i], face.verticies[(i + 1) % 3]);
+                let others: Vec<usize> = self
+                    .edge_faces(a, b)
+                    .into_iter()
+                    .filter(|other| *other != f)
+                    .collect();
+                let keep = match others.len() {
+                    0 => true,
+                    1 => {
+                        let other = &self.faces[others[0]];
+                        let other_normal = self.face_normal(&other.verticies);
+                        normal.dot(other_normal) < min_cos
+                            || mesh.tris[face.source].material != mesh.tris[other.source].material
+                            || !Simplifier::same_corners(&face, other, a)
+                            || !Simplifier::same_corners(&face, other, b)
+                    }
+                    _ => true,
+                };
+                if keep {
+                    // a plane along the edge at a right angle to the face
+                    let edge = self.positions[b] - self.positions[a];
+                    let mut across = edge.cross(normal);
+                    if across.length() == 0.0 {
+                        continue;
+                    }
+                    let across = across.normalize();
+                    let weight = Simplifier::EDGE_WEIGHT * edge.dot(edge) as f64;
+                    let plane = Quadric::plane(across, self.positions[a], weight);
+                    self.quadrics[a] = self.quadrics[a].add(&plane);
+                    self.quadrics[b] = self.quadrics[b].add(&plane);
+                    let plane = Quadric::plane(across, self.positions[a], 1.0);
+                    self.distances[a] = self.distances[a].add(&plane);
+                    self.distances[b] = self.distances[b].add(&plane);
+                }
+            }
+        }
+    }
+
+    /// Finds where the ends of an edge should meet and what it costs.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `a` - One end.
+    /// * `b` - The other end.
+    ///
+    /// # Return
+    /// The Collapse
+    ///
+    fn collapse(&self, a: usize, b: usize) -> Collapse {
+        let quadric = self.quadrics[a].add(&self.quadrics[b]);
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let mut candidates = vec![pa, pb, (pa + pb) * 0.5];
+        if let Some(best) = quadric.minimum() {
+            // a point far from the edge comes from nearly parallel planes
+            let reach = (pb - pa).length() * 2.0;
+            if (best - (pa + pb) * 0.5).length() <= reach {
+                candidates.insert(0, best);
+            }
+        }
+        let mut position = candidates[0];
+        let mut cost = f64::INFINITY;
+        for candidate in candidates {
+            let error = quadric.error(candidate).max(0.0);
+            if error < cost {
+                cost = error;
+                position = candidate;
+            }
+        }
+        let distance = self.distances[a].add(&self.distances[b]).error(position);
+        Collapse {
+            cost: cost,
+            distance: distance.max(0.0),
+            ends: (a, b),
+            stamps: (self.stamps[a], self.stamps[b]),
+            position: position,
+        }
+    }
+
+    /// Checks that collapsing an edge keeps the surface in one piece and
+    /// does not fold any face over.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `a` - The end that goes away.
+    /// * `b` - The end that is kept.
+    /// * `position` - Where the ends meet.
+    ///
+    /// # Return
+    /// If the edge can be collapsed
+    ///
+    fn can_collapse(&self, a: usize, b: usize, position: Vec3d) -> bool {
+        // the only verticies next to both ends may be the tips of the faces on the edge
+        let shared_faces = self.edge_faces(a, b);
+        let around_a = self.neighbours(a);
+        let mut shared = 0;
+        let mut seen: Vec<usize> = Vec::new();
+        for n in self.neighbours(b) {
+            if n != a && around_a.contains(&n) && !seen.contains(&n) {
+                seen.push(n);
+                shared += 1;
+            }
+        }
+        if shared != shared_faces.len() {
+            return false;
+        }
+        // joining two boundaries across the surface would pinch it
+        if shared_faces.len() > 1 && self.on_boundary(a) && self.on_boundary(b) {
+            return false;
+        }
+
+        for v in [a, b].iter() {
+            for f in self.vertex_faces[*v].iter() {
+                let face = &self.faces[*f];
+                if !face.alive || shared_faces.contains(f) {
+                    continue;
+                }
+                let before = self.face_normal(&face.verticies);
+                let moved: Vec<Vec3d> = face
+                    .verticies
+                    .iter()
+                    .map(|u| {
+                        if *u == a || *u == b {
+                            position
+                        } else {
+                            self.positions[*u]
+                        }
+                    })
+                    .collect();
+                let mut after = (moved[1] - moved[0]).cross(moved[2] - moved[0]);
+                if after.length() == 0.0
+                    || before.dot(after.normalize()) < Simplifier::MIN_NORMAL_DOT
+                {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Collapses an edge. The faces on the edge go away and the others
+    /// around the end that goes away move to the kept end. Their corners
+    /// get the attributes along the edge where the ends meet.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `a` - The end that goes away.
+    /// * `b` - The end that is kept.
+    /// * `position` - Where the ends meet.
+    ///
+    fn apply(&mut self, a: usize, b: usize, position: Vec3d) {
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let edge = pb - pa;
+        let t = if edge.dot(edge) > 0.0 {
+            ((position - pa).dot(edge) / edge.dot(edge)).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+
+        // the attributes of both ends on a face of the edge
+        let shared_faces = self.edge_faces(a, b);
+        let attributes = shared_faces.first().map(|f| {
+            let face = &self.faces[*f];
+            let ca = face.verticies.iter().position(|v| *v == a).unwrap();
+            let cb = face.verticies.iter().position(|v| *v == b).unwrap();
+            (
+                (face.normals[ca], face.colors[ca], face.uvs[ca]),
+                (face.normals[cb], face.colors[cb], face.uvs[cb]),
+            )
+        });
+        for f in shared_faces.iter() {
+            self.faces[*f].alive = false;
+            self.alive_faces -= 1;
+        }
+
+        let faces: Vec<usize> = self.vertex_faces[a]
+            .iter()
+            .chain(self.vertex_faces[b].iter())
+            .copied()
+            .filter(|f| self.faces[*f].alive)
+            .collect();
+        for f in faces.iter() {
+            let face = &mut self.faces[*f];
+            for corner in 0..3 {
+                if face.verticies[corner] == a {
+                    face.verticies[corner] = b;
+                    if let Some((_, at_b)) = attributes {
+                        Simplifier::blend(face, corner, at_b, t);
+                    }
+                } else if face.verticies[corner] == b {
+                    if let Some((at_a, _)) = attributes {
+                        Simplifier::blend(face, corner, at_a, 1.0 - t);
+                    }
+                }
+            }
+        }
+
+        let mut kept = faces;
+        kept.sort();
+        kept.dedup();
+        self.vertex_faces[b] = kept;
+        self.vertex_faces[a].clear();
+        self.positions[b] = position;
+        self.quadrics[b] = self.quadrics[a].add(&self.quadrics[b]);
+        self.distances[b] = self.distances[a].add(&self.distances[b]);
+        self.stamps[a] += 1;
+        self.stamps[b] += 1;
+    }
+
+    /// Mixes attributes into a corner of a face.
+    ///
+    /// # Arguments
+    /// * `face` - The face.
+    /// * `corner` - The corner.
+    /// * `other` - The normal, color and uv mixed in.
+    /// * `t` - How much of the other attributes are mixed in.
+    ///
+    fn blend(face: &mut Face, corner: usize, other: (Vec3d, (f32, f32, f32), (f32, f32)), t: f32) {
+        let (normal, color, uv) = other;
+        let mut mixed = face.normals[corner] * (1.0 - t) + normal * t;
+        if mixed.length() > 0.0 {
+            face.normals[corner] = mixed.normalize();
+        }
+        let c = face.colors[corner];
+        face.colors[corner] = (
+            c.0 + (color.0 - c.0) * t,
+            c.1 + (color.1 - c.1) * t,
+            c.2 + (color.2 - c.2) * t,
+        );
+        let u = face.uvs[corner];
+        face.uvs[corner] = (u.0 + (uv.0 - u.0) * t, u.1 + (uv.1 - u.1) * t);
+    }
+
+    /// Checks if two faces have the same attributes where they share a vertex.
+    ///
+    /// # Arguments
+    /// * `face` - One face.
+    /// * `other` - The other face.
+    /// * `v` - The shared vertex.
+    ///
+    /// # Return
+    /// If the normals, colors and uvs match
+    ///
+    fn same_corners(face: &Face, other: &Face, v: usize) -> bool {
+        let i = face.verticies.iter().position(|u| *u == v).unwrap();
+        let j = other.verticies.iter().position(|u| *u == v).unwrap();
+        let close = |a: f32, b: f32| (a - b).abs() < 0.001;
+        let (c, d) = (face.colors[i], other.colors[j]);
+        let (u, w) = (face.uvs[i], other.uvs[j]);
+        (face.flat || other.flat || (face.normals[i] - other.normals[j]).length() < 0.001)
+            && close(c.0, d.0)
+            && close(c.1, d.1)
+            && close(c.2, d.2)
+            && close(u.0, w.0)
+            && close(u.1, w.1)
+    }
+
+    /// The faces that still have an edge.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `a` - One end.
+    /// * `b` - The other end.
+    ///
+    /// # Return
+    /// The indices of the faces
+    ///
+    fn edge_faces(&self, a: usize, b: usize) -> Vec<usize> {
+        self.vertex_faces[a]
+            .iter()
+            .copied()
+            .filter(|f| self.faces[*f].alive && self.faces[*f].verticies.contains(&b))
+            .collect()
+    }
+
+    /// Checks if a vertex is at the end of an edge with only one face.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `v` - The vertex.
+    ///
+    /// # Return
+    /// If the vertex is on a boundary
+    ///
+    fn on_boundary(&self, v: usize) -> bool {
+        self.neighbours(v)
+            .into_iter()
+            .any(|u| self.edge_faces(v, u).len() == 1)
+    }
+
+    /// The verticies sharing a face with a vertex. Some may be listed twice.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `v` - The vertex.
+    ///
+    /// # Return
+    /// The indices of the verticies
+    ///
+    fn neighbours(&self, v: usize) -> Vec<usize> {
+        let mut around = Vec::new();
+        for f in self.vertex_faces[v].iter() {
+            if self.faces[*f].alive {
+                for u in self.faces[*f].verticies.iter() {
+                    if *u != v {
+                        around.push(*u);
+                    }
+                }
+            }
+        }
+        around
+    }
+
+    /// The unit normal of a face.
+    ///
+    /// # Arguments
+    /// * `self` - The Simplifier the function was called for.
+    /// * `verticies` - The verticies of the face.
+    ///
+    /// # Return
+    /// The normal or zero if the face has no area
+    ///
+    fn face_normal(&self, verticies: &[usize; 3]) -> Vec3d {
+        let (a, b, c) = (
+            self.positions[verticies[0]],
+            self.positions[verticies[1]],
+            self.positions[verticies[2]],
+        );
+        let mut normal = (b - a).cross(c - a);
+        if normal.length() == 0.0 {
+            normal
+        } else {
+            normal.normalize()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mesh::Mesh, vec3d::Vec3d};
+
+    /// One color for the whole Mesh so the colors of the faces make no creases.
+    fn plain(mut mesh: Mesh) -> Mesh {
+        for tri in mesh.tris.iter_mut() {
+            tri.set_color((0.5, 0.5, 0.5));
+        }
+        mesh
+    }
+
+    fn sphere(subdivisions: usize) -> Mesh {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_icosphere(1.0, subdivisions, false);
+        plain(mesh)
+    }
+
+    #[test]
+    fn reaches_the_target() {
+        let mesh = sphere(3);
+        let simplified = mesh.simplify(320, f32::INFINITY);
+        assert!(simplified.tris.len() <= 320);
+        assert!(simplified.tris.len() >= 300);
+        let report = simplified.validate();
+        assert!(report.is_watertight());
+        assert!(report.volume > 0.0);
+    }
+
+    #[test]
+    fn flat_grid_keeps_its_outline() {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_grid(1.0, 1.0, 8, 8, false);
+        let mesh = plain(mesh);
+        let simplified = mesh.simplify(0, 1e-4);
+        assert!(simplified.tris.len() < mesh.tris.len() / 4);
+        let report = simplified.validate();
+        assert!((report.area - 1.0).abs() < 1e-4);
+        assert!((report.aabb.min - mesh.aabb.min).length() < 1e-4);
+        assert!((report.aabb.max - mesh.aabb.max).length() < 1e-4);
+    }
+
+    #[test]
+    fn tolerance_keeps_a_curved_surface() {
+        // every collapse on the sphere moves the surface by more than this
+        let mesh = sphere(2);
+        assert_eq!(mesh.simplify(0, 1e-4).tris.len(), mesh.tris.len());
+        assert!(mesh.simplify(0, 0.1).tris.len() < mesh.tris.len());
+    }
+}
@@ -0,0 +1,458 @@
+use std::{
+    fs::{self, File},
+    io::{Error, ErrorKind},
+};
+
+/// What happens to texture coordinates outside of 0 to 1.
+///  - Repeat   the texture is tiled
+///  - Clamp    the edge texels are stretched
+#[derive(Copy, Clone, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+}
+
+/// One level of the mipmap chain of a Texture.
+#[derive(Clone)]
+pub struct TextureLevel {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<(f32, f32, f32)>,
+}
+
+/// A Texture is an image that is wrapped around a Mesh using the
+/// texture coordinates of its Triangles. (0, 0) is the bottom left
+/// corner of the image and (1, 1) is the top right corner.
+///
+/// Every level of the mipmap chain is half the size of the one before
+/// it so far away surfaces can be sampled without flickering. Each level
+/// is sampled bilinearly from the 4 closest texels.
+#[derive(Clone)]
+pub struct Texture {
+    pub levels: Vec<TextureLevel>,
+    pub wrap: WrapMode,
+    pub mipmaps: bool,
+}
+
+impl Texture {
+    /// Creates a new Texture from rgb texels and builds its mipmaps.
+    ///
+    /// # Arguments
+    /// * `width` - The width of the image.
+    /// * `height` - The height of the image.
+    /// * `texels` - The colors of the image row by row from the top left.
+    ///
+    /// # Return
+    /// A new Texture
+    ///
+    pub fn new(width: usize, height: usize, texels: Vec<(f32, f32, f32)>) -> Texture {
+        let mut levels = vec![TextureLevel {
+            width: width,
+            height: height,
+            texels: texels,
+        }];
+
+        // box filter every level down to 1 x 1
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.width <= 1 && last.height <= 1 {
+                break;
+            }
+            let width = usize::max(1, last.width / 2);
+            let height = usize::max(1, last.height / 2);
+            let mut texels: Vec<(f32, f32, f32)> = Vec::with_capacity(width * height);
+            for y in 0..height {
+                for x in 0..width {
+                    let x0 = usize::min(x * 2, last.width - 1);
+                    let x1 = usize::min(x * 2 + 1, last.width - 1);
+                    let y0 = usize::min(y * 2, last.height - 1);
+                    let y1 = usize::min(y * 2 + 1, last.height - 1);
+                    let a = last.texels[y0 * last.width + x0];
+                    let b = last.texels[y0 * last.width + x1];
+                    let c = last.texels[y1 * last.width + x0];
+                    let d = last.texels[y1 * last.width + x1];
+                    texels.push((
+                        (a.0 + b.0 + c.0 + d.0) * 0.25,
+                        (a.1 + b.1 + c.1 + d.1) * 0.25,
+                        (a.2 + b.2 + c.2 + d.2) * 0.25,
+                    ));
+                }
+            }
+            levels.push(TextureLevel {
+                width: width,
+                height: height,
+                texels: texels,
+            });
+        }
+
+        Texture {
+            levels: levels,
+            wrap: WrapMode::Repeat,
+            mipmaps: true,
+        }
+    }
+
+    /// Creates a Texture from 8 bit pixels.
+    ///
+    /// # Arguments
+    /// * `width` - The width of the image.
+    /// * `height` - The height of the image.
+    /// * `channels` - The number of bytes per pixel (1 grey, 2 grey alpha, 3 rgb, 4 rgba).
+    /// * `pixels` - The pixels row by row from the top left.
+    ///
+    /// # Return
+    /// A new Texture
+    ///
+    pub fn from_bytes(width: usize, height: usize, channels: usize, pixels: &[u8]) -> Texture {
+        let mut texels: Vec<(f32, f32, f32)> = Vec::with_capacity(width * height);
+        for i in 0..(width * height) {
+            let p = &pixels[i * channels..(i + 1) * channels];
+            if channels < 3 {
+                let grey = p[0] as f32 / 255.0;
+                texels.push((grey, grey, grey));
+            } else {
+                texels.push((
+                    p[0] as f32 / 255.0,
+                    p[1] as f32 / 255.0,
+                    p[2] as f32 / 255.0,
+                ));
+            }
+        }
+        Texture::new(width, height, texels)
+    }
+
+    /// Load a Texture from an image file.
+    ///
+    /// # Arguments
+    /// * `filename` - The filename of the png or ppm file.
+    ///
+    /// # Return
+    /// The Texture
+    ///
+    pub fn from_file(filename: &str) -> Result<Texture, Error> {
+        let file_type = filename.split('.').next_back().unwrap().to_lowercase();
+        if file_type == "png" {
+            Texture::from_png(filename)
+        } else if file_type == "ppm" {
+            Texture::from_ppm(filename)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported image type {}", file_type),
+            ))
+        }
+    }
+
+    /// Load a Texture from a png file.
+    ///
+    /// # Arguments
+    /// * `filename` - The filename of the png file.
+    ///
+    /// # Return
+    /// The Texture
+    ///
+    pub fn from_png(filename: &str) -> Result<Texture, Error> {
+        let decoder = png::Decoder::new(File::open(filename)?);
+        let (info, mut reader) = decoder
+            .read_info()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        reader
+            .next_frame(&mut pixels)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+        // palettes and 16 bit images are expanded to 8 bits per sample
+        let channels = match reader.output_color_type().0 {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            png::ColorType::RGB => 3,
+            png::ColorType::RGBA => 4,
+            png::ColorType::Indexed => {
+                return Err(Error::new(ErrorKind::InvalidData, "unexpanded palette"))
+            }
+        };
+        Ok(Texture::from_bytes(
+            info.width as usize,
+            info.height as usize,
+            channels,
+            &pixels,
+        ))
+    }
+
+    /// Load a Texture from a ppm file (P3 ascii or P6 binary).
+    ///
+    /// # Arguments
+    /// * `filename` - The filename of the ppm file.
+    ///
+    /// # Return
+    /// The Texture
+    ///
+    pub fn from_ppm(filename: &str) -> Result<Texture, Error> {
+        let data = fs::read(filename)?;
+        let invalid = || Error::new(ErrorKind::InvalidData, "invalid ppm file");
+
+        // the header is 4 tokens separated by whitespace and comments
+        let mut tokens: Vec<String> = Vec::new();
+        let mut i = 0;
+        while tokens.len() < 4 && i < data.len() {
+            if data[i] == b'#' {
+                while i < data.len() && data[i] != b'\n' {
+                    i += 1;
+                }
+            } else if data[i].is_ascii_whitespace() {
+                i += 1;
+            } else {
+                let start = i;
+                while i < data.len() && !data[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                tokens.push(String::from_utf8_lossy(&data[start..i]).to_string());
+            }
+        }
+        if tokens.len() < 4 {
+            return Err(invalid());
+        }
+        let width = tokens[1].parse::<usize>().map_err(|_| invalid())?;
+        let height = tokens[2].parse::<usize>().map_err(|_| invalid())?;
+        let max = tokens[3].parse::<f32>().map_err(|_| invalid())?;
+        if width == 0 || height == 0 || !(max > 0.0) {
+            return Err(invalid());
+        }
+
+        let mut texels: Vec<(f32, f32, f32)> = Vec::with_capacity(width * height);
+        if tokens[0] == "P6" {
+            // a single whitespace separates the header from the pixels
+            let start = i + 1;
+            let bytes = if max > 255.0 { 2 } else { 1 };
+            if data.len() < start + width * height * 3 * bytes {
+                return Err(invalid());
+            }
+            let sample = |n: usize| -> f32 {
+                let at = start + n * bytes;
+                if bytes == 2 {
+                    ((data[at] as u32) << 8 | data[at + 1] as u32) as f32 / max
+                } else {
+                    data[at] as f32 / max
+                }
+            };
+            for p in 0..(width * height) {
+                texels.push((sample(p * 3), sample(p * 3 + 1), sample(p * 3 + 2)));
+            }
+        } else if tokens[0] == "P3" {
+            let values: Vec<f32> = String::from_utf8_lossy(&data[i..])
+                .split_whitespace()
+                .map(|v| v.parse::<f32>().unwrap_or(0.0) / max)
+                .collect();
+            if values.len() < width * height * 3 {
+                return Err(invalid());
+            }
+            for p in 0..(width * height) {
+                texels.push((values[p * 3], values[p * 3 + 1], values[p * 3 + 2]));
+            }
+        } else {
+            return Err(invalid());
+        }
+        Ok(Texture::new(width, height, texels))
+    }
+
+    /// Samples the Texture at a texture coordinate. The footprint is how much
+    /// the texture coordinate changes from one pixel to the next and picks
+    /// the mipmap levels to blend between.
+    ///
+    /// # Arguments
+    /// * `self` - The Texture the function was called for.
+    /// * `uv` - The texture coordinate.
+    /// * `footprint` - The size of a pixel in texture coordinates.
+    ///
+    /// # Return
+    /// The color
+    ///
+    pub fn sample(&self, uv: (f32, f32), footprint: f32) -> (f32, f32, f32) {
+        if !self.mipmaps || !(footprint > 0.0) {
+            return self.sample_level(0, uv);
+        }
+
+        let size = usize::max(self.levels[0].width, self.levels[0].height) as f32;
+        let lod = f32::log2(footprint * size);
+        if lod <= 0.0 {
+            return self.sample_level(0, uv);
+        }
+        let last = self.levels.len() - 1;
+        if lod >= last as f32 {
+            return self.sample_level(last, uv);
+        }
+
+        // blend between the two closest levels
+        let lower = lod.floor() as usize;
+        let t = lod - lower as f32;
+        let a = self.sample_level(lower, uv);
+        let b = self.sample_level(lower + 1, uv);
+        (
+            a.0 + (b.0 - a.0) * t,
+            a.1 + (b.1 - a.1) * t,
+            a.2 + (b.2 - a.2) * t,
+        )
+    }
+
+    /// Samples one mipmap level of the Texture bilinearly.
+    ///
+    /// # Arguments
+    /// * `self` - The Texture the function was called for.
+    /// * `level` - The mipmap level.
+    /// * `uv` - The texture coordinate.
+    ///
+    /// # Return
+    /// The color
+    ///
+    pub fn sample_level(&self, level: usize, uv: (f32, f32)) -> (f32, f32, f32) {
+        let texture = &self.levels[level];
+        let x = uv.0 * texture.width as f32;
+        let y = (1.0 - uv.1) * texture.height as f32;
+
+        // texel centers are at .5
+        let x = x - 0.5;
+        let y = y - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let a = self.texel(texture, x0 as i64, y0 as i64);
+        let b = self.texel(texture, x0 as i64 + 1, y0 as i64);
+        let c = self.texel(texture, x0 as i64, y0 as i64 + 1);
+        let d = self.texel(texture, x0 as i64 + 1, y0 as i64 + 1);
+        (
+            (a.0 * (1.0 - fx) + b.0 * fx) * (1.0 - fy) + (c.0 * (1.0 - fx) + d.0 * fx) * fy,
+            (a.1 * (1.0 - fx) + b.1 * fx) * (1.0 - fy) + (c.1 * (1.0 - fx) + d.1 * fx) * fy,
+            (a.2 * (1.0 - fx) + b.2 * fx) * (1.0 - fy) + (c.2 * (1.0 - fx) + d.2 * fx) * fy,
+        )
+    }
+
+    /// Gets a texel, wrapping or clamping coordinates outside of the image.
+    ///
+    /// # Arguments
+    /// * `self` - The Texture the function was called for.
+    /// * `texture` - The mipmap level.
+    /// * `x` - The column of the texel.
+    /// * `y` - The row of the texel.
+    ///
+    /// # Return
+    /// The color of the texel
+    ///
+    fn texel(&self, texture: &TextureLevel, x: i64, y: i64) -> (f32, f32, f32) {
+        let width = texture.width as i64;
+        let height = texture.height as i64;
+        let (x, y) = match self.wrap {
+            WrapMode::Repeat => (x.rem_euclid(width), y.rem_euclid(height)),
+            WrapMode::Clamp => (
+                i64::max(0, i64::min(width - 1, x)),
+                i64::max(0, i64::min(height - 1, y)),
+            ),
+        };
+        texture.texels[(y * width + x) as usize]
+    }
+
+    /// Converts the full size image to rgba bytes.
+    ///
+    /// # Arguments
+    /// * `self` - The Texture the function was called for.
+    ///
+    /// # Return
+    /// A vec of 4 bytes per pixel
+    ///
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::with_capacity(self.levels[0].texels.len() * 4);
+        for texel in self.levels[0].texels.iter() {
+            out.push((texel.0.clamp(0.0, 1.0) * 255.0) as u8);
+            out.push((texel.1.clamp(0.0, 1.0) * 255.0) as u8);
+            out.push((texel.2.clamp(0.0, 1.0) * 255.0) as u8);
+            out.push(255);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        material::Material,
+        texture::{Texture, WrapMode},
+    };
+    use std::{fs, io::ErrorKind};
+
+    /// A 2 x 2 Texture, black on the left and white on the right.
+    fn halves() -> Texture {
+        let black = (0.0, 0.0, 0.0);
+        let white = (1.0, 1.0, 1.0);
+        Texture::new(2, 2, vec![black, white, black, white])
+    }
+
+    fn write(name: &str, contents: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("my-engine-{}", name));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn bilinear_sampling_blends_the_texels() {
+        let texture = halves();
+        assert_eq!(texture.sample_level(0, (0.25, 0.5)).0, 0.0);
+        assert_eq!(texture.sample_level(0, (0.75, 0.5)).0, 1.0);
+        assert!((texture.sample_level(0, (0.5, 0.5)).0 - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn wrap_mode_tiles_or_clamps() {
+        let mut texture = halves();
+        // past the right edge the left edge comes back
+        assert!((texture.sample_level(0, (1.0, 0.5)).0 - 0.5).abs() < 1e-6);
+        texture.wrap = WrapMode::Clamp;
+        assert_eq!(texture.sample_level(0, (1.0, 0.5)).0, 1.0);
+    }
+
+    #[test]
+    fn mipmaps_average_down_to_one_texel() {
+        let texture = halves();
+        assert_eq!(texture.levels.len(), 2);
+        assert_eq!(texture.levels[1].texels, vec![(0.5, 0.5, 0.5)]);
+        // a footprint the size of the whole texture samples the last level
+        assert_eq!(texture.sample((0.25, 0.5), 1.0), (0.5, 0.5, 0.5));
+        assert_eq!(texture.sample((0.25, 0.5), 0.0), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn ppm_is_read_and_scaled_by_its_maximum() {
+        let file = write("scaled.ppm", b"P3\n# a comment\n2 1\n4\n0 2 4 4 4 4\n");
+        let texture = Texture::from_file(&file).unwrap();
+        assert_eq!(
+            texture.levels[0].texels,
+            vec![(0.0, 0.5, 1.0), (1.0, 1.0, 1.0)]
+        );
+
+        let file = write("binary.ppm", b"P6 1 1 255\n\xff\x00\x80");
+        let texture = Texture::from_file(&file).unwrap();
+        assert_eq!(texture.levels[0].texels[0].0, 1.0);
+        assert_eq!(texture.levels[0].texels[0].1, 0.0);
+    }
+
+    #[test]
+    fn ppm_with_no_maximum_is_rejected() {
+        let file = write("zero.ppm", b"P3 1 1 0\n0 0 0\n");
+        let error = Texture::from_file(&file).err().unwrap();
+        assert!(error.kind() == ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn mtl_clamp_option_sets_the_wrap_mode() {
+        let file = write("clamped.ppm", b"P3 1 1 1\n1 1 1\n");
+        let dir = std::env::temp_dir();
+        let mut material = Material::new((0.5, 0.5, 0.5), 32.0);
+        material
+            .apply_mtl("map_Kd", &["-clamp", "on", &file], &dir)
+            .unwrap();
+        assert!(material.base_color_texture.as_ref().unwrap().wrap == WrapMode::Clamp);
+        material
+            .apply_mtl("map_Ke", &["-clamp", "off", &file], &dir)
+            .unwrap();
+        assert!(material.emissive_texture.as_ref().unwrap().wrap == WrapMode::Repeat);
+    }
+}
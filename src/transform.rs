@@ -0,0 +1,277 @@
+use crate::{bounds::Aabb, matrix3x3::Matrix3x3, triangle::Triangle, vec3d::Vec3d};
+
+/// A Transform places the verticies of a Mesh in the world. A vertex is
+/// scaled along the axes of the Mesh, turned by the rotation and then
+/// moved by the position. The Gizmo changes the Transform instead of
+/// the verticies so dragging it does not wear down the Mesh, and the
+/// verticies only change when the Transform is applied.
+#[derive(Clone)]
+pub struct Transform {
+    pub position: Vec3d,
+    pub rotation: Matrix3x3,
+    pub scale: Vec3d,
+}
+
+impl Transform {
+    /// Creates a Transform that leaves the verticies where they are.
+    ///
+    /// # Return
+    /// A new Transform
+    ///
+    pub fn identity() -> Transform {
+        Transform {
+            position: Vec3d::new(0.0, 0.0, 0.0),
+            rotation: Transform::axis_rotation(0, 0.0),
+            scale: Vec3d::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Creates the rotation matrix around an axis. It turns the same way
+    /// as the rotations of a Vec3d.
+    ///
+    /// # Arguments
+    /// * `axis` - 0 for x, 1 for y and 2 for z.
+    /// * `r` - The amount rotated by in degrees.
+    ///
+    /// # Return
+    /// The rotation matrix
+    ///
+    pub fn axis_rotation(axis: usize, r: f32) -> Matrix3x3 {
+        let turn = |mut v: Vec3d| {
+            match axis {
+                0 => v.x_axis_rotation(r, 0.0, 0.0),
+                1 => v.y_axis_rotation(r, 0.0, 0.0),
+                _ => v.z_axis_rotation(r, 0.0, 0.0),
+            }
+            v
+        };
+        // the turned axes are the columns of the matrix
+        Matrix3x3::from_vec3ds(
+            turn(Vec3d::new(1.0, 0.0, 0.0)),
+            turn(Vec3d::new(0.0, 1.0, 0.0)),
+            turn(Vec3d::new(0.0, 0.0, 1.0)),
+        )
+        .transpose()
+    }
+
+    /// Checks if the Transform leaves the verticies where they are.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    ///
+    /// # Return
+    /// If the Transform changes nothing
+    ///
+    pub fn is_identity(&self) -> bool {
+        let identity = Transform::identity();
+        (0..3).all(|i| (0..3).all(|j| self.rotation.m[i][j] == identity.rotation.m[i][j]))
+            && self.position.x == 0.0
+            && self.position.y == 0.0
+            && self.position.z == 0.0
+            && self.scale.x == 1.0
+            && self.scale.y == 1.0
+            && self.scale.z == 1.0
+    }
+
+    /// Moves a point of the Mesh into the world.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    /// * `point` - The point of the Mesh.
+    ///
+    /// # Return
+    /// The point in the world
+    ///
+    pub fn apply(&self, point: Vec3d) -> Vec3d {
+        self.position + self.rotation.clone() * (point * self.scale)
+    }
+
+    /// Turns a normal of the Mesh into the world. Scaling along an axis
+    /// tilts the normal the other way.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    /// * `normal` - The normal of the Mesh.
+    ///
+    /// # Return
+    /// The normal in the world with a length of 1
+    ///
+    pub fn apply_normal(&self, normal: Vec3d) -> Vec3d {
+        let inverse = Vec3d::new(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z);
+        (self.rotation.clone() * (normal * inverse)).normalize()
+    }
+
+    /// Moves a point in the world back onto the Mesh.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    /// * `point` - The point in the world.
+    ///
+    /// # Return
+    /// The point of the Mesh
+    ///
+    pub fn invert(&self, point: Vec3d) -> Vec3d {
+        self.invert_direction(point - self.position)
+    }
+
+    /// Turns a direction in the world back onto the Mesh. It keeps how
+    /// far along a ray a point is, so the direction is not made 1 long.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    /// * `direction` - The direction in the world.
+    ///
+    /// # Return
+    /// The direction on the Mesh
+    ///
+    pub fn invert_direction(&self, direction: Vec3d) -> Vec3d {
+        let inverse = Vec3d::new(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z);
+        (self.rotation.transpose() * direction) * inverse
+    }
+
+    /// Moves a Triangle of the Mesh into the world.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    /// * `tri` - The Triangle of the Mesh.
+    ///
+    /// # Return
+    /// The Triangle in the world
+    ///
+    pub fn apply_triangle(&self, tri: &Triangle) -> Triangle {
+        let mut moved = *tri;
+        moved.verticies = (
+            self.apply(tri.verticies.0),
+            self.apply(tri.verticies.1),
+            self.apply(tri.verticies.2),
+        );
+        moved.vertex_normals = (
+            self.apply_normal(tri.vertex_normals.0),
+            self.apply_normal(tri.vertex_normals.1),
+            self.apply_normal(tri.vertex_normals.2),
+        );
+        moved.normal = Triangle::calculate_normal(moved.verticies);
+        moved.center = Triangle::calculate_center(moved.verticies);
+        moved
+    }
+
+    /// Fits an Aabb in the world around an Aabb of the Mesh.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    /// * `aabb` - The Aabb of the Mesh.
+    ///
+    /// # Return
+    /// The Aabb in the world
+    ///
+    pub fn apply_aabb(&self, aabb: &Aabb) -> Aabb {
+        if aabb.is_empty() {
+            return *aabb;
+        }
+        let mut moved = Aabb::empty();
+        for corner in aabb.corners().iter() {
+            moved.grow(self.apply(*corner));
+        }
+        moved
+    }
+
+    /// The largest scale along any axis.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    ///
+    /// # Return
+    /// The largest scale
+    ///
+    pub fn max_scale(&self) -> f32 {
+        self.scale
+            .x
+            .abs()
+            .max(self.scale.y.abs())
+            .max(self.scale.z.abs())
+    }
+
+    /// Turns the Mesh around an axis of the world at a pivot point.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    /// * `axis` - 0 for x, 1 for y and 2 for z.
+    /// * `r` - The amount rotated by in degrees.
+    /// * `pivot` - The pivot point.
+    ///
+    pub fn rotate(&mut self, axis: usize, r: f32, pivot: Vec3d) {
+        let turn = Transform::axis_rotation(axis, r);
+        self.position = pivot + turn.clone() * (self.position - pivot);
+        self.rotation = turn * self.rotation.clone();
+    }
+
+    /// Scales the Mesh along its own axes away from a pivot point.
+    ///
+    /// # Arguments
+    /// * `self` - The Transform the function was called for.
+    /// * `factor` - The positive scale along each axis of the Mesh.
+    /// * `pivot` - The pivot point.
+    ///
+    pub fn scale_by(&mut self, factor: Vec3d, pivot: Vec3d) {
+        let local = self.rotation.transpose() * (self.position - pivot);
+        self.position = pivot + self.rotation.clone() * (local * factor);
+        self.scale = self.scale * factor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transform::Transform, triangle::Triangle, vec3d::Vec3d};
+
+    fn transform() -> Transform {
+        let mut transform = Transform::identity();
+        transform.position = Vec3d::new(1.0, -2.0, 0.5);
+        transform.rotate(0, 30.0, Vec3d::new(0.0, 1.0, 0.0));
+        transform.rotate(1, -50.0, Vec3d::new(0.0, 0.0, 0.0));
+        transform.scale_by(Vec3d::new(2.0, 0.5, 1.5), Vec3d::new(0.3, 0.0, 0.0));
+        transform
+    }
+
+    #[test]
+    fn identity_changes_nothing() {
+        let identity = Transform::identity();
+        assert!(identity.is_identity());
+        let point = Vec3d::new(0.3, -0.7, 2.0);
+        assert!((identity.apply(point) - point).length() < 1e-6);
+        assert!(!transform().is_identity());
+    }
+
+    #[test]
+    fn invert_undoes_apply() {
+        let transform = transform();
+        for i in 0..10 {
+            let i = i as f32;
+            let point = Vec3d::new(i.sin(), (i * 0.7).cos(), i * 0.3);
+            let back = transform.invert(transform.apply(point));
+            assert!((back - point).length() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn normals_stay_at_right_angles() {
+        let transform = transform();
+        let tri = Triangle::new(
+            Vec3d::new(0.0, 0.0, 0.0),
+            Vec3d::new(1.0, 0.2, 0.0),
+            Vec3d::new(0.3, 0.1, 1.0),
+        );
+        let moved = transform.apply_triangle(&tri);
+        let normal = transform.apply_normal(tri.normal);
+        assert!((normal - moved.normal).length() < 1e-4);
+    }
+
+    #[test]
+    fn rotation_turns_the_position_around_the_pivot() {
+        let mut transform = Transform::identity();
+        transform.rotate(2, 90.0, Vec3d::new(1.0, 0.0, 0.0));
+        let pivot_distance = (transform.position - Vec3d::new(1.0, 0.0, 0.0)).length();
+        assert!((pivot_distance - 1.0).abs() < 1e-5);
+        let turned = transform.apply(Vec3d::new(1.0, 0.0, 0.0));
+        assert!((turned - Vec3d::new(1.0, 0.0, 0.0)).length() < 1e-5);
+    }
+}
@@ -1,21 +1,35 @@
 use crate::vec3d::Vec3d;
 use ggez::graphics::Vertex;
 use ggez::{self, nalgebra::geometry::Point2};
-use rand::prelude::*;
-use std::ops::Add;
-
-/// A Triangle is a triangle with a normal, 3 vertecies, a
-/// color, a center point and its distance to the camera.
+use std::{cmp::Ordering, ops::Add};
+
+/// A Triangle is a triangle with a normal, 3 vertecies, the
+/// index of its Material in the Mesh, the index of the face it
+/// was projected from, how opaque it is, a center point and its
+/// distance to the camera. Each vertex also has its own normal
+/// and color used for smooth shading and a texture coordinate.
+/// Projected Triangles remember where their verticies are in the
+/// world for per pixel lighting.
 #[derive(Copy, Clone)]
 pub struct Triangle {
     pub normal: Vec3d,
     pub verticies: (Vec3d, Vec3d, Vec3d),
-    pub color: (f32, f32, f32),
+    pub material: usize,
+    pub face: usize,
+    pub alpha: f32,
     pub center: Vec3d,
     pub dist: f32,
+    pub vertex_normals: (Vec3d, Vec3d, Vec3d),
+    pub vertex_colors: ((f32, f32, f32), (f32, f32, f32), (f32, f32, f32)),
+    pub world_verticies: (Vec3d, Vec3d, Vec3d),
+    pub uvs: ((f32, f32), (f32, f32), (f32, f32)),
 }
 
 impl Triangle {
+    /// The vertex color of a Triangle that has not been given one.
+    /// It leaves the base color of the Material as it is.
+    pub const DEFAULT_COLOR: (f32, f32, f32) = (1.0, 1.0, 1.0);
+
     /// Create a new Triangle from 3 vertecies.
     ///
     /// # Arguments
@@ -30,16 +44,19 @@ impl Triangle {
         let normal = Triangle::calculate_normal((vertex1, vertex2, vertex3));
         let center = Triangle::calculate_center((vertex1, vertex2, vertex3));
 
-        let mut rng = rand::thread_rng();
-        let r = rng.gen::<f32>();
-        let g = rng.gen::<f32>();
-        let b = rng.gen::<f32>();
+        let (r, g, b) = Triangle::DEFAULT_COLOR;
         Triangle {
             normal: normal,
             verticies: (vertex1, vertex2, vertex3),
-            color: (r, g, b),
+            material: 0,
+            face: 0,
+            alpha: 1.0,
             center: center,
             dist: 0.0,
+            vertex_normals: (normal, normal, normal),
+            vertex_colors: ((r, g, b), (r, g, b), (r, g, b)),
+            world_verticies: (vertex1, vertex2, vertex3),
+            uvs: ((0.0, 0.0), (0.0, 0.0), (0.0, 0.0)),
         }
     }
 
@@ -62,19 +79,32 @@ impl Triangle {
     ) -> Triangle {
         let center = Triangle::calculate_center((vertex1, vertex2, vertex3));
 
-        let mut rng = rand::thread_rng();
-        let r = rng.gen::<f32>();
-        let g = rng.gen::<f32>();
-        let b = rng.gen::<f32>();
+        let (r, g, b) = Triangle::DEFAULT_COLOR;
         Triangle {
             normal: normal,
             verticies: (vertex1, vertex2, vertex3),
-            color: (r, g, b),
+            material: 0,
+            face: 0,
+            alpha: 1.0,
             center: center,
             dist: 0.0,
+            vertex_normals: (normal, normal, normal),
+            vertex_colors: ((r, g, b), (r, g, b), (r, g, b)),
+            world_verticies: (vertex1, vertex2, vertex3),
+            uvs: ((0.0, 0.0), (0.0, 0.0), (0.0, 0.0)),
         }
     }
 
+    /// Give all the verticies of the Triangle one color.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `color` - The new color.
+    ///
+    pub fn set_color(&mut self, color: (f32, f32, f32)) {
+        self.vertex_colors = (color, color, color);
+    }
+
     /// Calculates the surface normal.
     ///
     /// # Arguments
@@ -121,10 +151,14 @@ impl Triangle {
     /// * `origin_y` - The y position of the origin point.
     /// * `origin_z` - The z position of the origin point.
     ///
+    #[allow(dead_code)]
     pub fn x_axis_rotation(&mut self, r: f32, origin_y: f32, origin_z: f32) {
         self.verticies.0.x_axis_rotation(r, origin_y, origin_z);
         self.verticies.1.x_axis_rotation(r, origin_y, origin_z);
         self.verticies.2.x_axis_rotation(r, origin_y, origin_z);
+        self.vertex_normals.0.x_axis_rotation(r, 0.0, 0.0);
+        self.vertex_normals.1.x_axis_rotation(r, 0.0, 0.0);
+        self.vertex_normals.2.x_axis_rotation(r, 0.0, 0.0);
         self.normal = Triangle::calculate_normal(self.verticies);
         self.center = Triangle::calculate_center(self.verticies);
     }
@@ -137,10 +171,14 @@ impl Triangle {
     /// * `origin_x` - The x position of the origin point.
     /// * `origin_z` - The z position of the origin point.
     ///
+    #[allow(dead_code)]
     pub fn y_axis_rotation(&mut self, r: f32, origin_x: f32, origin_z: f32) {
         self.verticies.0.y_axis_rotation(r, origin_x, origin_z);
         self.verticies.1.y_axis_rotation(r, origin_x, origin_z);
         self.verticies.2.y_axis_rotation(r, origin_x, origin_z);
+        self.vertex_normals.0.y_axis_rotation(r, 0.0, 0.0);
+        self.vertex_normals.1.y_axis_rotation(r, 0.0, 0.0);
+        self.vertex_normals.2.y_axis_rotation(r, 0.0, 0.0);
         self.normal = Triangle::calculate_normal(self.verticies);
         self.center = Triangle::calculate_center(self.verticies);
     }
@@ -153,10 +191,85 @@ impl Triangle {
     /// * `origin_x` - The x position of the origin point.
     /// * `origin_y` - The y position of the origin point.
     ///
+    #[allow(dead_code)]
     pub fn z_axis_rotation(&mut self, r: f32, origin_x: f32, origin_y: f32) {
         self.verticies.0.z_axis_rotation(r, origin_x, origin_y);
         self.verticies.1.z_axis_rotation(r, origin_x, origin_y);
         self.verticies.2.z_axis_rotation(r, origin_x, origin_y);
+        self.vertex_normals.0.z_axis_rotation(r, 0.0, 0.0);
+        self.vertex_normals.1.z_axis_rotation(r, 0.0, 0.0);
+        self.vertex_normals.2.z_axis_rotation(r, 0.0, 0.0);
+        self.normal = Triangle::calculate_normal(self.verticies);
+        self.center = Triangle::calculate_center(self.verticies);
+    }
+
+    /// Moves one vertex of the Triangle. Vertex normals that were the
+    /// normal of the face become the new normal of the face.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `corner` - Which vertex, 0, 1 or 2.
+    /// * `position` - The new position of the vertex.
+    ///
+    pub fn set_vertex(&mut self, corner: usize, position: Vec3d) {
+        match corner {
+            0 => self.verticies.0 = position,
+            1 => self.verticies.1 = position,
+            _ => self.verticies.2 = position,
+        }
+        let old = self.normal;
+        self.normal = Triangle::calculate_normal(self.verticies);
+        self.center = Triangle::calculate_center(self.verticies);
+        let flat = |n: Vec3d| (n - old).length() < 0.0001;
+        if flat(self.vertex_normals.0) && flat(self.vertex_normals.1) && flat(self.vertex_normals.2)
+        {
+            self.vertex_normals = (self.normal, self.normal, self.normal);
+        }
+    }
+
+    /// Turns the Triangle around by swapping its second and third vertex
+    /// with their attributes. The normals point the other way.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    ///
+    pub fn flip(&mut self) {
+        let (v0, v1, v2) = self.verticies;
+        let (n0, n1, n2) = self.vertex_normals;
+        let (c0, c1, c2) = self.vertex_colors;
+        let (w0, w1, w2) = self.world_verticies;
+        let (t0, t1, t2) = self.uvs;
+        self.verticies = (v0, v2, v1);
+        self.vertex_normals = (n0 * -1.0, n2 * -1.0, n1 * -1.0);
+        self.vertex_colors = (c0, c2, c1);
+        self.world_verticies = (w0, w2, w1);
+        self.uvs = (t0, t2, t1);
+        self.normal = self.normal * -1.0;
+    }
+
+    /// Scales the Triangle along the axes away from the origin point.
+    /// The vertex normals are scaled by the inverse so they stay
+    /// perpendicular to the surface.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `factor` - The positive scale along each axis.
+    /// * `origin` - The origin point.
+    ///
+    #[allow(dead_code)]
+    pub fn scale(&mut self, factor: Vec3d, origin: Vec3d) {
+        let scale = |v: Vec3d| origin + (v - origin) * factor;
+        self.verticies = (
+            scale(self.verticies.0),
+            scale(self.verticies.1),
+            scale(self.verticies.2),
+        );
+        let inverse = Vec3d::new(1.0 / factor.x, 1.0 / factor.y, 1.0 / factor.z);
+        self.vertex_normals = (
+            (self.vertex_normals.0 * inverse).normalize(),
+            (self.vertex_normals.1 * inverse).normalize(),
+            (self.vertex_normals.2 * inverse).normalize(),
+        );
         self.normal = Triangle::calculate_normal(self.verticies);
         self.center = Triangle::calculate_center(self.verticies);
     }
@@ -200,535 +313,382 @@ impl Triangle {
         self.center = Triangle::calculate_center(self.verticies);
     }
 
-    /// Clip the Triangle's so that no part of them is being rendered off the screen.
+    /// Finds where a ray hits the Triangle (Möller-Trumbore). Both
+    /// sides of the Triangle are hit.
     ///
     /// # Arguments
-    /// * `x_bound` - The width of the screen
-    /// * `y_bound` - The height of the screen
+    /// * `self` - The Triangle the function was called for.
+    /// * `origin` - The start of the ray.
+    /// * `direction` - The direction of the ray.
     ///
     /// # Return
-    /// A vec of Triangle's that are on the screen and can replace the original
-    ///
-    pub fn clip(&mut self, x_bound: f32, y_bound: f32, mut depth: i32) -> Vec<Triangle> {
-        depth = depth + 1;
-        // if depth > 100 {
-        //     println!("{}", depth);
-        // }
-        if ((self.verticies.0.x > x_bound)
-            && (self.verticies.1.x > x_bound)
-            && (self.verticies.2.x > x_bound))
-            || ((self.verticies.0.y > y_bound)
-                && (self.verticies.1.y > y_bound)
-                && (self.verticies.2.y > y_bound))
-            || ((self.verticies.0.x < 0.0)
-                && (self.verticies.1.x < 0.0)
-                && (self.verticies.2.x < 0.0))
-            || ((self.verticies.0.y < 0.0)
-                && (self.verticies.1.y < 0.0)
-                && (self.verticies.2.y < 0.0))
-        {
-            let na: Vec<Triangle> = Vec::new();
-            return na; //return
+    /// How far along the ray in lengths of the direction it hits or None
+    ///
+    pub fn intersect_ray(&self, origin: Vec3d, direction: Vec3d) -> Option<f32> {
+        let (a, b, c) = self.verticies;
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = direction.cross(edge2);
+        let det = edge1.dot(p);
+        if det.abs() < 1e-12 {
+            return None;
         }
-
-        // X
-        if self.verticies.0.x > x_bound {
-            if self.verticies.1.x > x_bound {
-                if !(self.verticies.2.x > x_bound) {
-                    let mut b_c_pt = self.verticies.1 - self.verticies.2;
-                    let f = (x_bound - self.verticies.2.x) / b_c_pt.x;
-                    b_c_pt.x *= f;
-                    b_c_pt.y *= f;
-                    b_c_pt.z *= f;
-                    b_c_pt = b_c_pt + self.verticies.2;
-
-                    let mut c_a_pt = self.verticies.0 - self.verticies.2;
-                    let f = (x_bound - self.verticies.2.x) / c_a_pt.x;
-                    c_a_pt.x *= f;
-                    c_a_pt.y *= f;
-                    c_a_pt.z *= f;
-                    c_a_pt = c_a_pt + self.verticies.2;
-
-                    return Triangle::new(c_a_pt, b_c_pt, self.verticies.2)
-                        .clip(x_bound, y_bound, depth); // return
-                }
-            } else {
-                let mut a_b_pt = self.verticies.0 - self.verticies.1;
-                let f = (x_bound - self.verticies.1.x) / a_b_pt.x;
-                a_b_pt.x *= f;
-                a_b_pt.y *= f;
-                a_b_pt.z *= f;
-                a_b_pt = a_b_pt + self.verticies.1;
-                if self.verticies.2.x > x_bound {
-                    let mut b_c_pt = self.verticies.2 - self.verticies.1;
-                    let f = (x_bound - self.verticies.1.x) / b_c_pt.x;
-                    b_c_pt.x *= f;
-                    b_c_pt.y *= f;
-                    b_c_pt.z *= f;
-                    b_c_pt = b_c_pt + self.verticies.1;
-
-                    return Triangle::new(a_b_pt, self.verticies.1, b_c_pt)
-                        .clip(x_bound, y_bound, depth); // return
-                } else {
-                    let mut c_a_pt = self.verticies.0 - self.verticies.2;
-                    let f = (x_bound - self.verticies.2.x) / c_a_pt.x;
-                    c_a_pt.x *= f;
-                    c_a_pt.y *= f;
-                    c_a_pt.z *= f;
-                    c_a_pt = c_a_pt + self.verticies.2;
-
-                    let mut c = Triangle::new(a_b_pt, self.verticies.1, self.verticies.2)
-                        .clip(x_bound, y_bound, depth);
-                    c.append(
-                        &mut Triangle::new(a_b_pt, self.verticies.2, c_a_pt)
-                            .clip(x_bound, y_bound, depth),
-                    );
-
-                    return c;
-                }
-            }
+        let inverse = 1.0 / det;
+        let s = origin - a;
+        let u = s.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
         }
-
-        if self.verticies.1.x > x_bound {
-            let mut a_b_pt = self.verticies.1 - self.verticies.0;
-            let f = (x_bound - self.verticies.0.x) / a_b_pt.x;
-            a_b_pt.x *= f;
-            a_b_pt.y *= f;
-            a_b_pt.z *= f;
-            a_b_pt = a_b_pt + self.verticies.0;
-
-            if self.verticies.2.x > x_bound {
-                let mut c_a_pt = self.verticies.2 - self.verticies.0;
-                let f = (x_bound - self.verticies.0.x) / c_a_pt.x;
-                c_a_pt.x *= f;
-                c_a_pt.y *= f;
-                c_a_pt.z *= f;
-                c_a_pt = c_a_pt + self.verticies.0;
-
-                return Triangle::new(self.verticies.0, a_b_pt, c_a_pt)
-                    .clip(x_bound, y_bound, depth);
-            } else {
-                let mut b_c_pt = self.verticies.1 - self.verticies.2;
-                let f = (x_bound - self.verticies.2.x) / b_c_pt.x;
-                b_c_pt.x *= f;
-                b_c_pt.y *= f;
-                b_c_pt.z *= f;
-                b_c_pt = b_c_pt + self.verticies.2;
-
-                let mut c = Triangle::new(self.verticies.0, a_b_pt, self.verticies.2)
-                    .clip(x_bound, y_bound, depth);
-                c.append(
-                    &mut Triangle::new(a_b_pt, b_c_pt, self.verticies.2)
-                        .clip(x_bound, y_bound, depth),
-                );
-
-                return c;
-            }
+        let q = s.cross(edge1);
+        let v = direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
         }
-
-        //// \/\/\/
-        if depth > 100 {
-            println!("{}", depth);
+        let t = edge2.dot(q) * inverse;
+        if t > 1e-6 {
+            Some(t)
+        } else {
+            None
         }
+    }
 
-        if self.verticies.2.x > x_bound {
-            let mut c_a_pt = self.verticies.2 - self.verticies.0;
-            let f = (x_bound - self.verticies.0.x) / c_a_pt.x;
-            c_a_pt.x *= f;
-            c_a_pt.y *= f;
-            c_a_pt.z *= f;
-            c_a_pt = c_a_pt + self.verticies.0;
-
-            let mut b_c_pt = self.verticies.2 - self.verticies.1;
-            let f = (x_bound - self.verticies.1.x) / b_c_pt.x;
-            b_c_pt.x *= f;
-            b_c_pt.y *= f;
-            b_c_pt.z *= f;
-            b_c_pt = b_c_pt + self.verticies.1;
-
-            let mut c = Triangle::new(self.verticies.0, self.verticies.1, c_a_pt)
-                .clip(x_bound, y_bound, depth);
-            c.append(
-                &mut Triangle::new(c_a_pt, self.verticies.1, b_c_pt).clip(x_bound, y_bound, depth),
-            );
-
+    /// Finds the point on the Triangle closest to another point.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `point` - The other point.
+    ///
+    /// # Return
+    /// The closest point on the Triangle
+    ///
+    #[allow(dead_code)]
+    pub fn closest_point(&self, point: Vec3d) -> Vec3d {
+        let (a, b, c) = self.verticies;
+        let ab = b - a;
+        let ac = c - a;
+
+        // the corners
+        let ap = point - a;
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return a;
+        }
+        let bp = point - b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= 0.0 && d4 <= d3 {
+            return b;
+        }
+        let cp = point - c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= 0.0 && d5 <= d6 {
             return c;
         }
 
-        // /\/\/\
-        if depth > 100 {
-            println!("NA");
+        // the edges
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return a + ab * (d1 / (d1 - d3));
         }
-
-        // < 0
-        if self.verticies.0.x < 0.0 {
-            if self.verticies.1.x < 0.0 {
-                if !(self.verticies.2.x < 0.0) {
-                    let mut b_c_pt = self.verticies.1 - self.verticies.2;
-                    let f = -self.verticies.2.x / b_c_pt.x;
-                    b_c_pt.x *= f;
-                    b_c_pt.y *= f;
-                    b_c_pt.z *= f;
-                    b_c_pt = b_c_pt + self.verticies.2;
-
-                    let mut c_a_pt = self.verticies.0 - self.verticies.2;
-                    let f = -self.verticies.2.x / c_a_pt.x;
-                    c_a_pt.x *= f;
-                    c_a_pt.y *= f;
-                    c_a_pt.z *= f;
-                    c_a_pt = c_a_pt + self.verticies.2;
-
-                    return Triangle::new(c_a_pt, b_c_pt, self.verticies.2)
-                        .clip(x_bound, y_bound, depth); // return
-                }
-            } else {
-                let mut a_b_pt = self.verticies.0 - self.verticies.1;
-                let f = -self.verticies.1.x / a_b_pt.x;
-                a_b_pt.x *= f;
-                a_b_pt.y *= f;
-                a_b_pt.z *= f;
-                a_b_pt = a_b_pt + self.verticies.1;
-                if self.verticies.2.x < 0.0 {
-                    let mut b_c_pt = self.verticies.2 - self.verticies.1;
-                    let f = -self.verticies.1.x / b_c_pt.x;
-                    b_c_pt.x *= f;
-                    b_c_pt.y *= f;
-                    b_c_pt.z *= f;
-                    b_c_pt = b_c_pt + self.verticies.1;
-
-                    return Triangle::new(a_b_pt, self.verticies.1, b_c_pt)
-                        .clip(x_bound, y_bound, depth); // return
-                } else {
-                    let mut c_a_pt = self.verticies.0 - self.verticies.2;
-                    let f = -self.verticies.2.x / c_a_pt.x;
-                    c_a_pt.x *= f;
-                    c_a_pt.y *= f;
-                    c_a_pt.z *= f;
-                    c_a_pt = c_a_pt + self.verticies.2;
-
-                    let mut c = Triangle::new(a_b_pt, self.verticies.1, self.verticies.2)
-                        .clip(x_bound, y_bound, depth);
-                    c.append(
-                        &mut Triangle::new(a_b_pt, self.verticies.2, c_a_pt)
-                            .clip(x_bound, y_bound, depth),
-                    );
-
-                    return c;
-                }
-            }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return a + ac * (d2 / (d2 - d6));
         }
-
-        if self.verticies.1.x < 0.0 {
-            let mut a_b_pt = self.verticies.1 - self.verticies.0;
-            let f = -self.verticies.0.x / a_b_pt.x;
-            a_b_pt.x *= f;
-            a_b_pt.y *= f;
-            a_b_pt.z *= f;
-            a_b_pt = a_b_pt + self.verticies.0;
-
-            if self.verticies.2.x < 0.0 {
-                let mut c_a_pt = self.verticies.2 - self.verticies.0;
-                let f = -self.verticies.0.x / c_a_pt.x;
-                c_a_pt.x *= f;
-                c_a_pt.y *= f;
-                c_a_pt.z *= f;
-                c_a_pt = c_a_pt + self.verticies.0;
-
-                return Triangle::new(self.verticies.0, a_b_pt, c_a_pt)
-                    .clip(x_bound, y_bound, depth);
-            } else {
-                let mut b_c_pt = self.verticies.1 - self.verticies.2;
-                let f = -self.verticies.2.x / b_c_pt.x;
-                b_c_pt.x *= f;
-                b_c_pt.y *= f;
-                b_c_pt.z *= f;
-                b_c_pt = b_c_pt + self.verticies.2;
-
-                let mut c = Triangle::new(self.verticies.0, a_b_pt, self.verticies.2)
-                    .clip(x_bound, y_bound, depth);
-                c.append(
-                    &mut Triangle::new(a_b_pt, b_c_pt, self.verticies.2)
-                        .clip(x_bound, y_bound, depth),
-                );
-
-                return c;
-            }
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
         }
 
-        if self.verticies.2.x < 0.0 {
-            let mut c_a_pt = self.verticies.2 - self.verticies.0;
-            let f = -self.verticies.0.x / c_a_pt.x;
-            c_a_pt.x *= f;
-            c_a_pt.y *= f;
-            c_a_pt.z *= f;
-            c_a_pt = c_a_pt + self.verticies.0;
-
-            let mut b_c_pt = self.verticies.2 - self.verticies.1;
-            let f = -self.verticies.1.x / b_c_pt.x;
-            b_c_pt.x *= f;
-            b_c_pt.y *= f;
-            b_c_pt.z *= f;
-            b_c_pt = b_c_pt + self.verticies.1;
-
-            let mut c = Triangle::new(self.verticies.0, self.verticies.1, c_a_pt)
-                .clip(x_bound, y_bound, depth);
-            c.append(
-                &mut Triangle::new(c_a_pt, self.verticies.1, b_c_pt).clip(x_bound, y_bound, depth),
-            );
+        // the face
+        let denom = 1.0 / (va + vb + vc);
+        a + ab * (vb * denom) + ac * (vc * denom)
+    }
 
-            return c;
+    /// Calculates the barycentric coordinates of a point on the
+    /// screen (x, y) relative to the Triangle's verticies.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `point` - The point.
+    ///
+    /// # Return
+    /// The weights of the three verticies
+    ///
+    pub fn barycentric(&self, point: Vec3d) -> (f32, f32, f32) {
+        let (a, b, c) = self.verticies;
+        let denom = (b.y - c.y) * (a.x - c.x) + (c.x - b.x) * (a.y - c.y);
+        if denom == 0.0 {
+            return (1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0);
         }
+        let w0 = ((b.y - c.y) * (point.x - c.x) + (c.x - b.x) * (point.y - c.y)) / denom;
+        let w1 = ((c.y - a.y) * (point.x - c.x) + (a.x - c.x) * (point.y - c.y)) / denom;
+        (w0, w1, 1.0 - w0 - w1)
+    }
 
-        // --------------------------------------------------------------------------------------------------------
-        // Y ------------------------------------------------------------------------------------------------------
-        if self.verticies.0.y > y_bound {
-            if self.verticies.1.y > y_bound {
-                if !(self.verticies.2.y > y_bound) {
-                    let mut b_c_pt = self.verticies.1 - self.verticies.2;
-                    let f = (y_bound - self.verticies.2.y) / b_c_pt.y;
-                    b_c_pt.x *= f;
-                    b_c_pt.y *= f;
-                    b_c_pt.z *= f;
-                    b_c_pt = b_c_pt + self.verticies.2;
-
-                    let mut c_a_pt = self.verticies.0 - self.verticies.2;
-                    let f = (y_bound - self.verticies.2.y) / c_a_pt.y;
-                    c_a_pt.x *= f;
-                    c_a_pt.y *= f;
-                    c_a_pt.z *= f;
-                    c_a_pt = c_a_pt + self.verticies.2;
-
-                    return Triangle::new(c_a_pt, b_c_pt, self.verticies.2)
-                        .clip(x_bound, y_bound, depth);
-                } // else \/\/\/// return
-            } else {
-                let mut a_b_pt = self.verticies.0 - self.verticies.1;
-                let f = (y_bound - self.verticies.1.y) / a_b_pt.y;
-                a_b_pt.x *= f;
-                a_b_pt.y *= f;
-                a_b_pt.z *= f;
-                a_b_pt = a_b_pt + self.verticies.1;
-                if self.verticies.2.y > y_bound {
-                    let mut b_c_pt = self.verticies.2 - self.verticies.1;
-                    let f = (y_bound - self.verticies.1.y) / b_c_pt.y;
-                    b_c_pt.x *= f;
-                    b_c_pt.y *= f;
-                    b_c_pt.z *= f;
-                    b_c_pt = b_c_pt + self.verticies.1;
-
-                    return Triangle::new(a_b_pt, self.verticies.1, b_c_pt)
-                        .clip(x_bound, y_bound, depth); // return
-                } else {
-                    let mut c_a_pt = self.verticies.0 - self.verticies.2;
-                    let f = (y_bound - self.verticies.2.y) / c_a_pt.y;
-                    c_a_pt.x *= f;
-                    c_a_pt.y *= f;
-                    c_a_pt.z *= f;
-                    c_a_pt = c_a_pt + self.verticies.2;
-
-                    let mut c = Triangle::new(a_b_pt, self.verticies.1, self.verticies.2)
-                        .clip(x_bound, y_bound, depth);
-                    c.append(
-                        &mut Triangle::new(a_b_pt, self.verticies.2, c_a_pt)
-                            .clip(x_bound, y_bound, depth),
-                    );
-
-                    return c;
-                }
-            }
-        }
+    /// Creates a Triangle that is a piece of this one, so the
+    /// piece keeps the Material and the vertex attributes are
+    /// interpolated from this Triangle's verticies.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `vertex1` - The first vertex of the piece.
+    /// * `vertex2` - The second vertex of the piece.
+    /// * `vertex3` - The third vertex of the piece.
+    ///
+    /// # Return
+    /// The new piece
+    ///
+    pub fn fragment(&self, vertex1: Vec3d, vertex2: Vec3d, vertex3: Vec3d) -> Triangle {
+        let mut piece = *self;
+        piece.verticies = (vertex1, vertex2, vertex3);
+        piece.center = Triangle::calculate_center(piece.verticies);
+
+        // the attributes belong to the surface so they are not linear on the screen
+        let p0 = self.perspective_weights(self.barycentric(vertex1));
+        let p1 = self.perspective_weights(self.barycentric(vertex2));
+        let p2 = self.perspective_weights(self.barycentric(vertex3));
+        piece.vertex_colors = (
+            self.interpolate_color(p0),
+            self.interpolate_color(p1),
+            self.interpolate_color(p2),
+        );
+        piece.world_verticies = (
+            Triangle::interpolate_vec3d(self.world_verticies, p0),
+            Triangle::interpolate_vec3d(self.world_verticies, p1),
+            Triangle::interpolate_vec3d(self.world_verticies, p2),
+        );
+        piece.vertex_normals = (
+            Triangle::interpolate_vec3d(self.vertex_normals, p0),
+            Triangle::interpolate_vec3d(self.vertex_normals, p1),
+            Triangle::interpolate_vec3d(self.vertex_normals, p2),
+        );
+        piece.uvs = (
+            self.interpolate_uv(p0),
+            self.interpolate_uv(p1),
+            self.interpolate_uv(p2),
+        );
+        piece
+    }
 
-        if self.verticies.1.y > y_bound {
-            let mut a_b_pt = self.verticies.1 - self.verticies.0;
-            let f = (y_bound - self.verticies.0.y) / a_b_pt.y;
-            a_b_pt.x *= f;
-            a_b_pt.y *= f;
-            a_b_pt.z *= f;
-            a_b_pt = a_b_pt + self.verticies.0;
-
-            if self.verticies.2.y > y_bound {
-                let mut c_a_pt = self.verticies.2 - self.verticies.0;
-                let f = (y_bound - self.verticies.0.y) / c_a_pt.y;
-                c_a_pt.x *= f;
-                c_a_pt.y *= f;
-                c_a_pt.z *= f;
-                c_a_pt = c_a_pt + self.verticies.0;
-
-                return Triangle::new(self.verticies.0, a_b_pt, c_a_pt)
-                    .clip(x_bound, y_bound, depth);
-            } else {
-                let mut b_c_pt = self.verticies.1 - self.verticies.2;
-                let f = (y_bound - self.verticies.2.y) / b_c_pt.y;
-                b_c_pt.x *= f;
-                b_c_pt.y *= f;
-                b_c_pt.z *= f;
-                b_c_pt = b_c_pt + self.verticies.2;
-
-                let mut c = Triangle::new(self.verticies.0, a_b_pt, self.verticies.2)
-                    .clip(x_bound, y_bound, depth);
-                c.append(
-                    &mut Triangle::new(a_b_pt, b_c_pt, self.verticies.2)
-                        .clip(x_bound, y_bound, depth),
-                );
-
-                return c;
-            }
+    /// Interpolates the texture coordinates with barycentric weights.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `w` - The weights of the three verticies.
+    ///
+    /// # Return
+    /// The interpolated texture coordinate
+    ///
+    pub fn interpolate_uv(&self, w: (f32, f32, f32)) -> (f32, f32) {
+        let (t0, t1, t2) = self.uvs;
+        (
+            t0.0 * w.0 + t1.0 * w.1 + t2.0 * w.2,
+            t0.1 * w.0 + t1.1 * w.1 + t2.1 * w.2,
+        )
+    }
+
+    /// Corrects screen space barycentric weights of a projected Triangle
+    /// for perspective. The z of a projected vertex is 1 / distance which
+    /// is linear on the screen, so the weights are divided by the distance
+    /// and then normalized.
+    ///
+    /// # Arguments
+    /// * `self` - The projected Triangle the function was called for.
+    /// * `w` - The screen space weights of the three verticies.
+    ///
+    /// # Return
+    /// The perspective-correct weights
+    ///
+    pub fn perspective_weights(&self, w: (f32, f32, f32)) -> (f32, f32, f32) {
+        let w0 = w.0 * self.verticies.0.z;
+        let w1 = w.1 * self.verticies.1.z;
+        let w2 = w.2 * self.verticies.2.z;
+        let sum = w0 + w1 + w2;
+        if sum == 0.0 {
+            return w;
         }
+        (w0 / sum, w1 / sum, w2 / sum)
+    }
 
-        if self.verticies.2.y > y_bound {
-            let mut c_a_pt = self.verticies.2 - self.verticies.0;
-            let f = (y_bound - self.verticies.0.y) / c_a_pt.y;
-            c_a_pt.x *= f;
-            c_a_pt.y *= f;
-            c_a_pt.z *= f;
-            c_a_pt = c_a_pt + self.verticies.0;
-
-            let mut b_c_pt = self.verticies.2 - self.verticies.1;
-            let f = (y_bound - self.verticies.1.y) / b_c_pt.y;
-            b_c_pt.x *= f;
-            b_c_pt.y *= f;
-            b_c_pt.z *= f;
-            b_c_pt = b_c_pt + self.verticies.1;
-
-            let mut c = Triangle::new(self.verticies.0, self.verticies.1, c_a_pt)
-                .clip(x_bound, y_bound, depth);
-            c.append(
-                &mut Triangle::new(c_a_pt, self.verticies.1, b_c_pt).clip(x_bound, y_bound, depth),
-            );
+    /// Interpolates three Vec3d's with barycentric weights.
+    ///
+    /// # Arguments
+    /// * `values` - The values at the three verticies.
+    /// * `w` - The weights of the three verticies.
+    ///
+    /// # Return
+    /// The interpolated Vec3d
+    ///
+    pub fn interpolate_vec3d(values: (Vec3d, Vec3d, Vec3d), w: (f32, f32, f32)) -> Vec3d {
+        values.0 * w.0 + values.1 * w.1 + values.2 * w.2
+    }
 
-            return c;
+    /// Interpolates the vertex colors with barycentric weights.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `w` - The weights of the three verticies.
+    ///
+    /// # Return
+    /// The interpolated color
+    ///
+    pub fn interpolate_color(&self, w: (f32, f32, f32)) -> (f32, f32, f32) {
+        let (c0, c1, c2) = self.vertex_colors;
+        (
+            c0.0 * w.0 + c1.0 * w.1 + c2.0 * w.2,
+            c0.1 * w.0 + c1.1 * w.1 + c2.1 * w.2,
+            c0.2 * w.0 + c1.2 * w.1 + c2.2 * w.2,
+        )
+    }
+
+    /// Clip the Triangle's so that no part of them is being rendered off the screen.
+    /// The projected Triangle is cut by the four edges of the screen one after
+    /// the other and the polygon that is left is split into Triangle's again.
+    ///
+    /// # Arguments
+    /// * `self` - The projected Triangle the function was called for.
+    /// * `x_bound` - The width of the screen
+    /// * `y_bound` - The height of the screen
+    ///
+    /// # Return
+    /// A vec of Triangle's that are on the screen and can replace the original
+    ///
+    pub fn clip(&self, x_bound: f32, y_bound: f32) -> Vec<Triangle> {
+        let (a, b, c) = self.verticies;
+        let edges = [
+            (a.x, b.x, c.x),
+            (x_bound - a.x, x_bound - b.x, x_bound - c.x),
+            (a.y, b.y, c.y),
+            (y_bound - a.y, y_bound - b.y, y_bound - c.y),
+        ];
+        if edges.iter().all(|d| d.0 >= 0.0 && d.1 >= 0.0 && d.2 >= 0.0) {
+            return vec![*self];
         }
 
-        // < 0
-        if self.verticies.0.y < 0.0 {
-            if self.verticies.1.y < 0.0 {
-                if !(self.verticies.2.y < 0.0) {
-                    let mut b_c_pt = self.verticies.1 - self.verticies.2;
-                    let f = -self.verticies.2.y / b_c_pt.y;
-                    b_c_pt.x *= f;
-                    b_c_pt.y *= f;
-                    b_c_pt.z *= f;
-                    b_c_pt = b_c_pt + self.verticies.2;
-
-                    let mut c_a_pt = self.verticies.0 - self.verticies.2;
-                    let f = -self.verticies.2.y / c_a_pt.y;
-                    c_a_pt.x *= f;
-                    c_a_pt.y *= f;
-                    c_a_pt.z *= f;
-                    c_a_pt = c_a_pt + self.verticies.2;
-
-                    return Triangle::new(c_a_pt, b_c_pt, self.verticies.2)
-                        .clip(x_bound, y_bound, depth);
-                }
-            } else {
-                let mut a_b_pt = self.verticies.0 - self.verticies.1;
-                let f = -self.verticies.1.y / a_b_pt.y;
-                a_b_pt.x *= f;
-                a_b_pt.y *= f;
-                a_b_pt.z *= f;
-                a_b_pt = a_b_pt + self.verticies.1;
-                if self.verticies.2.y < 0.0 {
-                    let mut b_c_pt = self.verticies.2 - self.verticies.1;
-                    let f = -self.verticies.1.y / b_c_pt.y;
-                    b_c_pt.x *= f;
-                    b_c_pt.y *= f;
-                    b_c_pt.z *= f;
-                    b_c_pt = b_c_pt + self.verticies.1;
-
-                    return Triangle::new(a_b_pt, self.verticies.1, b_c_pt)
-                        .clip(x_bound, y_bound, depth); // return
-                } else {
-                    let mut c_a_pt = self.verticies.0 - self.verticies.2;
-                    let f = -self.verticies.2.y / c_a_pt.y;
-                    c_a_pt.x *= f;
-                    c_a_pt.y *= f;
-                    c_a_pt.z *= f;
-                    c_a_pt = c_a_pt + self.verticies.2;
-
-                    let mut c = Triangle::new(a_b_pt, self.verticies.1, self.verticies.2)
-                        .clip(x_bound, y_bound, depth);
-                    c.append(
-                        &mut Triangle::new(a_b_pt, self.verticies.2, c_a_pt)
-                            .clip(x_bound, y_bound, depth),
-                    );
-
-                    return c;
-                }
-            }
+        let mut polygon = vec![(1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0)];
+        for distances in edges.iter() {
+            polygon = Triangle::clip_polygon(&polygon, *distances);
         }
 
-        if self.verticies.1.y < 0.0 {
-            let mut a_b_pt = self.verticies.1 - self.verticies.0;
-            let f = -self.verticies.0.y / a_b_pt.y;
-            a_b_pt.x *= f;
-            a_b_pt.y *= f;
-            a_b_pt.z *= f;
-            a_b_pt = a_b_pt + self.verticies.0;
-
-            if self.verticies.2.y < 0.0 {
-                let mut c_a_pt = self.verticies.2 - self.verticies.0;
-                let f = -self.verticies.0.y / c_a_pt.y;
-                c_a_pt.x *= f;
-                c_a_pt.y *= f;
-                c_a_pt.z *= f;
-                c_a_pt = c_a_pt + self.verticies.0;
-
-                return Triangle::new(self.verticies.0, a_b_pt, c_a_pt)
-                    .clip(x_bound, y_bound, depth);
-            } else {
-                let mut b_c_pt = self.verticies.1 - self.verticies.2;
-                let f = -self.verticies.2.y / b_c_pt.y;
-                b_c_pt.x *= f;
-                b_c_pt.y *= f;
-                b_c_pt.z *= f;
-                b_c_pt = b_c_pt + self.verticies.2;
-
-                let mut c = Triangle::new(self.verticies.0, a_b_pt, self.verticies.2)
-                    .clip(x_bound, y_bound, depth);
-                c.append(
-                    &mut Triangle::new(a_b_pt, b_c_pt, self.verticies.2)
-                        .clip(x_bound, y_bound, depth),
-                );
-
-                return c;
-            }
+        // λ is linear on the screen so the corners can be interpolated directly
+        let corner = |w: (f32, f32, f32)| Triangle::interpolate_vec3d(self.verticies, w);
+        let mut out: Vec<Triangle> = Vec::new();
+        for i in 1..polygon.len().saturating_sub(1) {
+            out.push(self.fragment(
+                corner(polygon[0]),
+                corner(polygon[i]),
+                corner(polygon[i + 1]),
+            ));
         }
+        out
+    }
 
-        if self.verticies.2.y < 0.0 {
-            let mut c_a_pt = self.verticies.2 - self.verticies.0;
-            let f = -self.verticies.0.y / c_a_pt.y;
-            c_a_pt.x *= f;
-            c_a_pt.y *= f;
-            c_a_pt.z *= f;
-            c_a_pt = c_a_pt + self.verticies.0;
-
-            let mut b_c_pt = self.verticies.2 - self.verticies.1;
-            let f = -self.verticies.1.y / b_c_pt.y;
-            b_c_pt.x *= f;
-            b_c_pt.y *= f;
-            b_c_pt.z *= f;
-            b_c_pt = b_c_pt + self.verticies.1;
-
-            let mut c = Triangle::new(self.verticies.0, self.verticies.1, c_a_pt)
-                .clip(x_bound, y_bound, depth);
-            c.append(
-                &mut Triangle::new(c_a_pt, self.verticies.1, b_c_pt).clip(x_bound, y_bound, depth),
-            );
+    /// Clip a Triangle in the world to a plane. The part of the Triangle
+    /// with a negative distance to the plane is cut off. The vertex
+    /// attributes of the pieces are interpolated from this Triangle.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    /// * `distances` - The distances of the three verticies to the plane.
+    ///
+    /// # Return
+    /// A vec of the Triangle's in front of the plane
+    ///
+    pub fn clip_to_plane(&self, distances: (f32, f32, f32)) -> Vec<Triangle> {
+        if distances.0 >= 0.0 && distances.1 >= 0.0 && distances.2 >= 0.0 {
+            return vec![*self];
+        }
+        let polygon = Triangle::clip_polygon(
+            &vec![(1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0)],
+            distances,
+        );
 
-            return c;
+        let mut out: Vec<Triangle> = Vec::new();
+        for i in 1..polygon.len().saturating_sub(1) {
+            let weights = (polygon[0], polygon[i], polygon[i + 1]);
+            let mut piece = *self;
+            piece.verticies = (
+                Triangle::interpolate_vec3d(self.verticies, weights.0),
+                Triangle::interpolate_vec3d(self.verticies, weights.1),
+                Triangle::interpolate_vec3d(self.verticies, weights.2),
+            );
+            piece.world_verticies = piece.verticies;
+            piece.center = Triangle::calculate_center(piece.verticies);
+            piece.vertex_normals = (
+                Triangle::interpolate_vec3d(self.vertex_normals, weights.0),
+                Triangle::interpolate_vec3d(self.vertex_normals, weights.1),
+                Triangle::interpolate_vec3d(self.vertex_normals, weights.2),
+            );
+            piece.vertex_colors = (
+                self.interpolate_color(weights.0),
+                self.interpolate_color(weights.1),
+                self.interpolate_color(weights.2),
+            );
+            piece.uvs = (
+                self.interpolate_uv(weights.0),
+                self.interpolate_uv(weights.1),
+                self.interpolate_uv(weights.2),
+            );
+            out.push(piece);
         }
+        out
+    }
 
-        vec![*self]
+    /// Cuts a convex polygon on a Triangle by a plane (Sutherland-Hodgman).
+    /// The corners of the polygon are barycentric weights of the Triangle,
+    /// so the distance to the plane at a corner is interpolated from the
+    /// distances at the verticies.
+    ///
+    /// # Arguments
+    /// * `polygon` - The corners of the polygon.
+    /// * `distances` - The distances of the three verticies to the plane.
+    ///
+    /// # Return
+    /// The corners of the part of the polygon in front of the plane
+    ///
+    fn clip_polygon(
+        polygon: &Vec<(f32, f32, f32)>,
+        distances: (f32, f32, f32),
+    ) -> Vec<(f32, f32, f32)> {
+        let distance =
+            |w: (f32, f32, f32)| w.0 * distances.0 + w.1 * distances.1 + w.2 * distances.2;
+        let mut out: Vec<(f32, f32, f32)> = Vec::with_capacity(polygon.len() + 1);
+        for i in 0..polygon.len() {
+            let current = polygon[i];
+            let next = polygon[(i + 1) % polygon.len()];
+            let d_current = distance(current);
+            let d_next = distance(next);
+            if d_current >= 0.0 {
+                out.push(current);
+            }
+            if (d_current >= 0.0) != (d_next >= 0.0) {
+                let t = d_current / (d_current - d_next);
+                out.push((
+                    current.0 + (next.0 - current.0) * t,
+                    current.1 + (next.1 - current.1) * t,
+                    current.2 + (next.2 - current.2) * t,
+                ));
+            }
+        }
+        out
     }
 
-    /// sort an array of Triangle's according to depth
+    /// sort an array of Triangle's according to depth. Opaque Triangle's
+    /// come first and transparent ones after so they can be blended
+    /// over what is behind them.
     ///
     /// # Arguments
     /// * `v` - The vec of projected Triangle's
     ///
     pub fn painters_algorithm(projected_triangles: &Vec<Triangle>) -> Vec<Triangle> {
         let mut out: Vec<Triangle> = projected_triangles.clone();
-        out.sort_by(|b, a| a.dist.partial_cmp(&b.dist).unwrap());
+        out.sort_by(|b, a| {
+            (b.alpha < 1.0)
+                .cmp(&(a.alpha < 1.0))
+                .then(a.dist.partial_cmp(&b.dist).unwrap_or(Ordering::Equal))
+        });
         out
     }
 
@@ -751,11 +711,27 @@ impl Triangle {
         list
     }
 
+    /// convert a projected Triangle to an array of 3 ggez Vertex's
+    /// colored with the vertex colors and with the texture coordinates.
+    ///
+    /// # Arguments
+    /// * `self` - The Triangle the function was called for.
+    ///
+    /// # Return
+    /// An array of 3 Vertex's representing the projected 3 vertecies
+    /// of the Triangle.
+    ///
     pub fn form_vertexlist(&mut self) -> [Vertex; 3] {
         let list: [Vertex; 3] = [
-            self.verticies.0.form_vertex(),
-            self.verticies.1.form_vertex(),
-            self.verticies.2.form_vertex(),
+            self.verticies
+                .0
+                .form_vertex(self.vertex_colors.0, self.alpha, self.uvs.0),
+            self.verticies
+                .1
+                .form_vertex(self.vertex_colors.1, self.alpha, self.uvs.1),
+            self.verticies
+                .2
+                .form_vertex(self.vertex_colors.2, self.alpha, self.uvs.2),
         ];
         list
     }
@@ -766,7 +742,7 @@ impl Add<Vec3d> for Triangle {
     type Output = Triangle;
 
     fn add(self, other: Vec3d) -> Self {
-        let mut new_tri = self.clone();
+        let mut new_tri = self;
 
         new_tri.verticies.0 += other;
         new_tri.verticies.1 += other;
@@ -776,3 +752,115 @@ impl Add<Vec3d> for Triangle {
         new_tri
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{triangle::Triangle, vec3d::Vec3d};
+
+    /// A Triangle with a different color and texture coordinate at every vertex.
+    fn colored(a: Vec3d, b: Vec3d, c: Vec3d) -> Triangle {
+        let mut tri = Triangle::new(a, b, c);
+        tri.world_verticies = tri.verticies;
+        tri.vertex_colors = ((1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0));
+        tri.uvs = ((0.0, 0.0), (1.0, 0.0), (0.0, 1.0));
+        tri
+    }
+
+    fn close(a: (f32, f32, f32), b: (f32, f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-5 && (a.1 - b.1).abs() < 1e-5 && (a.2 - b.2).abs() < 1e-5
+    }
+
+    #[test]
+    fn near_plane_clip_interpolates_colors_and_uvs() {
+        let tri = colored(
+            Vec3d::new(0.0, 0.0, 1.0),
+            Vec3d::new(1.0, 0.0, 1.0),
+            Vec3d::new(0.0, 1.0, -1.0),
+        );
+        // the plane is z = 0 so the third vertex is cut off at half of its edges
+        let pieces = tri.clip_to_plane((1.0, 1.0, -1.0));
+        assert_eq!(pieces.len(), 2);
+        let mut corners = 0;
+        for piece in pieces.iter() {
+            let verticies = [piece.verticies.0, piece.verticies.1, piece.verticies.2];
+            let colors = [
+                piece.vertex_colors.0,
+                piece.vertex_colors.1,
+                piece.vertex_colors.2,
+            ];
+            let uvs = [piece.uvs.0, piece.uvs.1, piece.uvs.2];
+            for k in 0..3 {
+                assert!(verticies[k].z >= -1e-6);
+                if verticies[k].z.abs() < 1e-6 {
+                    corners += 1;
+                    // every attribute is the one of the point on the edge
+                    let w = tri.barycentric(verticies[k]);
+                    assert!(close(colors[k], tri.interpolate_color(w)));
+                    let uv = tri.interpolate_uv(w);
+                    assert!((uvs[k].0 - uv.0).abs() < 1e-5 && (uvs[k].1 - uv.1).abs() < 1e-5);
+                    assert!((colors[k].2 - 0.5).abs() < 1e-5);
+                    assert!((uvs[k].1 - 0.5).abs() < 1e-5);
+                }
+            }
+        }
+        assert!(corners >= 2);
+    }
+
+    #[test]
+    fn screen_clip_keeps_the_colors_of_the_surface() {
+        // z is 1 / distance, the right vertex is twice as far away
+        let tri = colored(
+            Vec3d::new(-50.0, 0.0, 1.0),
+            Vec3d::new(150.0, 0.0, 0.5),
+            Vec3d::new(-50.0, 100.0, 1.0),
+        );
+        let pieces = tri.clip(100.0, 100.0);
+        assert!(pieces.len() > 0);
+        for piece in pieces.iter() {
+            let verticies = [piece.verticies.0, piece.verticies.1, piece.verticies.2];
+            let colors = [
+                piece.vertex_colors.0,
+                piece.vertex_colors.1,
+                piece.vertex_colors.2,
+            ];
+            let uvs = [piece.uvs.0, piece.uvs.1, piece.uvs.2];
+            for k in 0..3 {
+                assert!(verticies[k].x >= -1e-3 && verticies[k].x <= 100.0 + 1e-3);
+                let p = tri.perspective_weights(tri.barycentric(verticies[k]));
+                assert!(close(colors[k], tri.interpolate_color(p)));
+                let uv = tri.interpolate_uv(p);
+                assert!((uvs[k].0 - uv.0).abs() < 1e-5 && (uvs[k].1 - uv.1).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn painters_algorithm_draws_opaque_then_back_to_front() {
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        let mut tris = Vec::new();
+        for (dist, alpha) in [(2.0, 0.5), (5.0, 1.0), (9.0, 0.5), (1.0, 1.0), (4.0, 0.5)].iter() {
+            let mut tri = colored(origin, Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0));
+            tri.dist = *dist;
+            tri.alpha = *alpha;
+            tris.push(tri);
+        }
+        let sorted: Vec<(f32, f32)> = Triangle::painters_algorithm(&tris)
+            .iter()
+            .map(|tri| (tri.dist, tri.alpha))
+            .collect();
+        assert_eq!(
+            sorted,
+            vec![(5.0, 1.0), (1.0, 1.0), (9.0, 0.5), (4.0, 0.5), (2.0, 0.5)]
+        );
+    }
+
+    #[test]
+    fn painters_algorithm_survives_a_nan_distance() {
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        let mut tris =
+            vec![colored(origin, Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0)); 3];
+        tris[1].dist = f32::NAN;
+        tris[2].dist = 3.0;
+        assert_eq!(Triangle::painters_algorithm(&tris).len(), 3);
+    }
+}
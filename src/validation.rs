@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+
+use crate::{bounds::Aabb, mesh::Mesh, vec3d::Vec3d};
+
+/// What is wrong with an edge of a Mesh.
+///  - Open          only one Triangle uses the edge
+///  - NonManifold   more than two Triangles use the edge
+///  - Winding       the two Triangles using the edge run along it the same way
+#[derive(Copy, Clone, PartialEq)]
+pub enum EdgeProblem {
+    Open,
+    NonManifold,
+    Winding,
+}
+
+/// A MeshReport measures a Mesh and lists what would keep it from
+/// being printed. A Mesh can be printed when it is watertight, every
+/// edge is shared by exactly two Triangles that agree on the winding
+/// and no Triangle is degenerate or a duplicate.
+///
+/// Verticies are matched by position so a Mesh does not need shared
+/// verticies. Duplicate Triangles are left out when the edges are checked
+/// so they are only reported once. Degenerate Triangles are kept since a
+/// sliver closes the edges of the Triangles around it, only their edges
+/// without length are left out.
+///
+/// Edges are kept as the index of a Triangle and the corner they start
+/// at so they follow the Mesh when it moves.
+pub struct MeshReport {
+    pub aabb: Aabb,
+    pub area: f32,
+    pub volume: f32,
+    pub centroid: Vec3d,
+    pub open_edges: usize,
+    pub non_manifold_edges: usize,
+    pub inconsistent_edges: usize,
+    pub problem_edges: Vec<(usize, usize, EdgeProblem)>,
+    pub inconsistent_faces: Vec<usize>,
+    pub degenerate_faces: Vec<usize>,
+    pub duplicate_faces: Vec<usize>,
+}
+
+impl MeshReport {
+    /// Triangles with less area than this are degenerate.
+    pub const MIN_AREA: f32 = 1e-10;
+
+    /// The color of an open edge.
+    pub const OPEN_COLOR: (f32, f32, f32) = (1.0, 0.15, 0.15);
+
+    /// The color of a non-manifold edge.
+    pub const NON_MANIFOLD_COLOR: (f32, f32, f32) = (1.0, 0.1, 1.0);
+
+    /// The color of an edge with inconsistent winding.
+    pub const WINDING_COLOR: (f32, f32, f32) = (1.0, 0.9, 0.1);
+
+    /// The color of the outline of a degenerate or duplicate Triangle.
+    pub const FACE_COLOR: (f32, f32, f32) = (0.1, 0.9, 1.0);
+
+    /// Measures a Mesh and checks it for problems.
+    ///
+    /// # Arguments
+    /// * `mesh` - The Mesh.
+    ///
+    /// # Return
+    /// A new MeshReport
+    ///
+    pub fn new(mesh: &Mesh) -> MeshReport {
+        let mut report = MeshReport {
+            aabb: Aabb::empty(),
+            area: 0.0,
+            volume: 0.0,
+            centroid: Vec3d::new(0.0, 0.0, 0.0),
+            open_edges: 0,
+            non_manifold_edges: 0,
+            inconsistent_edges: 0,
+            problem_edges: Vec::new(),
+            inconsistent_faces: Vec::new(),
+            degenerate_faces: Vec::new(),
+            duplicate_faces: Vec::new(),
+        };
+        report.measure(mesh);
+        report.check_faces(mesh);
+        report.check_edges(mesh);
+        report
+    }
+
+    /// Checks if every edge is shared by exactly two Triangles.
+    ///
+    /// # Arguments
+    /// * `self` - The MeshReport the function was called for.
+    ///
+    /// # Return
+    /// If the Mesh is closed and manifold
+    ///
+    pub fn is_watertight(&self) -> bool {
+        self.open_edges == 0 && self.non_manifold_edges == 0
+    }
+
+    /// Checks if nothing keeps the Mesh from being printed.
+    ///
+    /// # Arguments
+    /// * `self` - The MeshReport the function was called for.
+    ///
+    /// # Return
+    /// If the Mesh is watertight, consistently wound, faces outward
+    /// and has no degenerate or duplicate Triangles
+    ///
+    pub fn is_printable(&self) -> bool {
+        self.is_watertight()
+            && self.inconsistent_edges == 0
+            && self.degenerate_faces.len() == 0
+            && self.duplicate_faces.len() == 0
+            && self.volume > 0.0
+    }
+
+    /// Describes the report in a few lines of text.
+    ///
+    /// # Arguments
+    /// * `self` - The MeshReport the function was called for.
+    ///
+    /// # Return
+    /// The description
+    ///
+    pub fn summary(&self) -> String {
+        let v = |v: Vec3d| format!("({:.4}, {:.4}, {:.4})", v.x, v.y, v.z);
+        let mut lines = vec![
+            format!(
+                "bounding box        {} to {}",
+                v(self.aabb.min),
+                v(self.aabb.max)
+            ),
+            format!("surface area        {:.6}", self.area),
+            format!("signed volume       {:.6}", self.volume),
+            format!("centroid            {}", v(self.centroid)),
+            format!("open edges          {}", self.open_edges),
+            format!("non-manifold edges  {}", self.non_manifold_edges),
+            format!("inconsistent edges  {}", self.inconsistent_edges),
+            format!("degenerate faces    {}", self.degenerate_faces.len()),
+            format!("duplicate faces     {}", self.duplicate_faces.len()),
+        ];
+        lines.push(if self.is_printable() {
+            String::from("printable")
+        } else if self.is_watertight() && self.volume <= 0.0 {
+            String::from("not printable: the faces point inward")
+        } else {
+            String::from("not printable")
+        });
+        lines.join("\n")
+    }
+
+    /// The ends and color of every edge with a problem and of every
+    /// edge of a degenerate or duplicate Triangle in the world.
+    ///
+    /// # Arguments
+    /// * `self` - The MeshReport the function was called for.
+    /// * `mesh` - The Mesh the report is for.
+    ///
+    /// # Return
+    /// The real ends and the color of the lines
+    ///
+    pub fn lines(&self, mesh: &Mesh) -> Vec<(Vec3d, Vec3d, (f32, f32, f32))> {
+        let corner = |face: usize, corner: usize| {
+            let v = mesh.tris[face].verticies;
+            mesh.transform.apply([v.0, v.1, v.2][corner % 3])
+        };
+        let mut lines = Vec::new();
+        for (face, start, problem) in self.problem_edges.iter() {
+            if *face >= mesh.tris.len() {
+                continue;
+            }
+            let color = match problem {
+                EdgeProblem::Open => MeshReport::OPEN_COLOR,
+                EdgeProblem::NonManifold => MeshReport::NON_MANIFOLD_COLOR,
+                EdgeProblem::Winding => MeshReport::WINDING_COLOR,
+            };
+            lines.push((corner(*face, *start), corner(*face, start + 1), color));
+        }
+        for face in self
+            .degenerate_faces
+            .iter()
+            .chain(self.duplicate_faces.iter())
+        {
+            if *face >= mesh.tris.len() {
+                continue;
+            }
+            for start in 0..3 {
+                let color = MeshReport::FACE_COLOR;
+                lines.push((corner(*face, start), corner(*face, start + 1), color));
+            }
+        }
+        lines
+    }
+
+    /// Measures the bounding box, the area, the signed volume and the
+    /// centroid. The volume is the sum of the tetrahedra from the origin
+    /// to each Triangle so it is only meaningful for closed meshes and
+    /// negative when the Triangles face inward. It is summed in f64 since
+    /// the tetrahedra of a Mesh far from the origin mostly cancel out.
+    ///
+    /// # Arguments
+    /// * `self` - The MeshReport the function was called for.
+    /// * `mesh` - The Mesh.
+    ///
+    fn measure(&mut self, mesh: &Mesh) {
+        let f64s = |v: Vec3d| [v.x as f64, v.y as f64, v.z as f64];
+        let mut volume = 0.0;
+        let mut volume_center = [0.0; 3];
+        let mut area_center = Vec3d::new(0.0, 0.0, 0.0);
+        for tri in mesh.tris.iter() {
+            let (a, b, c) = tri.verticies;
+            self.aabb.grow(a);
+            self.aabb.grow(b);
+            self.aabb.grow(c);
+
+            let area = (b - a).cross(c - a).length() * 0.5;
+            self.area += area;
+            area_center += (a + b + c) * (area / 3.0);
+
+            let (a, b, c) = (f64s(a), f64s(b), f64s(c));
+            let tetrahedron = (a[0] * (b[1] * c[2] - b[2] * c[1])
+                + a[1] * (b[2] * c[0] - b[0] * c[2])
+                + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                / 6.0;
+            volume += tetrahedron;
+            for i in 0..3 {
+                volume_center[i] += (a[i] + b[i] + c[i]) * (tetrahedron / 4.0);
+            }
+        }
+        self.volume = volume as f32;
+        // a Mesh without volume has its centroid on the surface
+        self.centroid = if volume.abs() > MeshReport::MIN_AREA as f64 {
+            let c = |i: usize| (volume_center[i] / volume) as f32;
+            Vec3d::new(c(0), c(1), c(2))
+        } else if self.area > 0.0 {
+            area_center * (1.0 / self.area)
+        } else {
+            self.aabb.center()
+        };
+    }
+
+    /// Finds the degenerate Triangles and the Triangles that use the
+    /// same three verticies as an earlier one in any order.
+    ///
+    /// # Arguments
+    /// * `self` - The MeshReport the function was called for.
+    /// * `mesh` - The Mesh.
+    ///
+    fn check_faces(&mut self, mesh: &Mesh) {
+        let mut seen: HashMap<[(i64, i64, i64); 3], usize> = HashMap::new();
+        for i in 0..mesh.tris.len() {
+            let (a, b, c) = mesh.tris[i].verticies;
+            let mut keys = [
+                Mesh::vertex_key(a),
+                Mesh::vertex_key(b),
+                Mesh::vertex_key(c),
+            ];
+            let area = (b - a).cross(c - a).length() * 0.5;
+            if area < MeshReport::MIN_AREA
+                || keys[0] == keys[1]
+                || keys[1] == keys[2]
+                || keys[2] == keys[0]
+            {
+                self.degenerate_faces.push(i);
+                continue;
+            }
+            keys.sort();
+            if seen.insert(keys, i).is_some() {
+                self.duplicate_faces.push(i);
+            }
+        }
+    }
+
+    /// Counts how many Triangles use each edge and in which direction.
+    ///
+    /// # Arguments
+    /// * `self` - The MeshReport the function was called for.
+    /// * `mesh` - The Mesh.
+    ///
+    fn check_edges(&mut self, mesh: &Mesh) {
+        type Key = (i64, i64, i64);
+        let mut skip = vec![false; mesh.tris.len()];
+        for face in self.duplicate_faces.iter() {
+            skip[*face] = true;
+        }
+
+        // the Triangles using an edge with the corner it starts at and if
+        // they run along it from the smaller key to the larger one
+        let mut edges: HashMap<(Key, Key), Vec<(usize, usize, bool)>> = HashMap::new();
+        for i in 0..mesh.tris.len() {
+            if skip[i] {
+                continue;
+            }
+            let v = mesh.tris[i].verticies;
+            let keys = [
+                Mesh::vertex_key(v.0),
+                Mesh::vertex_key(v.1),
+                Mesh::vertex_key(v.2),
+            ];
+            for corner in 0..3 {
+                let (a, b) = (keys[corner], keys[(corner + 1) % 3]);
+                if a == b {
+                    continue;
+                }
+                let key = if a < b { (a, b) } else { (b, a) };
+                edges.entry(key).or_default().push((i, corner, a < b));
+            }
+        }
+
+        let mut inconsistent = vec![false; mesh.tris.len()];
+        for uses in edges.values() {
+            let problem = match uses.len() {
+                1 => {
+                    self.open_edges += 1;
+                    EdgeProblem::Open
+                }
+                2 if uses[0].2 == uses[1].2 => {
+                    self.inconsistent_edges += 1;
+                    inconsistent[uses[0].0] = true;
+                    inconsistent[uses[1].0] = true;
+                    EdgeProblem::Winding
+                }
+                2 => continue,
+                _ => {
+                    self.non_manifold_edges += 1;
+                    EdgeProblem::NonManifold
+                }
+            };
+            let (face, corner, _) = uses[0];
+            self.problem_edges.push((face, corner, problem));
+        }
+        // keep the edges in the order of the Triangles
+        self.problem_edges
+            .sort_by_key(|(face, corner, _)| (*face, *corner));
+        self.inconsistent_faces = (0..mesh.tris.len()).filter(|i| inconsistent[*i]).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mesh::Mesh, triangle::Triangle, vec3d::Vec3d};
+
+    fn cube() -> Mesh {
+        let mut mesh = Mesh::new(Vec3d::new(2.0, -1.0, 3.0));
+        mesh.form_cube();
+        mesh
+    }
+
+    #[test]
+    fn cube_is_a_closed_manifold() {
+        let report = cube().validate();
+        assert!(report.is_watertight());
+        assert!(report.is_printable());
+        assert_eq!(report.problem_edges.len(), 0);
+        assert!((report.volume - 1.0).abs() < 1e-5);
+        assert!((report.area - 6.0).abs() < 1e-5);
+        assert!((report.centroid - Vec3d::new(2.0, -1.0, 3.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn missing_face_leaves_open_edges() {
+        let mut mesh = cube();
+        mesh.tris.remove(0);
+        let report = mesh.validate();
+        assert_eq!(report.open_edges, 3);
+        assert!(!report.is_printable());
+    }
+
+    #[test]
+    fn flipped_face_is_inconsistent() {
+        let mut mesh = cube();
+        mesh.tris[0].flip();
+        let report = mesh.validate();
+        assert!(report.is_watertight());
+        assert_eq!(report.inconsistent_edges, 3);
+        // the face and the three faces across its edges
+        assert_eq!(report.inconsistent_faces.len(), 4);
+        assert!(report.inconsistent_faces.contains(&0));
+    }
+
+    #[test]
+    fn sliver_is_only_degenerate() {
+        // split the first Triangle at the middle of an edge and close the
+        // edge of its neighbour with a sliver
+        let mut mesh = cube();
+        let (a, b, c) = mesh.tris[0].verticies;
+        let m = (a + b) * 0.5;
+        mesh.tris[0] = Triangle::new(a, m, c);
+        mesh.tris.push(Triangle::new(m, b, c));
+        mesh.tris.push(Triangle::new(a, b, m));
+        let report = mesh.validate();
+        assert_eq!(report.degenerate_faces, vec![13]);
+        assert!(report.is_watertight());
+        assert_eq!(report.inconsistent_edges, 0);
+        assert!((report.volume - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn volume_far_from_the_origin() {
+        let mut mesh = Mesh::new(Vec3d::new(1000.0, 1000.0, 1000.0));
+        mesh.form_icosphere(0.5, 2, false);
+        let volume = mesh.validate().volume;
+        let mut centered = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        centered.form_icosphere(0.5, 2, false);
+        assert!((volume - centered.validate().volume).abs() < 1e-3);
+    }
+}
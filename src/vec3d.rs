@@ -36,7 +36,7 @@ impl Vec3d {
     pub fn x_axis_rotation(&mut self, r: f32, origin_y: f32, origin_z: f32) {
         let tmp_y = self.y;
         let tmp_z = self.z;
-        let angle: f32 = (r) * (3.14159265 / 180.0);
+        let angle: f32 = r.to_radians();
         self.y =
             (tmp_y - origin_y) * f32::cos(angle) - (tmp_z - origin_z) * f32::sin(angle) + origin_y;
         self.z =
@@ -54,7 +54,7 @@ impl Vec3d {
     pub fn y_axis_rotation(&mut self, r: f32, origin_x: f32, origin_z: f32) {
         let tmp_x = self.x;
         let tmp_z = self.z;
-        let angle: f32 = (r) * (3.14159265 / 180.0);
+        let angle: f32 = r.to_radians();
         self.x =
             (tmp_x - origin_x) * f32::cos(angle) - (tmp_z - origin_z) * f32::sin(angle) + origin_x;
         self.z =
@@ -72,7 +72,7 @@ impl Vec3d {
     pub fn z_axis_rotation(&mut self, r: f32, origin_x: f32, origin_y: f32) {
         let tmp_x = self.x;
         let tmp_y = self.y;
-        let angle: f32 = (r) * (3.14159265 / 180.0);
+        let angle: f32 = r.to_radians();
         self.x =
             (tmp_x - origin_x) * f32::cos(angle) - (tmp_y - origin_y) * f32::sin(angle) + origin_x;
         self.y =
@@ -88,7 +88,7 @@ impl Vec3d {
     /// self
     ///
     pub fn normalize(&mut self) -> Vec3d {
-        let len = f32::sqrt((self.x * self.x + self.y * self.y + self.z * self.z).into());
+        let len = f32::sqrt(self.x * self.x + self.y * self.y + self.z * self.z);
         self.x = self.x / len;
         self.y = self.y / len;
         self.z = self.z / len;
@@ -112,6 +112,48 @@ impl Vec3d {
         *self
     }
 
+    /// Calculate the dot product of two Vec3d's.
+    ///
+    /// # Arguments
+    /// * `self` - The Vec3d the function was called for.
+    /// * `other` - The other Vec3d.
+    ///
+    /// # Return
+    /// The dot product
+    ///
+    pub fn dot(&self, other: Vec3d) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Calculate the length of the Vec3d.
+    ///
+    /// # Arguments
+    /// * `self` - The Vec3d the function was called for.
+    ///
+    /// # Return
+    /// The length
+    ///
+    pub fn length(&self) -> f32 {
+        f32::sqrt(self.x * self.x + self.y * self.y + self.z * self.z)
+    }
+
+    /// Calculate the cross product of two Vec3d's.
+    ///
+    /// # Arguments
+    /// * `self` - The Vec3d the function was called for.
+    /// * `other` - The other Vec3d.
+    ///
+    /// # Return
+    /// The cross product
+    ///
+    pub fn cross(&self, other: Vec3d) -> Vec3d {
+        Vec3d::new(
+            (self.y * other.z) - (self.z * other.y),
+            (self.z * other.x) - (self.x * other.z),
+            (self.x * other.y) - (self.y * other.x),
+        )
+    }
+
     /// Remove the z value from the Vec3d.
     ///
     /// # Arguments
@@ -124,11 +166,24 @@ impl Vec3d {
         na::Point2::new(self.x, self.y)
     }
 
-    pub fn form_vertex(&mut self) -> Vertex {
+    /// Remove the z value from the Vec3d and give it a color
+    /// and a texture coordinate.
+    ///
+    /// # Arguments
+    /// * `self` - The Vec3d the function was called for.
+    /// * `color` - The color of the vertex.
+    /// * `alpha` - How opaque the vertex is.
+    /// * `uv` - The texture coordinate of the vertex.
+    ///
+    /// # Return
+    /// Vertex containing the x and y positions of the Vec3d
+    ///
+    pub fn form_vertex(&mut self, color: (f32, f32, f32), alpha: f32, uv: (f32, f32)) -> Vertex {
         Vertex {
-            color: [1.0, 1.0, 1.0, 1.0],
+            color: [color.0, color.1, color.2, alpha],
             pos: [self.x, self.y],
-            uv: [1.0, 1.0],
+            // ggez images start at the top left
+            uv: [uv.0, 1.0 - uv.1],
         }
     }
 }
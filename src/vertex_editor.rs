@@ -0,0 +1,368 @@
+use crate::{camera::Camera, gizmo::GizmoChange, mesh::Mesh, vec3d::Vec3d};
+
+/// A drag of the selected verticies in the plane facing the Camera
+/// through the grabbed vertex. `moved` is how far they were moved so far.
+#[derive(Copy, Clone)]
+pub struct VertexDrag {
+    pub start: (f32, f32),
+    pub depth: f32,
+    pub moved: Vec3d,
+}
+
+/// A VertexEditor shows the verticies of a Mesh as handles that can be
+/// selected by clicking them or by dragging a box around them. The
+/// selected verticies are dragged in the plane facing the Camera or
+/// along an axis with the Gizmo.
+///
+/// Triangles of the Mesh that share a vertex are all changed when it
+/// moves. The verticies are kept where the Transform of the Mesh places
+/// them and moved back onto the Mesh when they change.
+pub struct VertexEditor {
+    pub mesh: Option<usize>,
+    pub verticies: Vec<Vec3d>,
+    pub corners: Vec<Vec<(usize, usize)>>,
+    pub selected: Vec<bool>,
+    pub selection_box: Option<((f32, f32), (f32, f32))>,
+    pub drag: Option<VertexDrag>,
+}
+
+impl VertexEditor {
+    /// The color of a handle.
+    pub const HANDLE_COLOR: (f32, f32, f32) = (0.85, 0.85, 0.85);
+
+    /// The color of a selected handle.
+    pub const SELECTED_COLOR: (f32, f32, f32) = (1.0, 0.55, 0.1);
+
+    /// The width of a handle in pixels.
+    pub const HANDLE_SIZE: f32 = 6.0;
+
+    /// How far from a handle in pixels it can be clicked.
+    pub const GRAB_DISTANCE: f32 = 6.0;
+
+    /// Creates a new VertexEditor that is not editing.
+    ///
+    /// # Return
+    /// A new VertexEditor
+    ///
+    pub fn new() -> VertexEditor {
+        VertexEditor {
+            mesh: None,
+            verticies: Vec::new(),
+            corners: Vec::new(),
+            selected: Vec::new(),
+            selection_box: None,
+            drag: None,
+        }
+    }
+
+    /// Starts editing the verticies of a Mesh. Nothing is selected.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    /// * `index` - The index of the Mesh.
+    /// * `mesh` - The Mesh.
+    ///
+    pub fn begin(&mut self, index: usize, mesh: &Mesh) {
+        let (verticies, corners) = mesh.shared_verticies();
+        let verticies: Vec<Vec3d> = verticies
+            .into_iter()
+            .map(|vertex| mesh.transform.apply(vertex))
+            .collect();
+        self.mesh = Some(index);
+        self.selected = vec![false; verticies.len()];
+        self.verticies = verticies;
+        self.corners = corners;
+        self.selection_box = None;
+        self.drag = None;
+    }
+
+    /// Stops editing.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    ///
+    pub fn end(&mut self) {
+        *self = VertexEditor::new();
+    }
+
+    /// The average position of the selected verticies.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    ///
+    /// # Return
+    /// The center or None if nothing is selected
+    ///
+    pub fn selection_center(&self) -> Option<Vec3d> {
+        let mut sum = Vec3d::new(0.0, 0.0, 0.0);
+        let mut count = 0;
+        for i in 0..self.verticies.len() {
+            if self.selected[i] {
+                sum += self.verticies[i];
+                count += 1;
+            }
+        }
+        if count == 0 {
+            None
+        } else {
+            Some(sum * (1.0 / count as f32))
+        }
+    }
+
+    /// Finds the Triangles whose verticies are all selected.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    /// * `mesh` - The Mesh being edited.
+    ///
+    /// # Return
+    /// If each Triangle of the Mesh is selected
+    ///
+    pub fn selected_faces(&self, mesh: &Mesh) -> Vec<bool> {
+        let mut count = vec![0; mesh.tris.len()];
+        for i in 0..self.verticies.len() {
+            if self.selected[i] {
+                for (tri, _) in self.corners[i].iter() {
+                    count[*tri] += 1;
+                }
+            }
+        }
+        count.into_iter().map(|n| n == 3).collect()
+    }
+
+    /// Finds the handle under a pixel.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    /// * `camera` - The Camera.
+    /// * `pixel` - The position on the screen in pixels.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// The index of the closest vertex within the grab distance or None
+    ///
+    pub fn vertex_at(&self, camera: &Camera, pixel: (f32, f32), size: (f32, f32)) -> Option<usize> {
+        let r = camera.rotation_matrix();
+        let mut closest: Option<(usize, f32)> = None;
+        for i in 0..self.verticies.len() {
+            let projected = camera.project_point(self.verticies[i], size, r.clone());
+            if !(projected.z > 0.0) {
+                continue;
+            }
+            let (dx, dy) = (projected.x - pixel.0, projected.y - pixel.1);
+            let distance = f32::sqrt(dx * dx + dy * dy);
+            // the closest handle wins and of handles on top of each other the nearest one
+            let better = closest.is_none_or(|(j, d)| {
+                distance < d - 0.5
+                    || (distance < d + 0.5
+                        && projected.z > camera.project_point(self.verticies[j], size, r.clone()).z)
+            });
+            if distance <= VertexEditor::GRAB_DISTANCE && better {
+                closest = Some((i, distance));
+            }
+        }
+        closest.map(|(i, _)| i)
+    }
+
+    /// Selects the handle under the mouse and starts dragging the
+    /// selection, or starts a selection box if there is no handle.
+    /// Adding toggles the handle or keeps the selection for the box.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    /// * `camera` - The Camera.
+    /// * `pixel` - The position of the mouse in pixels.
+    /// * `size` - The dimensions of the screen.
+    /// * `add` - If the selection is added to.
+    ///
+    pub fn press(&mut self, camera: &Camera, pixel: (f32, f32), size: (f32, f32), add: bool) {
+        match self.vertex_at(camera, pixel, size) {
+            Some(i) => {
+                if add {
+                    self.selected[i] = !self.selected[i];
+                } else if !self.selected[i] {
+                    self.select_none();
+                    self.selected[i] = true;
+                }
+                if self.selected[i] {
+                    let r = camera.rotation_matrix();
+                    let depth = 1.0 / camera.project_point(self.verticies[i], size, r).z;
+                    self.drag = Some(VertexDrag {
+                        start: pixel,
+                        depth: depth,
+                        moved: Vec3d::new(0.0, 0.0, 0.0),
+                    });
+                }
+            }
+            None => {
+                if !add {
+                    self.select_none();
+                }
+                self.selection_box = Some((pixel, pixel));
+            }
+        }
+    }
+
+    /// Moves the selected verticies with the mouse or grows the
+    /// selection box.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    /// * `mesh` - The Mesh being edited.
+    /// * `camera` - The Camera.
+    /// * `pixel` - The position of the mouse in pixels.
+    /// * `size` - The dimensions of the screen.
+    ///
+    pub fn drag_to(
+        &mut self,
+        mesh: &mut Mesh,
+        camera: &Camera,
+        pixel: (f32, f32),
+        size: (f32, f32),
+    ) {
+        if let Some((start, _)) = self.selection_box {
+            self.selection_box = Some((start, pixel));
+        }
+        let mut drag = match self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        let moved = VertexEditor::point_at(camera, pixel, size, drag.depth)
+            - VertexEditor::point_at(camera, drag.start, size, drag.depth);
+        self.transform(mesh, GizmoChange::Move(moved - drag.moved));
+        drag.moved = moved;
+        self.drag = Some(drag);
+    }
+
+    /// Selects the handles in the selection box when the mouse is
+    /// released and stops dragging.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    /// * `camera` - The Camera.
+    /// * `size` - The dimensions of the screen.
+    ///
+    pub fn release(&mut self, camera: &Camera, size: (f32, f32)) {
+        if let Some((a, b)) = self.selection_box {
+            let r = camera.rotation_matrix();
+            let (min_x, max_x) = (a.0.min(b.0), a.0.max(b.0));
+            let (min_y, max_y) = (a.1.min(b.1), a.1.max(b.1));
+            for i in 0..self.verticies.len() {
+                let projected = camera.project_point(self.verticies[i], size, r.clone());
+                if projected.z > 0.0
+                    && projected.x >= min_x
+                    && projected.x <= max_x
+                    && projected.y >= min_y
+                    && projected.y <= max_y
+                {
+                    self.selected[i] = true;
+                }
+            }
+        }
+        self.selection_box = None;
+        self.drag = None;
+    }
+
+    /// Changes the selected verticies and every Triangle using them. The
+    /// vertex normals around them are smoothed again.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    /// * `mesh` - The Mesh being edited.
+    /// * `change` - The change.
+    ///
+    pub fn transform(&mut self, mesh: &mut Mesh, change: GizmoChange) {
+        let mut faces = vec![false; mesh.tris.len()];
+        for i in 0..self.verticies.len() {
+            if self.selected[i] {
+                change.apply_to_point(&mut self.verticies[i]);
+                let position = mesh.transform.invert(self.verticies[i]);
+                mesh.set_vertex(&self.corners[i], position);
+                for (tri, _) in self.corners[i].iter() {
+                    faces[*tri] = true;
+                }
+            }
+        }
+        mesh.update_vertex_normals(&faces);
+        mesh.refresh_points();
+        mesh.update_bounds();
+    }
+
+    /// Projects the handles onto the screen.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    /// * `camera` - The Camera.
+    /// * `size` - The dimensions of the screen.
+    ///
+    /// # Return
+    /// The projected center and the color of every handle in front of the Camera
+    ///
+    pub fn handles(&self, camera: &Camera, size: (f32, f32)) -> Vec<(Vec3d, (f32, f32, f32))> {
+        let r = camera.rotation_matrix();
+        let mut handles = Vec::new();
+        for i in 0..self.verticies.len() {
+            let projected = camera.project_point(self.verticies[i], size, r.clone());
+            if projected.z > 0.0 {
+                let color = if self.selected[i] {
+                    VertexEditor::SELECTED_COLOR
+                } else {
+                    VertexEditor::HANDLE_COLOR
+                };
+                handles.push((projected, color));
+            }
+        }
+        handles
+    }
+
+    /// The four sides of the selection box on the screen.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    ///
+    /// # Return
+    /// The ends of every side, empty if there is no selection box
+    ///
+    pub fn box_lines(&self) -> Vec<(Vec3d, Vec3d)> {
+        match self.selection_box {
+            Some((a, b)) => {
+                let corners = [
+                    Vec3d::new(a.0, a.1, 1.0),
+                    Vec3d::new(b.0, a.1, 1.0),
+                    Vec3d::new(b.0, b.1, 1.0),
+                    Vec3d::new(a.0, b.1, 1.0),
+                ];
+                (0..4).map(|i| (corners[i], corners[(i + 1) % 4])).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Clears the selection.
+    ///
+    /// # Arguments
+    /// * `self` - The VertexEditor the function was called for.
+    ///
+    fn select_none(&mut self) {
+        for selected in self.selected.iter_mut() {
+            *selected = false;
+        }
+    }
+
+    /// The point under a pixel at a distance in front of the Camera.
+    ///
+    /// # Arguments
+    /// * `camera` - The Camera.
+    /// * `pixel` - The position on the screen in pixels.
+    /// * `size` - The dimensions of the screen.
+    /// * `depth` - The distance in front of the Camera.
+    ///
+    /// # Return
+    /// The point
+    ///
+    fn point_at(camera: &Camera, pixel: (f32, f32), size: (f32, f32), depth: f32) -> Vec3d {
+        let (origin, direction) = camera.ray_through(pixel, size);
+        let forward = camera.rotation_matrix().transpose() * Vec3d::new(0.0, 0.0, 1.0);
+        origin + direction * (depth / direction.dot(forward))
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use crate::{mesh::Mesh, vec3d::Vec3d};
+
+/// A Weld is the connectivity of a Mesh after verticies closer than an
+/// epsilon were merged. Every corner of a Triangle points to a welded
+/// position.
+///
+/// The positions are found with a spatial hash of cells as wide as the
+/// epsilon. A vertex joins the closest position within the epsilon in
+/// its own or a neighbouring cell. Positions do not move when verticies
+/// join them so merging does not chain along a row of close verticies.
+pub struct Weld {
+    pub positions: Vec<Vec3d>,
+    pub faces: Vec<[usize; 3]>,
+    pub merged: usize,
+}
+
+impl Weld {
+    /// How close verticies have to be to be merged when no epsilon is given.
+    pub const DEFAULT_EPSILON: f32 = 0.00001;
+
+    /// Welds the verticies of a Mesh.
+    ///
+    /// # Arguments
+    /// * `mesh` - The Mesh.
+    /// * `epsilon` - How close verticies have to be to be merged.
+    ///
+    /// # Return
+    /// A new Weld
+    ///
+    pub fn new(mesh: &Mesh, epsilon: f32) -> Weld {
+        let epsilon = epsilon.max(f32::EPSILON);
+        let mut weld = Weld {
+            positions: Vec::new(),
+            faces: Vec::new(),
+            merged: 0,
+        };
+
+        let mut cells: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+        for tri in mesh.tris.iter() {
+            let v = [tri.verticies.0, tri.verticies.1, tri.verticies.2];
+            let mut face = [0; 3];
+            for k in 0..3 {
+                face[k] = weld.position(&mut cells, v[k], epsilon);
+            }
+            weld.faces.push(face);
+        }
+        weld.merged = mesh.tris.len() * 3 - weld.positions.len();
+        weld
+    }
+
+    /// Finds the welded position of a vertex or adds a new one.
+    ///
+    /// # Arguments
+    /// * `self` - The Weld the function was called for.
+    /// * `cells` - The positions in each cell of the spatial hash.
+    /// * `vertex` - The vertex.
+    /// * `epsilon` - How close verticies have to be to be merged.
+    ///
+    /// # Return
+    /// The index of the position
+    ///
+    fn position(
+        &mut self,
+        cells: &mut HashMap<(i64, i64, i64), Vec<usize>>,
+        vertex: Vec3d,
+        epsilon: f32,
+    ) -> usize {
+        let cell = |x: f32| (x / epsilon).floor() as i64;
+        let (x, y, z) = (cell(vertex.x), cell(vertex.y), cell(vertex.z));
+        let mut closest: Option<(usize, f32)> = None;
+        for dx in -1..2 {
+            for dy in -1..2 {
+                for dz in -1..2 {
+                    let positions = match cells.get(&(x + dx, y + dy, z + dz)) {
+                        Some(positions) => positions,
+                        None => continue,
+                    };
+                    for j in positions.iter() {
+                        let distance = (self.positions[*j] - vertex).length();
+                        if distance <= epsilon && closest.is_none_or(|(_, d)| distance < d) {
+                            closest = Some((*j, distance));
+                        }
+                    }
+                }
+            }
+        }
+        match closest {
+            Some((j, _)) => j,
+            None => {
+                self.positions.push(vertex);
+                let j = self.positions.len() - 1;
+                cells.entry((x, y, z)).or_default().push(j);
+                j
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mesh::Mesh, vec3d::Vec3d, weld::Weld};
+
+    #[test]
+    fn cube_welds_to_its_corners() {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        let weld = mesh.weld(Weld::DEFAULT_EPSILON);
+        assert_eq!(weld.positions.len(), 8);
+        assert_eq!(weld.merged, 12 * 3 - 8);
+        assert_eq!(weld.faces.len(), 12);
+    }
+
+    #[test]
+    fn close_verticies_are_merged() {
+        let mut mesh = Mesh::new(Vec3d::new(0.0, 0.0, 0.0));
+        mesh.form_cube();
+        let (a, b, c) = mesh.tris[0].verticies;
+        mesh.tris[0].verticies = (a + Vec3d::new(0.0005, 0.0, 0.0), b, c);
+        assert_eq!(Weld::new(&mesh, Weld::DEFAULT_EPSILON).positions.len(), 9);
+
+        let weld = mesh.weld(0.001);
+        assert_eq!(weld.positions.len(), 8);
+        // the Triangles now share the corner
+        let moved = mesh.tris[0].verticies.0;
+        assert!(mesh.tris[1..]
+            .iter()
+            .any(|tri| (tri.verticies.0 - moved).length() == 0.0));
+        assert!(mesh.validate().is_watertight());
+    }
+}